@@ -6,6 +6,13 @@ use std::io::Read;
 use std::path::Path;
 use ureq::get;
 
+use crate::backend::transformations::{OnMissing, OneToOne};
+
+/// The code list with the file-type Concepts of images.
+const FILE_TYPE_CODE_LIST: &str = "json/codelists/file_type.csv";
+/// The code list with the language Concepts.
+const LANGUAGE_CODE_LIST: &str = "json/codelists/language.csv";
+
 /// Decode json input from Base64, and returns the byte array.
 ///
 /// # Arguments
@@ -62,7 +69,7 @@ fn encode_image_from_url(url: &str) -> Result<String, Box<dyn Error>> {
     }
 }
 
-/// Creates contentType object based on input type in string
+/// Creates contentType object based on input type in string, looked up in the file-type code list
 ///
 /// # Arguments
 /// - `content_type`: The a type that is than rewritten into a serde Value object.
@@ -71,48 +78,11 @@ fn encode_image_from_url(url: &str) -> Result<String, Box<dyn Error>> {
 /// - `Ok(Value)`: The content value Object in ELM format if successful.
 /// - `Err(Box<dyn Error>)`: An error if the fetch or encoding fails.
 fn set_content_type(content_type: &str) -> Result<Value, Box<dyn Error>> {
-    let content_json = r#"
-    {
-        "id": "http://publications.europa.eu/resource/authority/file-type/PNG",
-        "type": "Concept",
-        "inScheme": {
-            "id": "http://publications.europa.eu/resource/authority/file-type",
-            "type": "ConceptScheme"
-        },
-        "prefLabel": {
-            "en": ["PNG"]
-        },
-        "notation": "file-type"
-    }
-    "#;
-
-    let mut parsed_content_type_json: Value = serde_json::from_str(content_json).unwrap();
-
-    match content_type {
-        "PNG" => {
-            parsed_content_type_json["id"] =
-                Value::String("http://publications.europa.eu/resource/authority/file-type/PNG".to_string());
-            parsed_content_type_json["prefLabel"]["en"][0] = Value::String("PNG".to_string());
-        }
-        "JPG" | "JPEG" => {
-            parsed_content_type_json["id"] =
-                Value::String("http://publications.europa.eu/resource/authority/file-type/JPEG".to_string());
-            parsed_content_type_json["prefLabel"]["en"][0] = Value::String("JPG".to_string());
-        }
-        "SVG" => {
-            parsed_content_type_json["id"] =
-                Value::String("http://publications.europa.eu/resource/authority/file-type/SVG".to_string());
-            parsed_content_type_json["prefLabel"]["en"][0] = Value::String("SVG".to_string());
-        }
-        _ => {
-            // Handle other cases
-            return Err(Box::new(std::io::Error::new(
-                std::io::ErrorKind::InvalidInput,
-                "This file Type is not exporteswent wrong!",
-            )));
-        }
-    }
-    Ok(parsed_content_type_json)
+    let lookup = OneToOne::lookup {
+        file: FILE_TYPE_CODE_LIST.to_string(),
+        on_missing: OnMissing::error,
+    };
+    Ok(lookup.apply(Value::String(content_type.to_string()))?)
 }
 
 /// Creates contentEncodingType object based on input type in string
@@ -157,7 +127,7 @@ fn set_content_enconding_type(content_encoding_type: &str) -> Result<Value, Box<
     Ok(parsed_content_encoding_type_json)
 }
 
-/// Creates language object based on input type in string
+/// Creates language object based on input type in string, looked up in the language code list
 ///
 /// # Arguments
 /// - `content_encoding_type`: The a type that is than rewritten into a serde Value object.
@@ -166,43 +136,11 @@ fn set_content_enconding_type(content_encoding_type: &str) -> Result<Value, Box<
 /// - `Ok(Value)`: The content value Object in ELM format if successful.
 /// - `Err(Box<dyn Error>)`: An error if the fetch or encoding fails.
 fn set_language(language: &str) -> Result<Value, Box<dyn Error>> {
-    let template_json = r#"
-  {
-    "id": "http://publications.europa.eu/resource/authority/language/ENG",
-    "type": "Concept",
-    "inScheme": {
-      "id": "http://publications.europa.eu/resource/authority/language",
-      "type": "ConceptScheme"
-    },
-    "prefLabel": {
-      "en": ["English"]
-    },
-    "notation": "language"
-  }
-  "#;
-
-    let mut parsed_language_json: Value = serde_json::from_str(template_json).unwrap();
-
-    match language {
-        "ENG" => {
-            parsed_language_json["id"] =
-                Value::String("http://publications.europa.eu/resource/authority/language/ENG".to_string());
-            parsed_language_json["prefLabel"]["en"][0] = Value::String("English".to_string());
-        }
-        "NLD" => {
-            parsed_language_json["id"] =
-                Value::String("http://publications.europa.eu/resource/authority/language/NLD".to_string());
-            parsed_language_json["prefLabel"]["en"][0] = Value::String("dutch".to_string());
-        }
-        _ => {
-            // Handle other cases
-            return Err(Box::new(std::io::Error::new(
-                std::io::ErrorKind::InvalidInput,
-                "This language Type is not supported!",
-            )));
-        }
-    }
-    Ok(parsed_language_json)
+    let lookup = OneToOne::lookup {
+        file: LANGUAGE_CODE_LIST.to_string(),
+        on_missing: OnMissing::error,
+    };
+    Ok(lookup.apply(Value::String(language.to_string()))?)
 }
 
 pub fn image_to_elm_media_object(image_value: Value) -> Result<Value, &'static str> {
@@ -211,7 +149,7 @@ pub fn image_to_elm_media_object(image_value: Value) -> Result<Value, &'static s
     //we need to achieve the following structure into the indivudualDisplay array:
     let json_data = r#"
     {
-        "id": "urn:epass:mediaObject:https://avatars.githubusercontent.com/u/22613412?v=4",
+        "id": "urn:epass:mediaObject:generated",
         "type": "MediaObject",
         "content": "bas64content",
         "contentEncoding": {
@@ -365,7 +303,7 @@ pub fn image_to_individual_display(image_value: Value) -> Result<Value, &'static
     //we need to achieve the following structure into the indivudualDisplay array:
     let json_data = r#"
     {
-        "id": "urn:epass:individualDisplay:c05743e7-9f9d-4e0b-899b-7ae6514c7a02",
+        "id": "urn:epass:individualDisplay:generated",
         "type": "IndividualDisplay",
         "language": {
           "id": "http://publications.europa.eu/resource/authority/language/ENG",
@@ -381,7 +319,7 @@ pub fn image_to_individual_display(image_value: Value) -> Result<Value, &'static
         },
         "displayDetail": [
           {
-            "id": "urn:epass:displayDetail:123",
+            "id": "urn:epass:displayDetail:generated",
             "type": "DisplayDetail",
             "image": {"object": "data"},
             "page": 1
@@ -442,7 +380,7 @@ pub fn create_display_parameter(image_value: Value) -> Result<Value, &'static st
     //we need to achieve the following structure into the indivudualDisplay array:
     let json_data = r#"
   {
-    "id": "urn:epass:displayParameter:1",
+    "id": "urn:epass:displayParameter:generated",
     "type": "DisplayParameter",
     "language": [
       {
@@ -458,11 +396,6 @@ pub fn create_display_parameter(image_value: Value) -> Result<Value, &'static st
         "notation": "language"
       }
     ],
-    "description": {
-      "en": [
-        "EBSI Example https://github.com/Knowledge-Innovation-Centre/ESBI-JSON-schemas/blob/main/examples%20of%20credentials/DigiComp%20Generic.json"
-      ]
-    },
     "individualDisplay": [],
     "primaryLanguage": {
       "id": "http://publications.europa.eu/resource/authority/language/ENG",
@@ -475,9 +408,6 @@ pub fn create_display_parameter(image_value: Value) -> Result<Value, &'static st
         "en": ["English"]
       },
       "notation": "language"
-    },
-    "title": {
-      "en": ["DigiComp Generic"]
     }
   }  "#;
 
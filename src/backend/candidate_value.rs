@@ -1,103 +1,189 @@
 use regex::Regex;
+use serde_json::{Map, Value};
 
 use crate::{
     backend::{
-        jsonpointer::{JsonPath, JsonPointer},
-        transformations::{DataLocation, OneToOne, StringToOne, StringValue, Transformation},
+        date_time::DateTimeFormat,
+        jsonpointer::{resolve_pointer, JsonPath, JsonPointer},
+        transformations::{
+            DataLocation, EntryOptions, ManyToOne, OneToMany, OneToOne, StringToOne, StringValue, Transformation,
+            FORMAT_PLACEHOLDER,
+        },
     },
-    state::{AppState, Pages, Transformations},
+    state::{AppState, MappingOptions, Pages, Transformations},
     trace_dbg,
 };
 
 pub fn set_candidate_output_value(state: &mut AppState, push_transformation: bool) {
-    // todo: is it needed to add directcopy to the start?
-    let selected_transformations = [
-        vec![Transformations::DirectCopy],
-        state.selected_transformations.clone(),
-    ]
-    .concat();
-
-    trace_dbg!(&state.selected_transformations);
-
-    for transformation in selected_transformations.iter() {
-        let transformation = define_transformation(state, *transformation);
+    if state.mapping_option == MappingOptions::OneToMany && !state.select_mapping_option {
+        set_split_candidate_output_value(state, push_transformation);
+        return;
+    }
+    if state.mapping_option == MappingOptions::ManyToOne && !state.select_mapping_option {
+        let transformation = define_join_transformation(state);
 
-        // todo: Can we we just get the key pointed to instead of copynig the entire repository?
         let mut temp_repository = state.repository.clone();
         let _ = temp_repository.apply_transformation(transformation.clone(), state.mapping);
 
-        let candidate_output_value = temp_repository
+        state.candidate_output_value = temp_repository
             .get(&state.mapping.output_format())
-            .unwrap()
-            .pointer(&state.output_pointer)
-            .unwrap();
-
-        state.candidate_output_value = candidate_output_value.to_string();
+            .and_then(|output| resolve_pointer(output, &state.output_pointer))
+            .unwrap_or_default()
+            .to_string();
 
-        // if the candidate_output_value is only loaded for display in the popup, we don't want to push the transformation just yet.
         if push_transformation {
             state.performed_mappings.push(transformation);
         }
+        return;
     }
-}
 
-pub fn define_transformation(state: &mut AppState, transformation: Transformations) -> Transformation {
-    let (input_format, output_format) = (state.mapping.input_format(), state.mapping.output_format());
-    let source_pointer: JsonPath = JsonPointer(state.input_fields[state.selected_input_field].0.clone()).into();
-    let input_value: String = state.input_fields[state.selected_input_field].0.clone();
-    let destination_path: JsonPath = JsonPointer(state.output_pointer.clone()).into();
+    trace_dbg!(&state.selected_transformations);
 
-    match transformation {
-        Transformations::LowerCase => Transformation::OneToOne {
-            type_: OneToOne::toLowerCase,
+    let transformation = define_pipeline_transformation(state);
+
+    // todo: Can we we just get the key pointed to instead of copynig the entire repository?
+    let mut temp_repository = state.repository.clone();
+    let _ = temp_repository.apply_transformation(transformation.clone(), state.mapping);
+
+    let candidate_output_value = temp_repository
+        .get(&state.mapping.output_format())
+        .and_then(|output| resolve_pointer(output, &state.output_pointer))
+        .unwrap_or_default();
+
+    state.candidate_output_value = candidate_output_value.to_string();
+
+    // if the candidate_output_value is only loaded for display in the popup, we don't want to push the transformation just yet.
+    if push_transformation {
+        state.performed_mappings.push(transformation);
+    }
+}
+
+/// Chain the selected transformations into a single pipeline, so each transformation is applied to the output of
+/// the previous one. Without selected transformations the input field is copied as is.
+pub fn define_pipeline_transformation(state: &mut AppState) -> Transformation {
+    match state.selected_transformations.clone().as_slice() {
+        [] => define_transformation(state, Transformations::DirectCopy),
+        [transformation] => define_transformation(state, *transformation),
+        transformations => Transformation::Pipeline {
+            pipeline: transformations
+                .iter()
+                .map(|transformation| define_step(state, *transformation))
+                .collect(),
             source: DataLocation {
-                format: input_format.clone(),
-                path: source_pointer.to_string(),
+                format: state.mapping.input_format(),
+                path: JsonPath::from(JsonPointer(state.input_fields[state.selected_input_field].0.clone())).to_string(),
             },
             destination: DataLocation {
-                format: output_format.clone(),
-                path: destination_path.to_string(),
+                format: state.mapping.output_format(),
+                path: JsonPath::from(JsonPointer(state.output_pointer.clone())).to_string(),
             },
+            options: EntryOptions::default(),
         },
-        Transformations::UpperCase => Transformation::OneToOne {
-            type_: OneToOne::toUpperCase,
-            source: DataLocation {
-                format: input_format.clone(),
-                path: source_pointer.to_string(),
-            },
-            destination: DataLocation {
-                format: output_format.clone(),
-                path: destination_path.to_string(),
-            },
+    }
+}
+
+/// Show the values each selected OneToMany destination will receive, as an object of output pointers and values.
+fn set_split_candidate_output_value(state: &mut AppState, push_transformation: bool) {
+    let transformation = define_split_transformation(state);
+
+    let mut temp_repository = state.repository.clone();
+    let _ = temp_repository.apply_transformation(transformation.clone(), state.mapping);
+
+    let mut candidate_output_value = Map::new();
+    if let Some(output) = temp_repository.get(&state.mapping.output_format()) {
+        for (_, output_pointer) in &state.split_destinations {
+            candidate_output_value.insert(
+                output_pointer.clone(),
+                resolve_pointer(output, output_pointer).unwrap_or_default(),
+            );
+        }
+    }
+
+    state.candidate_output_value = Value::Object(candidate_output_value).to_string();
+
+    if push_transformation {
+        state.performed_mappings.push(transformation);
+    }
+}
+
+/// Split the selected input field over the selected output fields, on the entered dividers.
+/// Dividers enclosed in slashes, e.g. `/\s*-\s*/`, are used as a regular expression.
+pub fn define_split_transformation(state: &AppState) -> Transformation {
+    let (input_format, output_format) = (state.mapping.input_format(), state.mapping.output_format());
+    let source_pointer: JsonPath = JsonPointer(state.input_fields[state.selected_input_field].0.clone()).into();
+
+    let type_ = match state
+        .dividers
+        .strip_prefix('/')
+        .and_then(|dividers| dividers.strip_suffix('/'))
+    {
+        Some(pattern) if !pattern.is_empty() => OneToMany::regexSplit {
+            pattern: pattern.to_string(),
         },
-        Transformations::TakeIndex => Transformation::OneToOne {
-            type_: OneToOne::takeIndex {
-                index: state.transformation_index.unwrap(),
-            },
-            source: DataLocation {
-                format: input_format.clone(),
-                path: source_pointer.to_string(),
-            },
-            destination: DataLocation {
-                format: output_format.clone(),
-                path: destination_path.to_string(),
-            },
+        _ => OneToMany::split {
+            dividers: state.dividers.clone(),
         },
-        Transformations::Slice => Transformation::OneToOne {
-            type_: OneToOne::slice {
-                index: state.transformation_index.unwrap(),
-            },
-            source: DataLocation {
-                format: input_format.clone(),
-                path: source_pointer.to_string(),
-            },
-            destination: DataLocation {
+    };
+
+    Transformation::OneToMany {
+        type_,
+        source: DataLocation {
+            format: input_format,
+            path: source_pointer.to_string(),
+        },
+        destinations: state
+            .split_destinations
+            .iter()
+            .map(|(_, output_pointer)| DataLocation {
                 format: output_format.clone(),
-                path: destination_path.to_string(),
-            },
+                path: JsonPath::from(JsonPointer(output_pointer.clone())).to_string(),
+            })
+            .collect(),
+        options: EntryOptions::default(),
+    }
+}
+
+/// Join the selected input fields into the selected output field.
+/// When the entered text contains placeholders like `{0}` it's used as a template, otherwise as the separator.
+pub fn define_join_transformation(state: &AppState) -> Transformation {
+    let (input_format, output_format) = (state.mapping.input_format(), state.mapping.output_format());
+    let destination_path: JsonPath = JsonPointer(state.output_pointer.clone()).into();
+
+    let type_ = if FORMAT_PLACEHOLDER.is_match(&state.dividers) {
+        ManyToOne::format {
+            template: state.dividers.clone(),
+        }
+    } else {
+        ManyToOne::join {
+            separator: state.dividers.clone(),
+        }
+    };
+
+    Transformation::ManyToOne {
+        type_,
+        sources: state
+            .join_sources
+            .iter()
+            .map(|input_pointer| DataLocation {
+                format: input_format.clone(),
+                path: JsonPath::from(JsonPointer(input_pointer.clone())).to_string(),
+            })
+            .collect(),
+        destination: DataLocation {
+            format: output_format,
+            path: destination_path.to_string(),
         },
-        // todo: This clippy warning is known, this body is for 'DirectCopy' and all others until they
-        // get their own branches
+        options: EntryOptions::default(),
+    }
+}
+
+pub fn define_transformation(state: &mut AppState, transformation: Transformations) -> Transformation {
+    let (input_format, output_format) = (state.mapping.input_format(), state.mapping.output_format());
+    let source_pointer: JsonPath = JsonPointer(state.input_fields[state.selected_input_field].0.clone()).into();
+    let input_value: String = state.input_fields[state.selected_input_field].0.clone();
+    let destination_path: JsonPath = JsonPointer(state.output_pointer.clone()).into();
+
+    match transformation {
         Transformations::StringToOne => Transformation::StringToOne {
             type_: StringToOne::stringit,
             source: StringValue {
@@ -107,9 +193,10 @@ pub fn define_transformation(state: &mut AppState, transformation: Transformatio
                 format: output_format.clone(),
                 path: destination_path.to_string(),
             },
+            options: EntryOptions::default(),
         },
-        Transformations::DirectCopy | _ => Transformation::OneToOne {
-            type_: OneToOne::copy,
+        _ => Transformation::OneToOne {
+            type_: define_step(state, transformation),
             source: DataLocation {
                 format: input_format.clone(),
                 path: source_pointer.to_string(),
@@ -118,10 +205,52 @@ pub fn define_transformation(state: &mut AppState, transformation: Transformatio
                 format: output_format.clone(),
                 path: destination_path.to_string(),
             },
+            options: EntryOptions::default(),
         },
     }
 }
 
+/// The OneToOne transformation of a transformation selected in the transformations bar.
+fn define_step(state: &AppState, transformation: Transformations) -> OneToOne {
+    match transformation {
+        Transformations::LowerCase => OneToOne::toLowerCase,
+        Transformations::UpperCase => OneToOne::toUpperCase,
+        Transformations::TakeIndex => OneToOne::takeIndex {
+            index: state.transformation_index.unwrap(),
+        },
+        Transformations::Slice => OneToOne::slice {
+            index: state.transformation_index.unwrap(),
+        },
+        Transformations::Regex => parse_regex_input(&state.regex).unwrap_or(OneToOne::copy),
+        Transformations::DateTime => OneToOne::dateTime {
+            output: DateTimeFormat::DateTime,
+        },
+        Transformations::DirectCopy | Transformations::StringToOne => OneToOne::copy,
+    }
+}
+
+/// Parse the regex entered in the transformations bar, `pattern => replacement` replaces all matches,
+/// a single pattern extracts its first capture group, or the whole match when it has no groups.
+/// Returns `None` when the pattern is empty or invalid.
+pub fn parse_regex_input(input: &str) -> Option<OneToOne> {
+    let (pattern, replacement) = match input.split_once(" => ") {
+        Some((pattern, replacement)) => (pattern, Some(replacement)),
+        None => (input, None),
+    };
+    let regex = Regex::new(pattern).ok().filter(|_| !pattern.is_empty())?;
+
+    Some(match replacement {
+        Some(replacement) => OneToOne::regexReplace {
+            pattern: pattern.to_string(),
+            replacement: replacement.to_string(),
+        },
+        None => OneToOne::regexExtract {
+            pattern: pattern.to_string(),
+            group: if regex.captures_len() > 1 { 1 } else { 0 },
+        },
+    })
+}
+
 pub fn set_output_pointer(state: &mut AppState) {
     let mut output_pointer = state.required_field_pointer.trim_start_matches("/required").to_string();
     if state.page == Pages::OptionalDataP3 {
@@ -149,6 +278,10 @@ pub fn get_clean_pointer(mut pointer: String) -> String {
         let re_not = Regex::new(r"not/.*/").unwrap();
         pointer = re_not.replace_all(&pointer, "").to_string();
     }
+    // The fields inside the `items` of an array are mapped element-wise, using the wildcard `*` segment
+    if pointer.contains("/items/") {
+        pointer = pointer.replace("/items/", "/*/");
+    }
 
     pointer
 }
@@ -0,0 +1,242 @@
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+use crate::{
+    backend::{
+        convert::{convert_embedded, load_mapping},
+        error::{ConversionError, TransformationError},
+        formats::FormatRegistry,
+        transformations::Transformation,
+    },
+    state::Mapping,
+};
+
+/// The `associationType` of a CLR association from an achievement to one of its parts.
+const PARENT_OF: &str = "isParentOf";
+/// The `associationType` of a CLR association from a part to the achievement it's part of.
+const CHILD_OF: &str = "isChildOf";
+
+/// Convert every claim of an ELM credential, including the claims in the `hasPart` of other claims, into an OBv3
+/// achievement credential for the `verifiableCredential` of a CLR 2.0.
+/// Each claim is converted with the ELM -> OBv3 mapping as the ELM credential holding only that claim, so the issuer,
+/// subject and dates of the credential carry over to each achievement credential.
+///
+/// # Arguments
+/// - `credential`: The ELM credential.
+/// - `parameters`: A `mapping` replaces the default ELM -> OBv3 mapping.
+/// - `registry`: The registry of the formats of the CLR conversion, which holds ELM and OBv3.
+///
+/// # Returns
+/// - `Ok(Value)`: The achievement credentials, parts following the claim they're part of.
+/// - `Err(TransformationError)`: If the mapping can't be loaded or applied to a claim.
+pub fn claims_to_achievement_credentials(
+    credential: Value,
+    parameters: &Value,
+    registry: &'static FormatRegistry,
+) -> Result<Value, TransformationError> {
+    let (conversion, mapping) = embedded_mapping(registry, "ELM", "OBv3", parameters)?;
+
+    let mut claims = Vec::new();
+    collect_claims(credential.pointer("/credentialSubject/hasClaim"), &mut claims);
+
+    let mut single_claim_credential = credential.clone();
+    // The proof of the credential doesn't hold for a single claim of it
+    if let Some(fields) = single_claim_credential.as_object_mut() {
+        fields.remove("proof");
+    }
+
+    let mut achievement_credentials = Vec::new();
+    for claim in claims {
+        let id = achievement_credential_id(&claim);
+        single_claim_credential["credentialSubject"]["hasClaim"] = json!([claim]);
+        let mut achievement_credential = convert_embedded(&single_claim_credential, conversion, &mapping)
+            .map_err(|error| TransformationError::Embedded(Box::new(error)))?;
+        // Otherwise every achievement credential has the id of the ELM credential
+        achievement_credential["id"] = Value::String(id);
+        achievement_credentials.push(achievement_credential);
+    }
+
+    Ok(Value::Array(achievement_credentials))
+}
+
+/// The `urn:uuid:` id of the achievement credential converted from an ELM claim, derived from the id of the claim so
+/// converting the same credential again gives the same ids. A claim without id is identified by its content instead.
+fn achievement_credential_id(claim: &Value) -> String {
+    let name = match claim.get("id").and_then(Value::as_str) {
+        Some(id) => id.to_string(),
+        None => claim.to_string(),
+    };
+    format!("urn:uuid:{}", Uuid::new_v5(&Uuid::NAMESPACE_URL, name.as_bytes()))
+}
+
+/// Create a CLR `isParentOf` association for every part in the `hasPart` of the ELM claims, nested parts included.
+/// The associations refer to the claims by their id, which the ELM -> OBv3 mapping gives the achievement of the
+/// achievement credential converted from the claim.
+pub fn has_part_to_associations(claims: Value) -> Value {
+    let mut associations = Vec::new();
+    collect_associations(&claims, &mut associations);
+
+    Value::Array(associations)
+}
+
+/// Convert the achievement credentials in the `verifiableCredential` of a CLR 2.0 subject into ELM claims, the
+/// reverse of `claims_to_achievement_credentials`. The claims are nested in the `hasPart` of the claim they're part
+/// of following the `isParentOf` and `isChildOf` associations of the subject.
+///
+/// # Arguments
+/// - `subject`: The `credentialSubject` of the CLR.
+/// - `parameters`: A `mapping` replaces the default OBv3 -> ELM mapping.
+/// - `registry`: The registry of the formats of the CLR conversion, which holds ELM and OBv3.
+///
+/// # Returns
+/// - `Ok(Value)`: The claims that aren't part of another claim.
+/// - `Err(TransformationError)`: If the mapping can't be loaded or applied to an achievement credential.
+pub fn achievement_credentials_to_claims(
+    subject: Value,
+    parameters: &Value,
+    registry: &'static FormatRegistry,
+) -> Result<Value, TransformationError> {
+    let (conversion, mapping) = embedded_mapping(registry, "OBv3", "ELM", parameters)?;
+
+    // The claims with the id of the achievement they were converted from, in the order of the credentials.
+    // Credentials embedded as a JWT string aren't converted.
+    let mut claims: Vec<(Option<String>, Value)> = Vec::new();
+    for achievement_credential in elements(subject.get("verifiableCredential")).filter(|vc| vc.is_object()) {
+        let credential = convert_embedded(achievement_credential, conversion, &mapping)
+            .map_err(|error| TransformationError::Embedded(Box::new(error)))?;
+        let achievement_id = achievement_credential
+            .pointer("/credentialSubject/achievement/id")
+            .and_then(Value::as_str);
+        for claim in elements(credential.pointer("/credentialSubject/hasClaim")) {
+            claims.push((achievement_id.map(str::to_string), claim.clone()));
+        }
+    }
+
+    let mut parts: HashMap<String, Vec<String>> = HashMap::new();
+    for association in elements(subject.get("association")) {
+        let id = |field: &str| association.get(field).and_then(Value::as_str).map(str::to_string);
+        let (Some(source_id), Some(target_id)) = (id("sourceId"), id("targetId")) else {
+            continue;
+        };
+        match association.get("associationType").and_then(Value::as_str) {
+            Some(PARENT_OF) => parts.entry(source_id).or_default().push(target_id),
+            Some(CHILD_OF) => parts.entry(target_id).or_default().push(source_id),
+            _ => {}
+        }
+    }
+
+    let by_id: HashMap<&str, &Value> = claims
+        .iter()
+        .rev()
+        .filter_map(|(id, claim)| Some((id.as_deref()?, claim)))
+        .collect();
+    let part_ids: HashSet<&str> = parts.values().flatten().map(String::as_str).collect();
+
+    let mut placed = HashSet::new();
+    let mut top_claims = Vec::new();
+    for (id, claim) in &claims {
+        match id.as_deref() {
+            None => top_claims.push(claim.clone()),
+            Some(id) if !part_ids.contains(id) && placed.insert(id.to_string()) => {
+                top_claims.push(with_parts(claim, id, &by_id, &parts, &mut placed))
+            }
+            _ => {}
+        }
+    }
+    // Parts whose associations form a cycle have no claim on top, they're kept as claims of their own
+    for (id, claim) in &claims {
+        if let Some(id) = id.as_deref().filter(|id| placed.insert(id.to_string())) {
+            top_claims.push(with_parts(claim, id, &by_id, &parts, &mut placed));
+        }
+    }
+
+    Ok(Value::Array(top_claims))
+}
+
+/// The conversion and mapping of the credentials embedded in a CLR, the default mapping between the formats unless
+/// the `mapping` parameter of the mapping entry names another mapping file.
+fn embedded_mapping(
+    registry: &'static FormatRegistry,
+    from: &str,
+    into: &str,
+    parameters: &Value,
+) -> Result<(Mapping, Vec<Transformation>), TransformationError> {
+    let embedded_error = |error: ConversionError| TransformationError::Embedded(Box::new(error));
+
+    let conversion = registry.mapping(from, into).map_err(embedded_error)?;
+    let mapping_path = match parameters.get("mapping").and_then(Value::as_str) {
+        Some(mapping_path) => mapping_path,
+        None => conversion.default_mapping().ok_or_else(|| {
+            embedded_error(ConversionError::InvalidMapping(format!(
+                "there is no mapping from {} into {}",
+                from, into
+            )))
+        })?,
+    };
+
+    let mapping = load_mapping(mapping_path, conversion).map_err(embedded_error)?;
+
+    Ok((conversion, mapping))
+}
+
+/// The elements of an array, or the value itself for the fields ELM allows to hold a single value instead.
+fn elements(value: Option<&Value>) -> Box<dyn Iterator<Item = &Value> + '_> {
+    match value {
+        Some(Value::Array(elements)) => Box::new(elements.iter()),
+        Some(Value::Null) | None => Box::new(std::iter::empty()),
+        Some(value) => Box::new(std::iter::once(value)),
+    }
+}
+
+/// Collect the claims depth first, each claim followed by its parts.
+fn collect_claims(claims: Option<&Value>, collected: &mut Vec<Value>) {
+    for claim in elements(claims) {
+        collected.push(claim.clone());
+        collect_claims(claim.get("hasPart"), collected);
+    }
+}
+
+fn collect_associations(claims: &Value, associations: &mut Vec<Value>) {
+    for claim in elements(Some(claims)) {
+        let Some(parts) = claim.get("hasPart") else {
+            continue;
+        };
+        if let Some(source_id) = claim.get("id").and_then(Value::as_str) {
+            for target_id in elements(Some(parts)).filter_map(|part| part.get("id").and_then(Value::as_str)) {
+                associations.push(json!({
+                    "type": "Association",
+                    "associationType": PARENT_OF,
+                    "sourceId": source_id,
+                    "targetId": target_id,
+                }));
+            }
+        }
+        collect_associations(parts, associations);
+    }
+}
+
+/// The claim with the claims of its parts in its `hasPart`, leaving out the parts already placed elsewhere.
+fn with_parts(
+    claim: &Value,
+    id: &str,
+    by_id: &HashMap<&str, &Value>,
+    parts: &HashMap<String, Vec<String>>,
+    placed: &mut HashSet<String>,
+) -> Value {
+    let mut claim = claim.clone();
+
+    let mut has_part = Vec::new();
+    for part_id in parts.get(id).into_iter().flatten() {
+        if let Some(part) = by_id.get(part_id.as_str()) {
+            if placed.insert(part_id.clone()) {
+                has_part.push(with_parts(part, part_id, by_id, parts, placed));
+            }
+        }
+    }
+    if let Some(fields) = claim.as_object_mut().filter(|_| !has_part.is_empty()) {
+        fields.insert("hasPart".to_string(), Value::Array(has_part));
+    }
+
+    claim
+}
@@ -0,0 +1,195 @@
+use csv::ReaderBuilder;
+use lazy_static::lazy_static;
+use serde_json::{Map, Value};
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::BufReader,
+    path::Path,
+    sync::{Arc, RwLock},
+    time::SystemTime,
+};
+
+use crate::backend::error::TransformationError;
+
+/// The target values of a code list by their source value.
+type CodeList = HashMap<String, Value>;
+
+lazy_static! {
+    /// The code lists read so far by their path, with the time the file was modified when it was read, so a lookup
+    /// of every element of an array reads its code list once.
+    static ref CODE_LISTS: RwLock<HashMap<String, (SystemTime, Arc<CodeList>)>> = RwLock::new(HashMap::new());
+}
+
+/// Load a code list mapping source values to target values.
+/// A code list file is read again when it was modified since it was last loaded.
+///
+/// A `.csv` file has a header row and one row per source value in its first column. With two columns the target is
+/// the string in the second column, with more columns it is an object with a field per column, named by its header.
+/// Dots in a header nest the field, e.g. `inScheme.id`, and a header ending in `[]` wraps the value in an array,
+/// e.g. `prefLabel.en[]`. Empty cells are left out.
+/// Any other file is read as a json object with the source values as keys and the target values as values.
+pub fn load_code_list(path: &str) -> Result<Arc<CodeList>, TransformationError> {
+    let modified = std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .map_err(|error| TransformationError::InvalidCodeList {
+            path: path.to_string(),
+            message: error.to_string(),
+        })?;
+
+    if let Some((_, code_list)) = CODE_LISTS
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .get(path)
+        .filter(|(read_at, _)| *read_at == modified)
+    {
+        return Ok(code_list.clone());
+    }
+
+    let code_list = Arc::new(read_code_list(path)?);
+    CODE_LISTS
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .insert(path.to_string(), (modified, code_list.clone()));
+
+    Ok(code_list)
+}
+
+fn read_code_list(path: &str) -> Result<CodeList, TransformationError> {
+    let invalid = |message: String| TransformationError::InvalidCodeList {
+        path: path.to_string(),
+        message,
+    };
+
+    let is_csv = Path::new(path)
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("csv"));
+    if !is_csv {
+        let file = File::open(path).map_err(|error| invalid(error.to_string()))?;
+        return serde_json::from_reader(BufReader::new(file)).map_err(|error| invalid(error.to_string()));
+    }
+
+    let mut reader = ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_path(path)
+        .map_err(|error| invalid(error.to_string()))?;
+    let headers = reader.headers().map_err(|error| invalid(error.to_string()))?.clone();
+    if headers.len() < 2 {
+        return Err(invalid("a code list needs a source and a target column".to_string()));
+    }
+
+    let mut code_list = HashMap::new();
+    for record in reader.records() {
+        let record = record.map_err(|error| invalid(error.to_string()))?;
+
+        let target = if headers.len() == 2 {
+            Value::String(record[1].to_string())
+        } else {
+            let mut target = Value::Object(Map::new());
+            for (header, cell) in headers.iter().zip(record.iter()).skip(1) {
+                if !cell.is_empty() {
+                    insert_cell(&mut target, header, cell).map_err(invalid)?;
+                }
+            }
+            target
+        };
+        code_list.insert(record[0].to_string(), target);
+    }
+
+    Ok(code_list)
+}
+
+/// Insert a csv cell into the target object at the field named by its header.
+///
+/// # Returns
+/// - `Err(String)`: If another column already wrote a value where the header nests the field, e.g. the columns
+///   `inScheme` and `inScheme.id`.
+fn insert_cell(target: &mut Value, header: &str, cell: &str) -> Result<(), String> {
+    let overlapping = || format!("the column `{}` overlaps with the value of another column", header);
+    let (header, value) = match header.strip_suffix("[]") {
+        Some(header) => (header, Value::Array(vec![Value::String(cell.to_string())])),
+        None => (header, Value::String(cell.to_string())),
+    };
+
+    let mut field = target;
+    for key in header.split('.') {
+        if field.is_null() {
+            *field = Value::Object(Map::new());
+        }
+        let Some(fields) = field.as_object_mut() else {
+            return Err(overlapping());
+        };
+        field = fields.entry(key).or_insert(Value::Null);
+    }
+    if !field.is_null() {
+        return Err(overlapping());
+    }
+    *field = value;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::time::Duration;
+
+    fn temp_path(name: &str) -> String {
+        let path = std::env::temp_dir().join(format!("{}-{}", std::process::id(), name));
+        path.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn reads_a_modified_code_list_again() {
+        let path = temp_path("code-list.json");
+
+        std::fs::write(&path, r#"{"online": "Online"}"#).unwrap();
+        assert_eq!(load_code_list(&path).unwrap()["online"], json!("Online"));
+
+        std::fs::write(&path, r#"{"online": "Online learning"}"#).unwrap();
+        let file = File::options().write(true).open(&path).unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(60)).unwrap();
+        assert_eq!(load_code_list(&path).unwrap()["online"], json!("Online learning"));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn nests_the_columns_of_a_csv_code_list() {
+        let path = temp_path("nested-code-list.csv");
+        std::fs::write(
+            &path,
+            "code,id,inScheme.id,prefLabel.en[]\nonline,http://example.org/online,http://example.org/settings,Online\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            load_code_list(&path).unwrap()["online"],
+            json!({
+                "id": "http://example.org/online",
+                "inScheme": {"id": "http://example.org/settings"},
+                "prefLabel": {"en": ["Online"]}
+            })
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn rejects_overlapping_csv_columns() {
+        let path = temp_path("overlapping-code-list.csv");
+        std::fs::write(
+            &path,
+            "code,inScheme,inScheme.id\nonline,settings,http://example.org/settings\n",
+        )
+        .unwrap();
+
+        let result = load_code_list(&path);
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(
+            result,
+            Err(TransformationError::InvalidCodeList { message, .. }) if message.contains("`inScheme.id`")
+        ));
+    }
+}
@@ -0,0 +1,394 @@
+use lazy_static::lazy_static;
+use serde::Serialize;
+use serde_json::{Number, Value};
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::BufReader,
+    sync::{Arc, RwLock},
+};
+
+use crate::backend::{error::ConversionError, jsonpointer::escape_token, transformations::JsonType};
+
+/// How many `$ref`s and combinators are followed into a schema, so recursive schemas don't loop.
+const MAX_SCHEMA_DEPTH: usize = 32;
+
+/// A value of the output credential of a type that isn't allowed by the output schema, see `coerce_to_schema`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Coercion {
+    /// The JsonPointer of the value in the output credential.
+    pub pointer: String,
+    pub found: JsonType,
+    /// The types allowed by the schema.
+    pub expected: Vec<JsonType>,
+    /// Whether the value was coerced, a value that can't be coerced is left unchanged.
+    pub coerced: bool,
+}
+
+lazy_static! {
+    /// The schemas read so far by their path, so they aren't read again for every conversion.
+    static ref SCHEMAS: RwLock<HashMap<String, Arc<Value>>> = RwLock::new(HashMap::new());
+}
+
+/// Load the json schema of an input or output format.
+/// A schema file is only read the first time it's loaded, later changes to the file aren't seen.
+pub fn load_schema(schema_path: &str) -> Result<Arc<Value>, ConversionError> {
+    if let Some(schema) = SCHEMAS
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .get(schema_path)
+    {
+        return Ok(schema.clone());
+    }
+
+    let schema = Arc::new(read_schema(schema_path)?);
+    SCHEMAS
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .insert(schema_path.to_string(), schema.clone());
+
+    Ok(schema)
+}
+
+fn read_schema(schema_path: &str) -> Result<Value, ConversionError> {
+    let file = File::open(schema_path)
+        .map_err(|error| ConversionError::InvalidSchema(format!("{}: {}", schema_path, error)))?;
+
+    serde_json::from_reader(BufReader::new(file))
+        .map_err(|error| ConversionError::InvalidSchema(format!("{}: {}", schema_path, error)))
+}
+
+/// Coerce the values of a credential into the types expected by its json schema:
+/// - A single value is wrapped in an array where the schema expects an array, an array with one element is
+///   unwrapped where it doesn't.
+/// - Numbers and booleans are written as strings where the schema expects a string, strings holding a number or
+///   boolean are parsed where the schema expects one.
+/// - A single string in a language map, e.g. `{"en": "title"}`, is wrapped in an array when the `additionalProperties`
+///   of the map expect an array, like any other value.
+///
+/// Only the `$ref`s within the schema are followed, values described by other schemas are left unchanged.
+///
+/// # Returns
+/// - `Vec<Coercion>`: The values that didn't have an expected type, with whether they could be coerced.
+pub fn coerce_to_schema(credential: &mut Value, schema: &Value) -> Vec<Coercion> {
+    let mut coercions = Vec::new();
+    let mut schemas = Vec::new();
+    flatten_schema(schema, schema, 0, &mut schemas);
+
+    coerce_value(credential, &schemas, schema, String::new(), &mut coercions);
+    coercions
+}
+
+fn coerce_value(value: &mut Value, schemas: &[&Value], root: &Value, pointer: String, coercions: &mut Vec<Coercion>) {
+    let expected = expected_types(schemas);
+    let found = JsonType::of(value);
+
+    if !value.is_null() && !expected.is_empty() && !expected.contains(&found) {
+        let coerced_value = coerce(value, &expected);
+        coercions.push(Coercion {
+            pointer: pointer.clone(),
+            found,
+            expected,
+            coerced: coerced_value.is_some(),
+        });
+        if let Some(coerced_value) = coerced_value {
+            *value = coerced_value;
+        }
+    }
+
+    match value {
+        Value::Array(elements) => {
+            for (index, element) in elements.iter_mut().enumerate() {
+                let items = child_schemas(root, schemas, |schema| match schema.get("items") {
+                    Some(Value::Array(tuple)) => tuple.get(index),
+                    items => items,
+                });
+                coerce_value(element, &items, root, format!("{}/{}", pointer, index), coercions);
+            }
+        }
+        Value::Object(fields) => {
+            for (key, field) in fields.iter_mut() {
+                let field_pointer = format!("{}/{}", pointer, escape_token(key));
+                let properties = child_schemas(root, schemas, |schema| {
+                    schema
+                        .get("properties")
+                        .and_then(|properties| properties.get(key.as_str()))
+                        .or_else(|| schema.get("additionalProperties").filter(|schema| schema.is_object()))
+                });
+                coerce_value(field, &properties, root, field_pointer, coercions);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// The value coerced into one of the expected types, if possible.
+fn coerce(value: &Value, expected: &[JsonType]) -> Option<Value> {
+    if expected.contains(&JsonType::of(value)) {
+        return Some(value.clone());
+    }
+
+    match value {
+        Value::Array(elements) if elements.len() == 1 => coerce(&elements[0], expected),
+        Value::Array(_) => None,
+        // A scalar of the expected type is preferred over wrapping the value in an array
+        _ => coerce_scalar(value, expected).or_else(|| {
+            expected
+                .contains(&JsonType::array)
+                .then(|| Value::Array(vec![value.clone()]))
+        }),
+    }
+}
+
+/// The scalar value written as a string, or the string parsed into a number or boolean, if one of them is expected.
+fn coerce_scalar(value: &Value, expected: &[JsonType]) -> Option<Value> {
+    match value {
+        Value::Number(_) | Value::Bool(_) if expected.contains(&JsonType::string) => {
+            Some(Value::String(value.to_string()))
+        }
+        Value::String(string) => {
+            let number = string
+                .trim()
+                .parse::<Number>()
+                .ok()
+                .filter(|_| expected.contains(&JsonType::number))
+                .map(Value::Number);
+            let boolean = string
+                .trim()
+                .parse::<bool>()
+                .ok()
+                .filter(|_| expected.contains(&JsonType::boolean))
+                .map(Value::Bool);
+            number.or(boolean)
+        }
+        _ => None,
+    }
+}
+
+/// The types allowed by any of the schemas, none when the schemas don't restrict the type.
+fn expected_types(schemas: &[&Value]) -> Vec<JsonType> {
+    let mut expected = Vec::new();
+
+    for schema in schemas {
+        let names = match schema.get("type") {
+            Some(Value::String(name)) => vec![name.as_str()],
+            Some(Value::Array(names)) => names.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        for name in names {
+            let json_type = match name {
+                "null" => JsonType::null,
+                "boolean" => JsonType::boolean,
+                "number" | "integer" => JsonType::number,
+                "string" => JsonType::string,
+                "array" => JsonType::array,
+                "object" => JsonType::object,
+                _ => continue,
+            };
+            if !expected.contains(&json_type) {
+                expected.push(json_type);
+            }
+        }
+    }
+
+    expected
+}
+
+/// The schemas of a property or element, found with `child` in each of the schemas of its parent.
+fn child_schemas<'a>(
+    root: &'a Value,
+    schemas: &[&'a Value],
+    child: impl Fn(&'a Value) -> Option<&'a Value>,
+) -> Vec<&'a Value> {
+    let mut children = Vec::new();
+    for schema in schemas {
+        if let Some(child_schema) = child(schema) {
+            flatten_schema(root, child_schema, 0, &mut children);
+        }
+    }
+    children
+}
+
+/// Collect a schema with the schemas it refers to and combines with `allOf`, `anyOf` and `oneOf`.
+fn flatten_schema<'a>(root: &'a Value, schema: &'a Value, depth: usize, flattened: &mut Vec<&'a Value>) {
+    if depth > MAX_SCHEMA_DEPTH {
+        return;
+    }
+
+    if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+        if let Some(referenced) = reference.strip_prefix('#').and_then(|pointer| root.pointer(pointer)) {
+            flatten_schema(root, referenced, depth + 1, flattened);
+        }
+    }
+    flattened.push(schema);
+
+    for combinator in ["allOf", "anyOf", "oneOf"] {
+        for branch in schema.get(combinator).and_then(Value::as_array).into_iter().flatten() {
+            flatten_schema(root, branch, depth + 1, flattened);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn wraps_a_single_value_in_an_array() {
+        let schema = json!({"type": "object", "properties": {"type": {"type": "array", "items": {"type": "string"}}}});
+        let mut credential = json!({"type": "VerifiableCredential"});
+
+        let coercions = coerce_to_schema(&mut credential, &schema);
+
+        assert_eq!(credential, json!({"type": ["VerifiableCredential"]}));
+        assert_eq!(
+            coercions,
+            vec![Coercion {
+                pointer: "/type".to_string(),
+                found: JsonType::string,
+                expected: vec![JsonType::array],
+                coerced: true,
+            }]
+        );
+    }
+
+    #[test]
+    fn unwraps_an_array_with_one_element() {
+        let schema = json!({"type": "object", "properties": {"credentialSubject": {"type": "object"}}});
+        let mut credential = json!({"credentialSubject": [{"id": "did:example:1"}]});
+
+        let coercions = coerce_to_schema(&mut credential, &schema);
+
+        assert_eq!(credential, json!({"credentialSubject": {"id": "did:example:1"}}));
+        assert!(coercions[0].coerced);
+
+        let mut credential = json!({"credentialSubject": [{"id": "did:example:1"}, {"id": "did:example:2"}]});
+        let coercions = coerce_to_schema(&mut credential, &schema);
+
+        assert_eq!(
+            credential,
+            json!({"credentialSubject": [{"id": "did:example:1"}, {"id": "did:example:2"}]})
+        );
+        assert!(!coercions[0].coerced);
+    }
+
+    #[test]
+    fn converts_numbers_booleans_and_strings() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "credits": {"type": "number"},
+                "required": {"type": "boolean"},
+                "level": {"type": "string"},
+                "passed": {"type": "string"},
+                "grade": {"type": "integer"}
+            }
+        });
+        let mut credential = json!({"credits": " 7.5 ", "required": "true", "level": 6, "passed": false, "grade": "A"});
+
+        let coercions = coerce_to_schema(&mut credential, &schema);
+
+        assert_eq!(
+            credential,
+            json!({"credits": 7.5, "required": true, "level": "6", "passed": "false", "grade": "A"})
+        );
+        let not_coerced: Vec<&str> = coercions
+            .iter()
+            .filter(|coercion| !coercion.coerced)
+            .map(|coercion| coercion.pointer.as_str())
+            .collect();
+        assert_eq!(not_coerced, vec!["/grade"]);
+    }
+
+    #[test]
+    fn wraps_language_map_values_only_where_arrays_are_expected() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "title": {"$ref": "#/definitions/ManyLangString"},
+                "name": {"$ref": "#/definitions/LangString"}
+            },
+            "definitions": {
+                "ManyLangString": {
+                    "type": "object",
+                    "propertyNames": {"pattern": "^[a-z]{2}$"},
+                    "additionalProperties": {"type": "array", "items": {"type": "string"}}
+                },
+                "LangString": {
+                    "type": "object",
+                    "propertyNames": {"pattern": "^[a-z]{2}$"},
+                    "additionalProperties": {"type": "string"}
+                }
+            }
+        });
+        let mut credential = json!({"title": {"en": "Title", "de": ["Titel"]}, "name": {"en": "Name"}});
+
+        let coercions = coerce_to_schema(&mut credential, &schema);
+
+        assert_eq!(
+            credential,
+            json!({"title": {"en": ["Title"], "de": ["Titel"]}, "name": {"en": "Name"}})
+        );
+        assert_eq!(coercions.len(), 1);
+        assert_eq!(coercions[0].pointer, "/title/en");
+    }
+
+    #[test]
+    fn follows_refs_and_all_of() {
+        let schema = json!({
+            "allOf": [
+                {"$ref": "#/definitions/Credential"},
+                {"properties": {"validFrom": {"type": "string"}}}
+            ],
+            "definitions": {
+                "Credential": {"type": "object", "properties": {"type": {"$ref": "#/definitions/Types"}}},
+                "Types": {"type": "array"}
+            }
+        });
+        let mut credential = json!({"type": "VerifiableCredential", "validFrom": 2024});
+
+        coerce_to_schema(&mut credential, &schema);
+
+        assert_eq!(
+            credential,
+            json!({"type": ["VerifiableCredential"], "validFrom": "2024"})
+        );
+    }
+
+    #[test]
+    fn escapes_the_pointers_of_the_coercions() {
+        let schema = json!({"type": "object", "additionalProperties": {"type": "string"}});
+        let mut credential = json!({"a/b~c": 1});
+
+        let coercions = coerce_to_schema(&mut credential, &schema);
+
+        assert_eq!(coercions[0].pointer, "/a~1b~0c");
+    }
+
+    #[test]
+    fn stops_following_refs_beyond_the_depth_limit() {
+        let recursive = json!({"$ref": "#", "type": "string"});
+        let mut credential = json!(1);
+
+        coerce_to_schema(&mut credential, &recursive);
+
+        assert_eq!(credential, json!("1"));
+
+        let mut definitions = serde_json::Map::new();
+        for index in 0..MAX_SCHEMA_DEPTH + 8 {
+            definitions.insert(
+                format!("d{}", index),
+                json!({"$ref": format!("#/definitions/d{}", index + 1)}),
+            );
+        }
+        definitions.insert(format!("d{}", MAX_SCHEMA_DEPTH + 8), json!({"type": "string"}));
+        let chain = json!({"$ref": "#/definitions/d0", "definitions": definitions});
+        let mut credential = json!(1);
+
+        let coercions = coerce_to_schema(&mut credential, &chain);
+
+        assert_eq!(credential, json!(1));
+        assert!(coercions.is_empty());
+    }
+}
@@ -0,0 +1,411 @@
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::{collections::HashMap, fs::File, io::BufReader};
+
+use crate::{
+    backend::{
+        coercion::{coerce_to_schema, Coercion},
+        desm_mapping::desm_transformations,
+        error::ConversionError,
+        id_generation::{assign_ids, IdStrategy},
+        init_conversion::enter_fixed_values,
+        repository::{FieldTrace, Repository, UsedFallback},
+        transformations::Transformation,
+        unconsumed::UnconsumedField,
+    },
+    state::Mapping,
+    trace_dbg,
+};
+
+/// The result of a successful conversion.
+#[derive(Debug, Clone, Serialize)]
+pub struct ConversionOutput {
+    /// The converted credential in the output format.
+    pub credential: Value,
+    pub input_format: String,
+    pub output_format: String,
+    /// The fields completed by the mapping as (destination path, source path).
+    pub completed_fields: Vec<(String, String)>,
+    /// The transformations whose source was missing, completed with a fallback source or their default value.
+    pub fallbacks: Vec<UsedFallback>,
+    /// The values that didn't have the type expected by the output schema, empty unless `coerce` was set.
+    pub coercions: Vec<Coercion>,
+    /// The values written to the output by each mapping entry, empty unless `trace` was set.
+    pub trace: Vec<FieldTrace>,
+    /// The fields of the input that no mapping entry used, empty unless `report_unconsumed` was set.
+    pub unconsumed: Vec<UnconsumedField>,
+}
+
+/// The options of a conversion that aren't part of the mapping.
+#[derive(Debug, Clone, Default)]
+pub struct ConversionOptions {
+    /// The languages of the credential, in order of preference, used by the language map transformations.
+    pub preferred_languages: Vec<String>,
+    /// Coerce the values of the output into the types expected by its json schema, see `coerce_to_schema`.
+    pub coerce: bool,
+    /// How the ids of the nodes generated for the output are generated, see `assign_ids`.
+    pub id_strategy: IdStrategy,
+    /// Record which mapping entry wrote each value of the output, from which source values, see `FieldTrace`.
+    pub trace: bool,
+    /// Report the fields of the input that no mapping entry used, so their information is missing from the output.
+    pub report_unconsumed: bool,
+}
+
+/// Convert a credential with the default options, see `convert_with_options`.
+pub fn convert(
+    input: &Value,
+    conversion: Mapping,
+    mapping: &[Transformation],
+) -> Result<ConversionOutput, ConversionError> {
+    convert_with_options(input, conversion, mapping, &ConversionOptions::default())
+}
+
+/// Convert a credential without touching any terminal state. Nothing is written to disk. The formats are the ones of
+/// the registry the `conversion` was found in, a registry built in memory with `FormatRegistry::from_formats` and
+/// formats given their schema with `Format::with_schema` aren't read from disk. Otherwise the conversion reads the
+/// json schema of the input format when its input is normalised and the one of the output format with `coerce`.
+/// The template and code list files of `template` and `lookup` transformations and the mappings of the credentials
+/// embedded in a CLR are read as well, when the mapping uses them.
+///
+/// # Arguments
+/// - `input`: The credential in the input format of the `conversion`.
+/// - `conversion`: The direction of the conversion, e.g. from `OBv3` into `ELM`, see `Mapping::find`.
+/// - `mapping`: The transformations to apply to the input credential.
+/// - `options`: The options of the conversion, e.g. the preferred languages.
+///
+/// # Returns
+/// - `Ok(ConversionOutput)`: The converted credential including the metadata of the conversion.
+/// - `Err(ConversionError)`: If the input is not a json object, a transformation could not be applied or the output
+///   schema could not be read.
+pub fn convert_with_options(
+    input: &Value,
+    conversion: Mapping,
+    mapping: &[Transformation],
+    options: &ConversionOptions,
+) -> Result<ConversionOutput, ConversionError> {
+    let (input_format, output_format) = (conversion.input_format(), conversion.output_format());
+
+    match input.as_object() {
+        Some(object) if !object.is_empty() => {}
+        Some(_) => return Err(ConversionError::InvalidInput("the credential is empty".to_string())),
+        None => {
+            return Err(ConversionError::InvalidInput(
+                "the credential is not a json object".to_string(),
+            ))
+        }
+    }
+
+    let mut input = input.clone();
+    normalise_input(&mut input, conversion)?;
+
+    let mut repository = Repository::from(HashMap::from_iter(vec![
+        (input_format.clone(), input.clone()),
+        (output_format.clone(), json!({})),
+    ]));
+    if options.trace {
+        repository.enable_trace();
+    }
+
+    let mut mapping = mapping.to_vec();
+    for transformation in mapping.iter_mut() {
+        for step in transformation.steps_mut() {
+            step.prefer_languages(&options.preferred_languages);
+        }
+    }
+
+    let applied_mapping = repository.apply_mapping(mapping, conversion)?;
+    trace_dbg!(&applied_mapping.fallbacks);
+
+    let mut unconsumed = Vec::new();
+    if options.report_unconsumed {
+        unconsumed = repository.unconsumed_fields(&input_format);
+        trace_dbg!(&unconsumed);
+    }
+
+    enter_fixed_values(&mut repository, conversion);
+
+    let mut credential = repository.remove(&output_format).unwrap_or_else(|| json!({}));
+    assign_ids(&mut credential, &input, options.id_strategy);
+
+    let mut coercions = Vec::new();
+    if options.coerce {
+        let schema = conversion.output.load_schema()?;
+        coercions = coerce_to_schema(&mut credential, &schema);
+        trace_dbg!(&coercions);
+    }
+
+    trace_dbg!("Successfully converted the credential");
+    Ok(ConversionOutput {
+        credential,
+        input_format,
+        output_format,
+        completed_fields: applied_mapping.completed_fields,
+        fallbacks: applied_mapping.fallbacks,
+        coercions,
+        trace: applied_mapping.trace,
+        unconsumed,
+    })
+}
+
+/// Convert a credential embedded in another credential, like the achievement credentials of a CLR.
+/// The ids of the generated nodes are left as placeholders, so `assign_ids` keeps them unique within the enclosing
+/// credential instead of within each embedded credential.
+pub fn convert_embedded(
+    input: &Value,
+    conversion: Mapping,
+    mapping: &[Transformation],
+) -> Result<Value, ConversionError> {
+    let mut input = input.clone();
+    normalise_input(&mut input, conversion)?;
+
+    let mut repository = Repository::from(HashMap::from_iter(vec![
+        (conversion.input_format(), input),
+        (conversion.output_format(), json!({})),
+    ]));
+    repository.apply_mapping(mapping.to_vec(), conversion)?;
+    enter_fixed_values(&mut repository, conversion);
+
+    Ok(repository
+        .remove(&conversion.output_format())
+        .unwrap_or_else(|| json!({})))
+}
+
+/// Coerce the input credential into the json schema of its format, if the conversion has one, so the mapping can
+/// rely on a single shape for each field, see `Mapping::normalised_input`.
+pub fn normalise_input(input: &mut Value, conversion: Mapping) -> Result<(), ConversionError> {
+    if let Some(format) = conversion.normalised_input() {
+        let schema = format.load_schema()?;
+        let coercions = coerce_to_schema(input, &schema);
+        trace_dbg!(&coercions);
+    }
+
+    Ok(())
+}
+
+/// Load the transformations from a custom mapping file, or from the DESM mappings when the path is "DESM".
+///
+/// # Arguments
+/// - `mapping_path`: The path of the custom mapping json file, or "DESM".
+/// - `conversion`: The direction of the conversion, only used for the DESM mappings.
+///
+/// # Returns
+/// - `Ok(Vec<Transformation>)`: The transformations found in the mapping.
+/// - `Err(ConversionError)`: If the mapping file, or the DESM spine mappings, can't be opened or deserialized.
+pub fn load_mapping(mapping_path: &str, conversion: Mapping) -> Result<Vec<Transformation>, ConversionError> {
+    if mapping_path == "DESM" {
+        return desm_transformations(conversion);
+    }
+
+    let file = File::open(mapping_path)
+        .map_err(|error| ConversionError::InvalidMapping(format!("{}: {}", mapping_path, error)))?;
+
+    serde_json::from_reader(BufReader::new(file))
+        .map_err(|error| ConversionError::InvalidMapping(format!("{}: {}", mapping_path, error)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Convert an example credential of the `test` directory with the default mapping of the conversion.
+    fn convert_example(path: &str, from: &str, to: &str) -> Value {
+        let input: Value = serde_json::from_reader(BufReader::new(File::open(path).unwrap())).unwrap();
+        convert_input(&input, from, to)
+    }
+
+    fn convert_input(input: &Value, from: &str, to: &str) -> Value {
+        let conversion = Mapping::find(from, to).unwrap();
+        let mapping = load_mapping(conversion.default_mapping().unwrap(), conversion).unwrap();
+
+        convert(input, conversion, &mapping).unwrap().credential
+    }
+
+    #[test]
+    fn converts_open_badges_2_assertions() {
+        let credential = convert_example("test/OB2_example.json", "OB2", "OBv3");
+
+        assert_eq!(credential["id"], "https://1edtech.edu/assertions/3732");
+        assert_eq!(credential["validFrom"], "2022-07-01T00:00:00Z");
+        assert_eq!(credential["validUntil"], "2030-01-01T00:00:00Z");
+        assert_eq!(credential["issuer"]["id"], "https://1edtech.edu/issuers/565049");
+        assert_eq!(credential["issuer"]["name"], "1EdTech University");
+        let achievement = &credential["credentialSubject"]["achievement"];
+        assert_eq!(achievement["name"], "Robotics Fundamentals");
+        assert_eq!(
+            achievement["criteria"]["id"],
+            "https://1edtech.edu/badges/robotics/criteria"
+        );
+        // A single evidence and alignment are normalised into arrays
+        assert_eq!(credential["evidence"][0]["name"], "Robot project");
+        assert_eq!(credential["evidence"].as_array().unwrap().len(), 1);
+        assert_eq!(achievement["alignment"][0]["targetCode"], "robotics");
+        assert_eq!(
+            credential["credentialSubject"]["identifier"][0]["identityHash"],
+            "sha256$c7ef86405ba71b85acd8e2e95166c4b111448089f2e1599f42fe1bba46e865c5"
+        );
+
+        let credential = convert_example("test/OB2_example.json", "OB2", "ELM");
+
+        assert_eq!(credential["issuer"]["id"], "https://1edtech.edu/issuers/565049");
+        assert_eq!(credential["validUntil"], "2030-01-01T00:00:00Z");
+        assert_eq!(
+            credential["credentialSubject"]["hasClaim"][0]["title"],
+            json!({ "en": ["Robotics Fundamentals"] })
+        );
+    }
+
+    #[test]
+    fn copies_an_issuer_and_criteria_given_by_their_iri_as_the_id() {
+        let mut input: Value =
+            serde_json::from_reader(BufReader::new(File::open("test/OB2_example.json").unwrap())).unwrap();
+        input["badge"]["issuer"] = json!("https://1edtech.edu/issuers/565049");
+        input["badge"]["criteria"] = json!("https://1edtech.edu/badges/robotics/criteria");
+
+        let credential = convert_input(&input, "OB2", "OBv3");
+
+        assert_eq!(credential["issuer"]["id"], "https://1edtech.edu/issuers/565049");
+        assert_eq!(
+            credential["credentialSubject"]["achievement"]["criteria"]["id"],
+            "https://1edtech.edu/badges/robotics/criteria"
+        );
+
+        let credential = convert_input(&input, "OB2", "ELM");
+
+        assert_eq!(credential["issuer"]["id"], "https://1edtech.edu/issuers/565049");
+    }
+
+    #[test]
+    fn converts_verifiable_credentials_of_both_versions() {
+        let credential = convert_example("test/W3CVC_v1_example.json", "W3CVC", "OBv3");
+
+        assert_eq!(credential["id"], "http://example.edu/credentials/1872");
+        assert_eq!(
+            credential["issuer"],
+            json!({ "id": "https://example.edu/issuers/565049", "name": "Example University", "type": ["Profile"] })
+        );
+        // The 1.1 dates are taken when a credential doesn't have the 2.0 ones
+        assert_eq!(credential["validFrom"], "2010-01-01T19:23:24Z");
+        assert_eq!(credential["validUntil"], "2030-01-01T19:23:24Z");
+        // The single subject of the array is the subject
+        assert_eq!(
+            credential["credentialSubject"]["id"],
+            "did:example:ebfeb1f712ebc6f1c276e12ec21"
+        );
+
+        let credential = convert_example("test/W3CVC_v1_example.json", "W3CVC", "ELM");
+
+        assert_eq!(credential["issuer"]["id"], "https://example.edu/issuers/565049");
+        assert_eq!(
+            credential["issuer"]["legalName"],
+            json!({ "en": ["Example University"] })
+        );
+        assert_eq!(credential["validFrom"], "2010-01-01T19:23:24Z");
+        assert_eq!(
+            credential["credentialSubject"]["id"],
+            "did:example:ebfeb1f712ebc6f1c276e12ec21"
+        );
+
+        let input = json!({
+            "@context": ["https://www.w3.org/ns/credentials/v2"],
+            "id": "urn:uuid:2",
+            "type": ["VerifiableCredential"],
+            "issuer": "https://example.edu/issuers/565049",
+            "validFrom": "2024-01-01T00:00:00Z",
+            "issuanceDate": "2010-01-01T19:23:24Z",
+            "credentialSubject": { "id": "did:example:subject" },
+        });
+        let credential = convert_input(&input, "W3CVC", "OBv3");
+
+        assert_eq!(credential["issuer"]["id"], "https://example.edu/issuers/565049");
+        assert_eq!(credential["validFrom"], "2024-01-01T00:00:00Z");
+    }
+
+    #[test]
+    fn writes_verifiable_credentials_2_0() {
+        let obv3 = convert_example("test/W3CVC_v1_example.json", "W3CVC", "OBv3");
+
+        let credential = convert_input(&obv3, "OBv3", "W3CVC");
+
+        assert_eq!(credential["@context"], json!(["https://www.w3.org/ns/credentials/v2"]));
+        assert_eq!(credential["id"], "http://example.edu/credentials/1872");
+        assert_eq!(credential["issuer"]["id"], "https://example.edu/issuers/565049");
+        assert_eq!(credential["validFrom"], "2010-01-01T19:23:24Z");
+        assert_eq!(credential["validUntil"], "2030-01-01T19:23:24Z");
+        assert_eq!(
+            credential["credentialSubject"]["id"],
+            "did:example:ebfeb1f712ebc6f1c276e12ec21"
+        );
+        assert!(credential.get("issuanceDate").is_none());
+    }
+
+    /// The ids and titles of the claims of an ELM credential, with the claims they have as parts.
+    fn claim_tree(claims: &Value) -> Vec<Value> {
+        claims
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|claim| json!([claim["id"], claim["title"], claim_tree(&claim["hasPart"])]))
+            .collect()
+    }
+
+    #[test]
+    fn round_trips_transcripts_through_a_comprehensive_learner_record() {
+        let elm: Value =
+            serde_json::from_reader(BufReader::new(File::open("test/ELM_transcript_example.json").unwrap())).unwrap();
+
+        let clr = convert_input(&elm, "ELM", "CLR");
+
+        let subject = &clr["credentialSubject"];
+        assert_eq!(subject["id"], "did:example:ebfeb1f712ebc6f1c276e12ec21");
+        let names: Vec<&Value> = subject["verifiableCredential"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|credential| &credential["credentialSubject"]["achievement"]["name"])
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "Bachelor of Computer Science",
+                "Programming 1",
+                "Databases",
+                "Databases Project"
+            ]
+        );
+        assert_eq!(subject["association"].as_array().unwrap().len(), 3);
+        assert_eq!(
+            subject["association"][2],
+            json!({
+                "type": "Association",
+                "associationType": "isParentOf",
+                "sourceId": "https://1edtech.edu/achievements/databases",
+                "targetId": "https://1edtech.edu/achievements/databases-project",
+            })
+        );
+
+        let round_tripped = convert_input(&clr, "CLR", "ELM");
+
+        assert_eq!(round_tripped["id"], elm["id"]);
+        assert_eq!(round_tripped["issuer"], elm["issuer"]);
+        assert_eq!(round_tripped["validFrom"], elm["validFrom"]);
+        assert_eq!(round_tripped["credentialSubject"]["id"], elm["credentialSubject"]["id"]);
+        assert_eq!(
+            claim_tree(&round_tripped["credentialSubject"]["hasClaim"]),
+            claim_tree(&elm["credentialSubject"]["hasClaim"])
+        );
+
+        let clr_round_tripped = convert_input(&round_tripped, "ELM", "CLR");
+
+        assert_eq!(
+            clr_round_tripped["credentialSubject"]["association"],
+            subject["association"]
+        );
+        assert_eq!(
+            clr_round_tripped["credentialSubject"]["verifiableCredential"]
+                .as_array()
+                .unwrap()
+                .len(),
+            4
+        );
+    }
+}
@@ -0,0 +1,167 @@
+use serde::{Deserialize, Serialize};
+use time::{
+    format_description::well_known::{Rfc2822, Rfc3339},
+    macros::format_description,
+    Date, OffsetDateTime, PrimitiveDateTime, Time, UtcOffset,
+};
+
+/// The format a date is written in by the `dateTime` transformation.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+pub enum DateTimeFormat {
+    /// A date and time in UTC, e.g. `2024-08-30T12:00:00Z`.
+    #[default]
+    #[serde(rename = "xsd:dateTime")]
+    DateTime,
+    /// The date in the timezone of the input, e.g. `2024-08-30` for `2024-08-30T01:00:00+02:00`.
+    #[serde(rename = "xsd:date")]
+    Date,
+}
+
+/// Normalise a date or date and time, written in the given format. A date and time is normalised to UTC, a date is
+/// the calendar date of the input in its own timezone, so it doesn't move to the day before or after.
+/// Recognises RFC 3339 and ISO 8601 dates and times with or without seconds, fractions and timezone, e.g.
+/// `2024-08-30T14:00:00+02:00`, `2024-08-30 14:00` or `2024-08-30T14:00+0200`, RFC 2822 dates like
+/// `Fri, 30 Aug 2024 14:00:00 +0200` and dates without a time like `2024-08-30`.
+/// A date and time without timezone is taken to be in UTC, a date without time to be at midnight UTC.
+///
+/// # Returns
+/// - `Some(String)`: The normalised date.
+/// - `None`: If the format of the input isn't recognised.
+pub fn normalise_date_time(input: &str, format: DateTimeFormat) -> Option<String> {
+    format_date_time(parse_date_time(input.trim())?, format)
+}
+
+/// Normalise a unix timestamp in seconds, see `normalise_date_time`.
+pub fn normalise_timestamp(timestamp: i64, format: DateTimeFormat) -> Option<String> {
+    format_date_time(OffsetDateTime::from_unix_timestamp(timestamp).ok()?, format)
+}
+
+/// The unix timestamp in seconds of a date or date and time in one of the formats of `normalise_date_time`.
+pub fn date_time_to_timestamp(input: &str) -> Option<i64> {
+    parse_date_time(input.trim()).map(OffsetDateTime::unix_timestamp)
+}
+
+/// Write a date and time in the given format, in UTC for `xsd:dateTime`.
+fn format_date_time(date_time: OffsetDateTime, format: DateTimeFormat) -> Option<String> {
+    let formatted = match format {
+        DateTimeFormat::DateTime => date_time
+            .to_offset(UtcOffset::UTC)
+            .format(format_description!("[year]-[month]-[day]T[hour]:[minute]:[second]Z")),
+        DateTimeFormat::Date => date_time.format(format_description!("[year]-[month]-[day]")),
+    };
+    formatted.ok()
+}
+
+/// Parse a date in one of the recognised formats, in its own timezone or in UTC if it has none.
+fn parse_date_time(input: &str) -> Option<OffsetDateTime> {
+    // The well-known formats only accept a `Z` with seconds, so it is written as an offset for the other formats
+    let with_offset = match input.strip_suffix(['Z', 'z']) {
+        Some(local) => format!("{}+00:00", local),
+        None => input.to_string(),
+    };
+
+    let offset_date_time = OffsetDateTime::parse(input, &Rfc3339)
+        .or_else(|_| OffsetDateTime::parse(input, &Rfc2822))
+        .or_else(|_| {
+            OffsetDateTime::parse(
+                &with_offset,
+                format_description!(
+                    "[year]-[month]-[day][first [T][ ]][hour]:[minute][optional [:[second][optional [.[subsecond]]]]]\
+                     [optional [ ]][offset_hour sign:mandatory][optional [:]][offset_minute]"
+                ),
+            )
+        });
+    if let Ok(offset_date_time) = offset_date_time {
+        return Some(offset_date_time);
+    }
+
+    let local_date_time = PrimitiveDateTime::parse(
+        input,
+        format_description!(
+            "[year]-[month]-[day][first [T][ ]][hour]:[minute][optional [:[second][optional [.[subsecond]]]]]"
+        ),
+    )
+    .or_else(|_| {
+        Date::parse(input, format_description!("[year]-[month]-[day]"))
+            .map(|date| PrimitiveDateTime::new(date, Time::MIDNIGHT))
+    });
+    local_date_time.ok().map(PrimitiveDateTime::assume_utc)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalises_the_recognised_formats_to_utc() {
+        for input in [
+            "2024-08-30T12:00:00Z",
+            "2024-08-30t12:00:00z",
+            "2024-08-30T14:00:00+02:00",
+            "2024-08-30T14:00:00.250+02:00",
+            "2024-08-30T14:00+0200",
+            "2024-08-30 14:00:00 +02:00",
+            "2024-08-30T12:00Z",
+            "2024-08-30T12:00:00",
+            "2024-08-30 12:00",
+            "Fri, 30 Aug 2024 14:00:00 +0200",
+            " 2024-08-30T12:00:00Z ",
+        ] {
+            assert_eq!(
+                normalise_date_time(input, DateTimeFormat::DateTime).as_deref(),
+                Some("2024-08-30T12:00:00Z"),
+                "{}",
+                input
+            );
+        }
+        assert_eq!(
+            normalise_date_time("2024-08-30", DateTimeFormat::DateTime).as_deref(),
+            Some("2024-08-30T00:00:00Z")
+        );
+        assert_eq!(
+            normalise_date_time("2024-01-01T01:00:00+02:00", DateTimeFormat::DateTime).as_deref(),
+            Some("2023-12-31T23:00:00Z")
+        );
+    }
+
+    #[test]
+    fn keeps_the_date_in_the_timezone_of_the_input() {
+        for (input, date) in [
+            ("2024-01-01T01:00:00+02:00", "2024-01-01"),
+            ("2024-01-01T23:00:00-05:00", "2024-01-01"),
+            ("2024-01-01T12:00:00Z", "2024-01-01"),
+            ("2024-01-01T12:00:00", "2024-01-01"),
+            ("2024-01-01", "2024-01-01"),
+            ("Mon, 1 Jan 2024 01:00:00 +0200", "2024-01-01"),
+        ] {
+            assert_eq!(
+                normalise_date_time(input, DateTimeFormat::Date).as_deref(),
+                Some(date),
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn leaves_unrecognised_dates_out() {
+        for input in ["", "yesterday", "30/08/2024", "2024-13-01", "2024-08-30T25:00:00Z"] {
+            assert_eq!(normalise_date_time(input, DateTimeFormat::DateTime), None, "{}", input);
+        }
+    }
+
+    #[test]
+    fn converts_unix_timestamps() {
+        assert_eq!(
+            normalise_timestamp(1725019200, DateTimeFormat::DateTime).as_deref(),
+            Some("2024-08-30T12:00:00Z")
+        );
+        assert_eq!(
+            normalise_timestamp(1725019200, DateTimeFormat::Date).as_deref(),
+            Some("2024-08-30")
+        );
+        assert_eq!(date_time_to_timestamp("2024-08-30T14:00:00+02:00"), Some(1725019200));
+        assert_eq!(date_time_to_timestamp("2024-08-30"), Some(1724976000));
+        assert_eq!(date_time_to_timestamp("not a date"), None);
+    }
+}
@@ -1,7 +1,7 @@
 use std::fs::File;
 
 use crate::{
-    backend::transformations::Transformation,
+    backend::{error::ConversionError, formats::DesmSpine, transformations::Transformation},
     state::{AppState, Mapping},
     trace_dbg,
 };
@@ -9,21 +9,18 @@ use crate::{
 use csv::ReaderBuilder;
 use serde::Deserialize;
 
-use super::transformations::{DataLocation, OneToOne};
+use super::transformations::{DataLocation, EntryOptions, OneToOne};
 
 /// Currently DESM only works at the Property level without considering the fields within a property.
 /// This basically renders the mapping useless as for us only the field level really matters.
 /// Another problem is that DESM mappings are path agnostic, but we need the specific paths.
 pub fn apply_desm_mapping(state: &mut AppState) {
-    let elm_spine_mapping: Vec<DesmCSVParsed> = desm_csv_parser(
-        "desm/assertion_csvs/Microcredential+Mapping_Assertion_ELM+Micro-Credential_20240208063519.csv",
-    );
-    let obv3_spine_mapping: Vec<DesmCSVParsed> =
-        desm_csv_parser("desm/assertion_csvs/Microcredential+Mapping_Assertion_Open+Badges+3.0_3.0_20240301181832.csv");
-
-    let transformations: Vec<Transformation> = match state.mapping {
-        Mapping::ELMToOBv3 => build_transformations_from_csv_parsed(elm_spine_mapping, obv3_spine_mapping),
-        Mapping::OBv3ToELM => build_transformations_from_csv_parsed(obv3_spine_mapping, elm_spine_mapping),
+    let transformations: Vec<Transformation> = match desm_transformations(state.mapping) {
+        Ok(transformations) => transformations,
+        Err(error) => {
+            trace_dbg!(&error);
+            return;
+        }
     };
 
     trace_dbg!(&transformations);
@@ -45,20 +42,40 @@ pub fn apply_desm_mapping(state: &mut AppState) {
     }
 }
 
-pub fn desm_csv_parser(path: &str) -> Vec<DesmCSVParsed> {
+/// Build the transformations for the given conversion from the DESM spine mappings of its formats.
+/// Without a spine mapping for both formats there's nothing to build the transformations from.
+///
+/// # Returns
+/// - `Err(ConversionError)`: If the spine mapping of either format can't be read.
+pub fn desm_transformations(mapping: Mapping) -> Result<Vec<Transformation>, ConversionError> {
+    let (Some(input_spine), Some(output_spine)) = (&mapping.input.desm, &mapping.output.desm) else {
+        trace_dbg!("No DESM spine mapping for the conversion");
+        return Ok(Vec::new());
+    };
+
+    Ok(build_transformations_from_csv_parsed(
+        desm_csv_parser(input_spine, &mapping.input.name)?,
+        desm_csv_parser(output_spine, &mapping.output.name)?,
+    ))
+}
+
+pub fn desm_csv_parser(spine: &DesmSpine, format: &str) -> Result<Vec<DesmCSVParsed>, ConversionError> {
     let mut ret: Vec<DesmCSVParsed> = Vec::new();
 
-    let elm_rdr = File::open(path).expect("error: wrong path hardcoded in the backend");
+    let elm_rdr = File::open(&spine.path).map_err(|error| {
+        ConversionError::InvalidMapping(format!(
+            "the DESM spine mapping {} of {} can't be read: {}",
+            spine.path, format, error
+        ))
+    })?;
     let mut elm_rdr = ReaderBuilder::new().has_headers(true).from_reader(elm_rdr);
 
     for line in elm_rdr.deserialize() {
         let result: Result<DesmCSVParsed, csv::Error> = line;
         if let Ok(mut entry) = result {
-            // Todo: ugly hardcode, but can only develop this further in tandem with DESM when their output is more mature.
-            if entry.mapped_schema == "ELM Micro-Credential" {
-                entry.mapped_schema = "ELM".to_string();
-            } else if entry.mapped_schema.starts_with("ob:") || entry.mapped_schema == "Open Badges 3.0 (3.0)" {
-                entry.mapped_schema = "OBv3".to_string();
+            // The terms are mapped onto the format they originate from by its name.
+            if entry.mapped_schema == spine.origin {
+                entry.mapped_schema = format.to_string();
             }
 
             // We could add "Similar" here as well. Right now it doesnt mean much as DESM currently only maps Properties and doesnt go down to the field level.
@@ -68,7 +85,7 @@ pub fn desm_csv_parser(path: &str) -> Vec<DesmCSVParsed> {
         }
     }
 
-    ret
+    Ok(ret)
 }
 
 pub fn build_transformations_from_csv_parsed(
@@ -91,6 +108,7 @@ pub fn build_transformations_from_csv_parsed(
                         format: outp_e.mapped_schema.clone(),
                         path: "$.".to_owned() + to_camel_case(&outp_e.mapped_term_name).as_str(),
                     },
+                    options: EntryOptions::default(),
                 });
             }
         }
@@ -2,22 +2,25 @@ use codes_iso_3166::part_1::CountryCode;
 use serde_json::{json, Map, Value};
 use std::str::FromStr;
 
+use crate::backend::id_generation::generated_id;
+
 /// Creates country code based on input type in string found in addressCountryCode
 ///
 /// # Arguments
 /// - `country_code`: the code found in .
 ///
 /// # Returns
-/// - Value: The content value Object in ELM format if successful.
-pub fn address_to_location(address_value: Value) -> Value {
+/// - `Ok(Value)`: The content value Object in ELM format if successful.
+/// - `Err(&'static str)`: If the addressCountryCode is not a known country code.
+pub fn address_to_location(address_value: Value) -> Result<Value, &'static str> {
     //inspect the address object (address as used in issuer for now) and re write it so it can be reused in ELM
     //we need to achieve the following structure into the indivudualDisplay array:
     let json_data = r#"
       {
-        "id": "urn:epass:certificateLocation:1",
+        "id": "urn:epass:certificateLocation:generated",
         "type": "Location",
         "address": {
-          "id": "urn:epass:certificateAddress:1",
+          "id": "urn:epass:certificateAddress:generated",
           "type": "Address",
           "countryCode": {
             "id": "http://publications.europa.eu/resource/authority/country/ESP",
@@ -44,9 +47,9 @@ pub fn address_to_location(address_value: Value) -> Value {
         if let Some(country_code_str) = country_code.as_str() {
             if country_code_str.is_empty() {
                 //println!("The addressCountryCode is empty.");
-                country = CountryCode::from_str("NLD").unwrap();
+                country = CountryCode::NL;
             } else {
-                country = CountryCode::from_str(country_code_str).unwrap();
+                country = CountryCode::from_str(country_code_str).map_err(|_| "unknown addressCountryCode")?;
                 //println!("The addressCountryCode is: {}", country_code_str);
             }
         } else {
@@ -58,12 +61,13 @@ pub fn address_to_location(address_value: Value) -> Value {
 
     parsed_json["address"]["countryCode"]["id"] = Value::String(format!(
         "http://publications.europa.eu/resource/authority/language/{}",
-        country.alpha_3_code().unwrap()
+        country.alpha_3_code().ok_or("country code has no alpha-3 code")?
     ));
-    parsed_json["address"]["countryCode"]["prefLabel"]["en"] = Value::String(country.full_name().unwrap().to_string());
+    parsed_json["address"]["countryCode"]["prefLabel"]["en"] =
+        Value::String(country.full_name().ok_or("country code has no name")?.to_string());
 
     //println!("{:#?}", parsed_json);
-    parsed_json
+    Ok(parsed_json)
 }
 
 /// Creates specifiedBy based on input type in string found title
@@ -78,11 +82,8 @@ pub fn title_to_specifiedby(title: Value) -> Value {
     //we need to achieve the following structure for a specification:
     let json_data = r#"
   {
-          "id": "urn:epass:learningAchievementSpec:1",
-          "type": "Qualification",
-          "title": {
-            "en": ["Data and software business"]
-          }
+          "id": "urn:epass:learningAchievementSpec:generated",
+          "type": "Qualification"
   }
   "#;
 
@@ -94,7 +95,7 @@ pub fn title_to_specifiedby(title: Value) -> Value {
         if title_str.is_empty() {
             return Value::Null;
         } else {
-            parsed_json["title"]["en"][0] = Value::String(title_str.to_string());
+            parsed_json["title"] = json!({ "en": [title_str] });
         }
     } else {
         return Value::Null;
@@ -116,7 +117,7 @@ pub fn credentialpoint_values_to_object(credits: Value) -> Value {
     //we need to achieve the following structure for a creditpoint:
     let json_data = r#"
   {
-    "id": "urn:epass:creditPoint:1",
+    "id": "urn:epass:creditPoint:generated",
     "type": "CreditPoint",
     "framework": {
       "id": "http://data.europa.eu/snb/education-credit/6fcec5c5af",
@@ -128,8 +129,7 @@ pub fn credentialpoint_values_to_object(credits: Value) -> Value {
       "prefLabel": {
         "en": ["European Credit Transfer System"]
       }
-    },
-    "point": "5"
+    }
   }
   "#;
 
@@ -139,7 +139,7 @@ pub fn credentialpoint_values_to_object(credits: Value) -> Value {
     // Access the "addressCountryCode" field
     match credits {
         Value::String(_) => {
-            parsed_json["point"] = Value::String(credits.to_string());
+            parsed_json["point"] = credits;
         }
         Value::Number(_) => {
             parsed_json["point"] = Value::String(credits.to_string());
@@ -164,14 +164,10 @@ pub fn eqf_to_specifiedby_qualification(alignment: Value) -> Value {
     //we need to achieve the following structure for a creditpoint:
     let json_data = r#"
       {
-        "id": "http://data.europa.eu/snb/eqf/5",
         "type": "Concept",
         "inScheme": {
           "id": "http://data.europa.eu/snb/eqf/25831c2",
           "type": "ConceptScheme"
-        },
-        "prefLabel": {
-          "en": ["Level 5"]
         }
       }
   "#;
@@ -210,39 +206,14 @@ pub fn eqf_to_specifiedby_qualification(alignment: Value) -> Value {
 /// - `assessment_type`: an array that could be found in OBv3 but needs to be translated to fit the new structure of ELM.
 ///
 /// # Returns
-/// - Value: The specifiedBy object with dummies in ELM format if successful.
+/// - Value: The LearningAssessment object in ELM format if successful.
 pub fn assessment_type_to_specifiedby_assesment(assessement_type: Value) -> Value {
     //inspect the title object and re write it so it can be reused in ELM for building a creditpoint that cn be used in Specification
     //we need to achieve the following structure for a creditpoint:
     let json_data = r#"
 {
-  "id": "urn:epass:learningAssessment:1",
-  "type": "LearningAssessment",
-  "awardedBy": {
-    "id": "urn:epass:awardingProcess:1",
-    "type": "AwardingProcess",
-    "awardingBody": [
-      {
-        "id": "urn:epass:org:1",
-        "type": "Organisation",
-        "location": [ {"address":"placeholder"}
-        ],
-        "legalName": {
-          "en": ["University of Life"]
-        }
-      }
-    ]
-  },
-  "title": {
-    "en": ["AssessmentTypeValue"]
-  },
-  "grade": {
-    "id": "urn:epass:note:1",
-    "type": "Note",
-    "noteLiteral": {
-      "en": ["0"]
-    }
-  }
+  "id": "urn:epass:learningAssessment:generated",
+  "type": "LearningAssessment"
 }
 
 "#;
@@ -254,7 +225,7 @@ pub fn assessment_type_to_specifiedby_assesment(assessement_type: Value) -> Valu
         if assessement_type_str.is_empty() {
             return Value::Null;
         } else {
-            parsed_json["title"]["en"][0] = Value::String(assessement_type_str.to_string());
+            parsed_json["title"] = json!({ "en": [assessement_type_str] });
         }
     } else {
         return Value::Null;
@@ -342,7 +313,7 @@ pub fn transform_alignment_to_learning_outcomes(json_obj: Value) -> Value {
                         learning_outcome.insert("title".to_string(), json!({"en": [title]}));
                         learning_outcome.insert("type".to_string(), Value::String("LearningOutcome".to_string()));
                         if !description.is_empty() {
-                        learning_outcome.insert("additionalNote".to_string(), json!([{"id": "urn:epass:note:3", "type": "Note", "noteLiteral": {"en": [description]}}]));
+                        learning_outcome.insert("additionalNote".to_string(), json!([{"id": generated_id("note"), "type": "Note", "noteLiteral": {"en": [description]}}]));
                         }
                         learning_outcome.insert("id".to_string(), Value::String(target_url.to_string()));
 
@@ -381,7 +352,7 @@ pub fn create_learning_outcome_summary(json_obj: Value) -> Value {
             Value::Null
         } else {
             let json_result = json!({
-              "id": "urn:epass:note:3",
+              "id": "urn:epass:note:generated",
               "type": "Note",
               "noteLiteral": {
                   "en": [outcome_sum_str]
@@ -0,0 +1,160 @@
+use serde_json::{json, Value};
+use std::fmt;
+
+/// Errors that can occur while converting a credential from one format to another.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConversionError {
+    /// The arguments passed to the headless cli are missing or invalid.
+    InvalidArguments(String),
+    /// The input credential could not be read or is not a json object.
+    InvalidInput(String),
+    /// The mapping file could not be read or deserialized into transformations.
+    InvalidMapping(String),
+    /// A transformation from the mapping could not be applied.
+    Transformation {
+        /// The index of the failing transformation in the mapping.
+        index: usize,
+        source_path: Option<String>,
+        destination_path: Option<String>,
+        cause: TransformationError,
+    },
+    /// The converted credential could not be serialized or written.
+    Output(String),
+    /// The json schema of the input or output format could not be read, to coerce the credential into.
+    InvalidSchema(String),
+    /// The formats registered in `json/formats` could not be read or are inconsistent.
+    InvalidFormatRegistry(String),
+}
+
+/// The reason a single transformation could not be applied.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TransformationError {
+    /// The source path is not a valid JsonPath.
+    InvalidJsonPath { path: String, message: String },
+    /// The destination path can't be converted into a JsonPointer.
+    InvalidJsonPointer { path: String, message: String },
+    /// A regular expression of the transformation doesn't compile.
+    InvalidRegex { pattern: String, message: String },
+    /// The repository doesn't contain a credential in the given format.
+    MissingFormat(String),
+    /// A transformation helper rejected the source value.
+    Helper(String),
+    /// No `TransformationHandler` is registered for the `type_` of the transformation.
+    UnknownHandler(String),
+    /// The template file can't be read or contains a placeholder that isn't bound.
+    InvalidTemplate { path: String, message: String },
+    /// The code list file of a lookup can't be read.
+    InvalidCodeList { path: String, message: String },
+    /// The code list of a lookup with `onMissing: error` has no entry for the source value.
+    MissingCode { path: String, value: String },
+    /// A credential embedded in the source value, like the achievement credentials of a CLR, can't be converted.
+    Embedded(Box<ConversionError>),
+}
+
+impl ConversionError {
+    /// The process exit code used by the headless cli for this error.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            ConversionError::InvalidArguments(_) => 2,
+            ConversionError::InvalidInput(_) => 3,
+            ConversionError::InvalidMapping(_) => 4,
+            ConversionError::Transformation { .. } => 5,
+            ConversionError::Output(_) => 6,
+            ConversionError::InvalidSchema(_) => 7,
+            ConversionError::InvalidFormatRegistry(_) => 8,
+        }
+    }
+
+    /// A json representation of the error, used as the body of error responses of the webservice.
+    pub fn to_json(&self) -> Value {
+        match self {
+            ConversionError::Transformation {
+                index,
+                source_path,
+                destination_path,
+                cause,
+            } => json!({
+                "error": "Transformation Failed",
+                "message": self.to_string(),
+                "transformation": {
+                    "index": index,
+                    "source": source_path,
+                    "destination": destination_path,
+                    "cause": cause.to_string(),
+                }
+            }),
+            ConversionError::InvalidArguments(_) | ConversionError::InvalidInput(_) => json!({
+                "error": "Bad Request",
+                "message": self.to_string(),
+            }),
+            ConversionError::InvalidMapping(_)
+            | ConversionError::Output(_)
+            | ConversionError::InvalidSchema(_)
+            | ConversionError::InvalidFormatRegistry(_) => {
+                json!({
+                    "error": "Internal Server Error",
+                    "message": self.to_string(),
+                })
+            }
+        }
+    }
+}
+
+impl fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConversionError::InvalidArguments(message) => write!(f, "invalid arguments: {}", message),
+            ConversionError::InvalidInput(message) => write!(f, "invalid input credential: {}", message),
+            ConversionError::InvalidMapping(message) => write!(f, "invalid mapping: {}", message),
+            ConversionError::Transformation {
+                index,
+                source_path,
+                destination_path,
+                cause,
+            } => write!(
+                f,
+                "transformation {} failed ({} -> {}): {}",
+                index,
+                source_path.as_deref().unwrap_or("-"),
+                destination_path.as_deref().unwrap_or("-"),
+                cause
+            ),
+            ConversionError::Output(message) => write!(f, "failed to write the output: {}", message),
+            ConversionError::InvalidSchema(message) => write!(f, "invalid json schema: {}", message),
+            ConversionError::InvalidFormatRegistry(message) => write!(f, "invalid format registry: {}", message),
+        }
+    }
+}
+
+impl fmt::Display for TransformationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransformationError::InvalidJsonPath { path, message } => {
+                write!(f, "invalid JsonPath `{}`: {}", path, message)
+            }
+            TransformationError::InvalidJsonPointer { path, message } => {
+                write!(f, "`{}` can't be converted to a JsonPointer: {}", path, message)
+            }
+            TransformationError::InvalidRegex { pattern, message } => {
+                write!(f, "invalid regular expression `{}`: {}", pattern, message)
+            }
+            TransformationError::MissingFormat(format) => write!(f, "no credential found for format {}", format),
+            TransformationError::Helper(message) => write!(f, "{}", message),
+            TransformationError::UnknownHandler(name) => write!(f, "unknown transformation `{}`", name),
+            TransformationError::InvalidTemplate { path, message } => {
+                write!(f, "invalid template `{}`: {}", path, message)
+            }
+            TransformationError::InvalidCodeList { path, message } => {
+                write!(f, "invalid code list `{}`: {}", path, message)
+            }
+            TransformationError::MissingCode { path, value } => {
+                write!(f, "code list `{}` has no entry for `{}`", path, value)
+            }
+            TransformationError::Embedded(error) => write!(f, "can't convert an embedded credential: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for ConversionError {}
+
+impl std::error::Error for TransformationError {}
@@ -0,0 +1,516 @@
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::BufReader,
+    path::Path,
+    sync::{Arc, OnceLock},
+};
+
+use crate::{
+    backend::{coercion::load_schema, error::ConversionError},
+    state::Mapping,
+};
+
+/// The directory the formats are registered in, one json file per format.
+pub const FORMATS_DIRECTORY: &str = "json/formats";
+
+static REGISTRY: OnceLock<FormatRegistry> = OnceLock::new();
+
+/// A credential format the converter can convert from and into, as declared in its file in `FORMATS_DIRECTORY`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Format {
+    /// The name of the format, used as the `format` of the data locations in a mapping, e.g. `OBv3`.
+    pub name: String,
+    /// The name of the standard shared by its versions, used in the `From` and `To` of the api, e.g. `OB`.
+    pub family: String,
+    pub version: String,
+    /// Other versions of the family the format reads as input, e.g. `1.1` for Verifiable Credentials 2.0, which are
+    /// coerced into the schema of the format.
+    #[serde(default)]
+    pub input_versions: Vec<String>,
+    /// The path of the json schema of the format.
+    pub schema: String,
+    /// The json schema of the format, when it's given with `with_schema` instead of read from the `schema` path.
+    #[serde(skip)]
+    pub schema_document: Option<Arc<Value>>,
+    /// Coerce an input in this format into its schema before it's mapped, for formats allowing multiple shapes for
+    /// the same field, see `normalise_input`.
+    #[serde(default)]
+    pub normalise_input: bool,
+    /// The `@context` of a credential converted into this format.
+    #[serde(default)]
+    pub context: Option<Value>,
+    /// The fields with a fixed value in a credential converted into this format, e.g. its `credentialSchema`.
+    #[serde(default)]
+    pub fixed_fields: Map<String, Value>,
+    /// The paths of the default mapping files into other formats, by the name of the other format.
+    #[serde(default)]
+    pub mappings: BTreeMap<String, String>,
+    /// The format a credential in this format is converted into when no output format is given, the first of its
+    /// `mappings` when it doesn't declare one.
+    #[serde(default)]
+    pub default_output: Option<String>,
+    /// The DESM spine mapping of the format, without it DESM can't be used to convert from or into it.
+    #[serde(default)]
+    pub desm: Option<DesmSpine>,
+}
+
+impl Format {
+    /// Give the format its json schema, so it's never read from the `schema` path.
+    pub fn with_schema(mut self, schema: Value) -> Format {
+        self.schema_document = Some(Arc::new(schema));
+        self
+    }
+
+    /// The json schema of the format, given with `with_schema` or else read from its `schema` path, see `load_schema`.
+    pub fn load_schema(&self) -> Result<Arc<Value>, ConversionError> {
+        match &self.schema_document {
+            Some(schema) => Ok(schema.clone()),
+            None => load_schema(&self.schema),
+        }
+    }
+
+    /// Resolve the relative paths of the format, which are relative to the directory the converter runs in, against
+    /// the `root` directory instead.
+    fn resolve_paths(&mut self, root: &Path) {
+        let resolve = |path: &mut String| {
+            if Path::new(path.as_str()).is_relative() {
+                *path = root.join(path.as_str()).to_string_lossy().to_string();
+            }
+        };
+
+        resolve(&mut self.schema);
+        self.mappings.values_mut().for_each(resolve);
+        if let Some(desm) = &mut self.desm {
+            resolve(&mut desm.path);
+        }
+    }
+
+    /// The name of the format converted into when no output format is given, see `default_output`.
+    pub fn default_output(&self) -> Option<&str> {
+        self.default_output
+            .as_deref()
+            .or_else(|| self.mappings.keys().next().map(String::as_str))
+    }
+}
+
+/// A csv export of a DESM mapping of a format onto the spine.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct DesmSpine {
+    pub path: String,
+    /// The `Mapped term origin` of the terms of this format in the csv.
+    pub origin: String,
+}
+
+/// The formats registered in a directory, sorted by name.
+#[derive(Debug, Clone, Default)]
+pub struct FormatRegistry {
+    formats: Vec<Format>,
+}
+
+impl FormatRegistry {
+    /// Load every json file in the directory as a format.
+    ///
+    /// # Returns
+    /// - `Ok(FormatRegistry)`: The registered formats.
+    /// - `Err(ConversionError)`: If a file can't be read, two formats have the same name, a format has a default
+    ///   mapping into a format that isn't registered or a default output it has no mapping into.
+    pub fn load(directory: impl AsRef<Path>) -> Result<FormatRegistry, ConversionError> {
+        let directory = directory.as_ref();
+        let invalid =
+            |message: String| ConversionError::InvalidFormatRegistry(format!("{}: {}", directory.display(), message));
+
+        let mut formats: Vec<Format> = Vec::new();
+        for entry in std::fs::read_dir(directory).map_err(|error| invalid(error.to_string()))? {
+            let path = entry.map_err(|error| invalid(error.to_string()))?.path();
+            if !path.is_file() || path.extension().and_then(|s| s.to_str()) != Some("json") {
+                continue;
+            }
+
+            let file = File::open(&path).map_err(|error| invalid(format!("{}: {}", path.display(), error)))?;
+            let format: Format = serde_json::from_reader(BufReader::new(file))
+                .map_err(|error| invalid(format!("{}: {}", path.display(), error)))?;
+            formats.push(format);
+        }
+
+        FormatRegistry::from_formats(formats).map_err(|error| match error {
+            ConversionError::InvalidFormatRegistry(message) => invalid(message),
+            error => error,
+        })
+    }
+
+    /// Load the formats in `FORMATS_DIRECTORY` of the `root` directory, e.g. the directory the converter is installed
+    /// in, resolving the relative paths of their schemas, mappings and DESM spine mappings against it. Unlike
+    /// `global`, this doesn't depend on the directory the converter runs in.
+    pub fn load_from_root(root: impl AsRef<Path>) -> Result<FormatRegistry, ConversionError> {
+        let root = root.as_ref();
+        let mut registry = FormatRegistry::load(root.join(FORMATS_DIRECTORY))?;
+        for format in &mut registry.formats {
+            format.resolve_paths(root);
+        }
+
+        Ok(registry)
+    }
+
+    /// A registry of the given formats, e.g. built in memory by a service embedding the converter. Together with
+    /// `Format::with_schema` no file is read to find the formats of a conversion.
+    ///
+    /// # Returns
+    /// - `Ok(FormatRegistry)`: The formats, sorted by name.
+    /// - `Err(ConversionError)`: If two formats have the same name, a format has a default mapping into a format that
+    ///   isn't registered or a default output it has no mapping into.
+    pub fn from_formats(mut formats: Vec<Format>) -> Result<FormatRegistry, ConversionError> {
+        let invalid = |message: String| ConversionError::InvalidFormatRegistry(message);
+
+        formats.sort_by(|a, b| a.name.cmp(&b.name));
+        if let Some(pair) = formats.windows(2).find(|pair| pair[0].name == pair[1].name) {
+            return Err(invalid(format!("the format {} is registered twice", pair[0].name)));
+        }
+
+        for format in &formats {
+            if let Some(target) = format
+                .mappings
+                .keys()
+                .find(|target| !formats.iter().any(|f| &f.name == *target))
+            {
+                return Err(invalid(format!(
+                    "the format {} has a mapping into {}, which isn't registered",
+                    format.name, target
+                )));
+            }
+            if let Some(output) = format
+                .default_output
+                .as_ref()
+                .filter(|output| !format.mappings.contains_key(*output))
+            {
+                return Err(invalid(format!(
+                    "the format {} has {} as its default output, but no mapping into it",
+                    format.name, output
+                )));
+            }
+        }
+
+        Ok(FormatRegistry { formats })
+    }
+
+    /// The registry of the formats in `FORMATS_DIRECTORY`, loaded the first time it's used.
+    pub fn global() -> Result<&'static FormatRegistry, ConversionError> {
+        if let Some(registry) = REGISTRY.get() {
+            return Ok(registry);
+        }
+        let registry = FormatRegistry::load(FORMATS_DIRECTORY)?;
+
+        Ok(REGISTRY.get_or_init(|| registry))
+    }
+
+    pub fn formats(&self) -> &[Format] {
+        &self.formats
+    }
+
+    /// The conversion between the formats of this registry with the given names. The registry lives as long as the
+    /// conversions using it, e.g. in a `static`, see `global`.
+    ///
+    /// # Returns
+    /// - `Ok(Mapping)`: The conversion from the `from` format into the `to` format.
+    /// - `Err(ConversionError)`: If a format isn't registered or the formats are the same.
+    pub fn mapping(&'static self, from: &str, to: &str) -> Result<Mapping, ConversionError> {
+        let format = |name: &str| {
+            self.get(name).ok_or_else(|| {
+                ConversionError::InvalidArguments(format!(
+                    "unknown format {}, the registered formats are {}",
+                    name,
+                    self.names()
+                ))
+            })
+        };
+        let (input, output) = (format(from)?, format(to)?);
+        if input == output {
+            return Err(ConversionError::InvalidArguments(format!(
+                "can't convert {} into itself",
+                input.name
+            )));
+        }
+
+        Ok(Mapping {
+            registry: self,
+            input,
+            output,
+        })
+    }
+
+    /// The conversions between every two formats of this registry.
+    pub fn mappings(&'static self) -> Vec<Mapping> {
+        self.formats
+            .iter()
+            .flat_map(|input| {
+                self.formats
+                    .iter()
+                    .filter(move |output| *output != input)
+                    .map(move |output| Mapping {
+                        registry: self,
+                        input,
+                        output,
+                    })
+            })
+            .collect()
+    }
+
+    /// The format with the given name, ignoring case.
+    pub fn get(&self, name: &str) -> Option<&Format> {
+        self.formats
+            .iter()
+            .find(|format| format.name.eq_ignore_ascii_case(name))
+    }
+
+    /// The format of the given family, ignoring case, in the given version, or the latest version of the family when
+    /// no version is given.
+    pub fn find_version(&self, family: &str, version: Option<&str>) -> Result<&Format, ConversionError> {
+        self.find_family_version(family, version, |format, version| format.version == version)
+    }
+
+    /// The format reading an input of the given family, ignoring case, in the given version, see `find_version`.
+    /// Besides its own version, a format reads the `input_versions` it declares.
+    pub fn find_input_version(&self, family: &str, version: Option<&str>) -> Result<&Format, ConversionError> {
+        self.find_family_version(family, version, |format, version| {
+            format.version == version
+                || format
+                    .input_versions
+                    .iter()
+                    .any(|input_version| input_version == version)
+        })
+    }
+
+    fn find_family_version(
+        &self,
+        family: &str,
+        version: Option<&str>,
+        has_version: impl Fn(&Format, &str) -> bool,
+    ) -> Result<&Format, ConversionError> {
+        let versions: Vec<&Format> = self
+            .formats
+            .iter()
+            .filter(|format| format.family.eq_ignore_ascii_case(family))
+            .collect();
+        if versions.is_empty() {
+            return Err(ConversionError::InvalidArguments(format!(
+                "unknown format family {}",
+                family
+            )));
+        }
+
+        let format = match version {
+            Some(version) => versions
+                .iter()
+                .filter(|format| has_version(format, version))
+                .max_by_key(|format| version_key(&format.version)),
+            None => versions.iter().max_by_key(|format| version_key(&format.version)),
+        };
+
+        format.copied().ok_or_else(|| {
+            ConversionError::InvalidArguments(format!(
+                "the format family {} has no version {}, the registered versions are {}",
+                family,
+                version.unwrap_or_default(),
+                versions
+                    .iter()
+                    .map(|format| format.version.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ))
+        })
+    }
+
+    /// The names of the registered formats, e.g. for error messages.
+    pub fn names(&self) -> String {
+        self.formats
+            .iter()
+            .map(|format| format.name.as_str())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// Compare versions like `2.0` and `3.0` by their numeric components.
+fn version_key(version: &str) -> Vec<u64> {
+    version
+        .split('.')
+        .map(|component| component.parse().unwrap_or_default())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{
+        convert::{convert_with_options, ConversionOptions},
+        transformations::Transformation,
+    };
+    use serde_json::json;
+
+    /// A format of the `Test` family whose schema file doesn't exist, so reading it fails the conversion.
+    fn format(name: &str, version: &str, schema: Value) -> Format {
+        let format: Format = serde_json::from_value(json!({
+            "name": name,
+            "family": "Test",
+            "version": version,
+            "schema": "json/formats/missing_schema.json",
+            "normaliseInput": true,
+            "fixedFields": { "type": [name] },
+        }))
+        .unwrap();
+
+        format.with_schema(schema)
+    }
+
+    #[test]
+    fn converts_with_formats_built_in_memory() {
+        let input_schema = json!({ "properties": { "names": { "type": "array", "items": { "type": "string" } } } });
+        let output_schema = json!({ "properties": { "name": { "type": "string" } } });
+        let registry = FormatRegistry::from_formats(vec![
+            format("Source", "1.0", input_schema),
+            format("Target", "2.0", output_schema),
+        ])
+        .unwrap();
+        let registry: &'static FormatRegistry = Box::leak(Box::new(registry));
+        let mapping: Vec<Transformation> = serde_json::from_value(json!([{
+            "type_": "copy",
+            "source": { "format": "Source", "path": "$.names" },
+            "destination": { "format": "Target", "path": "$.name" },
+        }]))
+        .unwrap();
+        let options = ConversionOptions {
+            coerce: true,
+            ..Default::default()
+        };
+
+        let conversion = registry.mapping("source", "target").unwrap();
+        let output = convert_with_options(&json!({ "names": "Maths" }), conversion, &mapping, &options).unwrap();
+
+        assert_eq!(conversion.registry.names(), "Source, Target");
+        assert_eq!(output.credential, json!({ "name": "Maths", "type": ["Target"] }));
+    }
+
+    #[test]
+    fn resolves_the_paths_of_the_formats_against_the_root() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let registry = FormatRegistry::load_from_root(root).unwrap();
+
+        let elm = registry.get("ELM").unwrap();
+        assert!(Path::new(&elm.schema).is_absolute());
+        assert!(Path::new(&elm.schema).is_file());
+        assert!(elm.mappings.values().all(|mapping| Path::new(mapping).is_file()));
+        assert_eq!(
+            Path::new(&elm.desm.as_ref().unwrap().path),
+            root.join(
+                &FormatRegistry::global()
+                    .unwrap()
+                    .get("ELM")
+                    .unwrap()
+                    .desm
+                    .as_ref()
+                    .unwrap()
+                    .path
+            )
+        );
+    }
+
+    /// The message of the `InvalidFormatRegistry` error of a registry of the formats.
+    fn registry_error(formats: Vec<Format>) -> String {
+        match FormatRegistry::from_formats(formats) {
+            Err(ConversionError::InvalidFormatRegistry(message)) => message,
+            result => panic!(
+                "expected an invalid format registry, got {:?}",
+                result.map(|registry| registry.names())
+            ),
+        }
+    }
+
+    #[test]
+    fn rejects_inconsistent_registries() {
+        let with_mappings = |name: &str, mappings: &[&str], default_output: Option<&str>| {
+            let mut format = format(name, "1.0", json!({}));
+            format.mappings = mappings
+                .iter()
+                .map(|target| (target.to_string(), format!("json/mapping/{}_{}.json", name, target)))
+                .collect();
+            format.default_output = default_output.map(str::to_string);
+            format
+        };
+
+        assert_eq!(
+            registry_error(vec![
+                with_mappings("Source", &[], None),
+                with_mappings("Source", &[], None)
+            ]),
+            "the format Source is registered twice"
+        );
+        assert_eq!(
+            registry_error(vec![with_mappings("Source", &["Missing"], None)]),
+            "the format Source has a mapping into Missing, which isn't registered"
+        );
+        assert_eq!(
+            registry_error(vec![
+                with_mappings("Source", &["Target"], Some("Other")),
+                with_mappings("Target", &[], None),
+                with_mappings("Other", &[], None),
+            ]),
+            "the format Source has Other as its default output, but no mapping into it"
+        );
+
+        let registry = FormatRegistry::from_formats(vec![
+            with_mappings("Source", &["Target", "Other"], None),
+            with_mappings("Target", &[], None),
+            with_mappings("Other", &[], None),
+        ])
+        .unwrap();
+        assert_eq!(registry.names(), "Other, Source, Target");
+        assert_eq!(registry.get("source").unwrap().default_output(), Some("Other"));
+    }
+
+    #[test]
+    fn reports_the_file_of_an_invalid_format() {
+        let directory = std::env::temp_dir().join(format!("{}-formats", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        std::fs::write(directory.join("broken.json"), r#"{ "name": "Broken" }"#).unwrap();
+
+        let result = FormatRegistry::load(&directory);
+
+        std::fs::remove_dir_all(&directory).unwrap();
+        assert!(matches!(
+            result,
+            Err(ConversionError::InvalidFormatRegistry(message))
+                if message.contains("broken.json") && message.contains("missing field `family`")
+        ));
+    }
+
+    #[test]
+    fn finds_the_formats_of_a_conversion() {
+        let registry = FormatRegistry::from_formats(vec![
+            format("TestV1", "1.0", json!({})),
+            format("TestV2", "2.0", json!({})),
+        ])
+        .unwrap();
+        let registry: &'static FormatRegistry = Box::leak(Box::new(registry));
+
+        assert!(matches!(
+            registry.mapping("TestV1", "Missing"),
+            Err(ConversionError::InvalidArguments(message))
+                if message == "unknown format Missing, the registered formats are TestV1, TestV2"
+        ));
+        assert!(matches!(
+            registry.mapping("TestV1", "testv1"),
+            Err(ConversionError::InvalidArguments(message)) if message == "can't convert TestV1 into itself"
+        ));
+        assert_eq!(registry.find_version("test", None).unwrap().name, "TestV2");
+        assert_eq!(registry.find_version("Test", Some("1.0")).unwrap().name, "TestV1");
+        assert!(matches!(
+            registry.find_version("Test", Some("3.0")),
+            Err(ConversionError::InvalidArguments(message))
+                if message == "the format family Test has no version 3.0, the registered versions are 1.0, 2.0"
+        ));
+        assert_eq!(registry.mappings().len(), 2);
+    }
+}
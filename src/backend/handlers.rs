@@ -0,0 +1,150 @@
+use lazy_static::lazy_static;
+use serde_json::Value;
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+
+use crate::backend::{
+    base64_encode::{create_display_parameter, image_to_elm_media_object},
+    clr::{achievement_credentials_to_claims, claims_to_achievement_credentials, has_part_to_associations},
+    elm_mapping_helper::{
+        address_to_location, assessment_type_to_specifiedby_assesment, create_learning_outcome_summary,
+        credentialpoint_values_to_object, eqf_to_specifiedby_qualification, object_to_note_literal,
+        title_to_specifiedby, transform_alignment_to_learning_outcomes, transform_learning_setting,
+    },
+    error::TransformationError,
+    formats::FormatRegistry,
+};
+use crate::state::Mapping;
+
+/// A transformation of a single source value, applied by the mapping entries with the name it is registered under as
+/// their `type_`. Other crates can implement it, or pass a closure, to add institution-specific transformations with
+/// `register_handler` without forking the converter.
+pub trait TransformationHandler: Send + Sync {
+    /// Transform the value found at the source path into the value written to the destination path.
+    ///
+    /// # Arguments
+    /// - `value`: The value found at the source path.
+    /// - `parameters`: The `parameters` of the mapping entry, `Value::Null` when it has none.
+    fn apply(&self, value: Value, parameters: &Value) -> Result<Value, TransformationError>;
+
+    /// Transform the value like `apply`, as part of the `conversion`. Handlers depending on the conversion implement
+    /// it, e.g. to convert the credentials embedded in the value with the formats of the same registry.
+    fn apply_in_conversion(
+        &self,
+        value: Value,
+        parameters: &Value,
+        _conversion: Mapping,
+    ) -> Result<Value, TransformationError> {
+        self.apply(value, parameters)
+    }
+}
+
+/// A CLR transformation converting the credentials embedded in the value, with the formats of the registry of the
+/// conversion it's part of, or of the global registry when it's applied on its own.
+struct EmbeddedConversion(fn(Value, &Value, &'static FormatRegistry) -> Result<Value, TransformationError>);
+
+impl TransformationHandler for EmbeddedConversion {
+    fn apply(&self, value: Value, parameters: &Value) -> Result<Value, TransformationError> {
+        let registry = FormatRegistry::global().map_err(|error| TransformationError::Embedded(Box::new(error)))?;
+        (self.0)(value, parameters, registry)
+    }
+
+    fn apply_in_conversion(
+        &self,
+        value: Value,
+        parameters: &Value,
+        conversion: Mapping,
+    ) -> Result<Value, TransformationError> {
+        (self.0)(value, parameters, conversion.registry)
+    }
+}
+
+impl<F> TransformationHandler for F
+where
+    F: Fn(Value, &Value) -> Result<Value, TransformationError> + Send + Sync,
+{
+    fn apply(&self, value: Value, parameters: &Value) -> Result<Value, TransformationError> {
+        self(value, parameters)
+    }
+}
+
+lazy_static! {
+    static ref REGISTRY: RwLock<HashMap<String, Arc<dyn TransformationHandler>>> = RwLock::new(builtin_handlers());
+}
+
+/// Register a handler under `name`, replacing a handler registered under the same name before.
+pub fn register_handler(name: &str, handler: impl TransformationHandler + 'static) {
+    REGISTRY
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .insert(name.to_string(), Arc::new(handler));
+}
+
+/// The handler registered under `name`, if any.
+pub fn get_handler(name: &str) -> Option<Arc<dyn TransformationHandler>> {
+    REGISTRY
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .get(name)
+        .cloned()
+}
+
+/// The handlers for the ELM helper, image and CLR transformations shipped with the converter.
+fn builtin_handlers() -> HashMap<String, Arc<dyn TransformationHandler>> {
+    let infallible = |helper: fn(Value) -> Value| {
+        Arc::new(move |value: Value, _: &Value| Ok(helper(value))) as Arc<dyn TransformationHandler>
+    };
+    let fallible = |helper: fn(Value) -> Result<Value, &'static str>| {
+        Arc::new(move |value: Value, _: &Value| {
+            helper(value).map_err(|error| TransformationError::Helper(error.to_string()))
+        }) as Arc<dyn TransformationHandler>
+    };
+
+    let handlers: [(&str, Arc<dyn TransformationHandler>); 14] = [
+        ("imageToIndividualDisplay", fallible(create_display_parameter)),
+        ("imageToMediaObject", fallible(image_to_elm_media_object)),
+        ("addressToLocation", fallible(address_to_location)),
+        ("titleToSpecifiedByObject", infallible(title_to_specifiedby)),
+        (
+            "creditToSpecifiedByObject",
+            infallible(credentialpoint_values_to_object),
+        ),
+        (
+            "eqfToSpecifiedByQualification",
+            infallible(eqf_to_specifiedby_qualification),
+        ),
+        (
+            "learningSettingToSpecifiedByObject",
+            infallible(transform_learning_setting),
+        ),
+        (
+            "assessmentToProvenBy",
+            infallible(assessment_type_to_specifiedby_assesment),
+        ),
+        ("objectToNoteLiteral", infallible(object_to_note_literal)),
+        (
+            "translateLearningOutcome",
+            infallible(transform_alignment_to_learning_outcomes),
+        ),
+        (
+            "createLearningOutcomeSummary",
+            infallible(create_learning_outcome_summary),
+        ),
+        (
+            "claimsToAchievementCredentials",
+            Arc::new(EmbeddedConversion(claims_to_achievement_credentials)),
+        ),
+        ("hasPartToAssociations", infallible(has_part_to_associations)),
+        (
+            "achievementCredentialsToClaims",
+            Arc::new(EmbeddedConversion(achievement_credentials_to_claims)),
+        ),
+    ];
+
+    handlers
+        .into_iter()
+        .map(|(name, handler)| (name.to_string(), handler))
+        .collect()
+}
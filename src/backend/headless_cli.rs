@@ -1,94 +1,242 @@
-use crate::backend::init_conversion::init_conversion;
-use crate::backend::init_conversion::load_mapping_file;
-use crate::p2_p3_common::create_output_files;
-use crate::state::{AppState, Mapping};
+use crate::backend::convert::{convert_with_options, load_mapping, ConversionOptions, ConversionOutput};
+use crate::backend::error::ConversionError;
+use crate::backend::id_generation::IdStrategy;
+use crate::backend::jwt::{read_credential, wrap_credential, Jwk, JwtOptions};
+use crate::state::Mapping;
 use crate::trace_dbg;
 
 use clap::Parser;
-use std::fs::read_dir;
-use std::io::Result;
+use serde::Serialize;
+use std::fs::{read_dir, File};
+use std::io::Write;
 use std::path::Path;
 
-use super::init_conversion::load_input_file;
+/// The extensions of the input files, json credentials and credentials secured in a JWT.
+const INPUT_EXTENSIONS: [&str; 2] = ["json", "jwt"];
 
-pub fn run_headless(cli_args: &mut Args, state: &mut AppState) -> Result<()> {
+/// Run the headless conversion of a single file or a (nested) batch directory.
+/// A failing file in a batch doesn't stop the batch, the last error is returned once all files are processed.
+pub fn run_headless(cli_args: &Args) -> Result<(), ConversionError> {
     check_args(cli_args)?;
     trace_dbg!(&cli_args);
-    init_appstate_headless(cli_args, state);
 
-    if cli_args.input_file.is_some() {
-        load_files_apply_transformations(state);
-    } else if cli_args.input_directory.is_some() {
+    let conversion = Mapping::find(required(&cli_args.from, "from")?, required(&cli_args.to, "to")?)?;
+    let mapping_path = required(&cli_args.mapping_file, "mapping-file")?;
+    let options = ConversionOptions {
+        preferred_languages: cli_args.preferred_languages.clone(),
+        coerce: cli_args.coerce,
+        id_strategy: cli_args.id_strategy,
+        trace: cli_args.trace,
+        report_unconsumed: cli_args.report_unconsumed,
+    };
+    let jwt_options = JwtOptions {
+        verify_key: cli_args.verify_key.as_deref().map(Jwk::load).transpose()?,
+        wrap_output: cli_args.output_jwt,
+        signing_key: cli_args.signing_key.as_deref().map(Jwk::load).transpose()?,
+    };
+
+    if let Some(input_file) = cli_args.input_file.clone() {
+        let output_file = required(&cli_args.output_file, "output-file")?;
+        convert_file(
+            &input_file,
+            output_file,
+            mapping_path,
+            conversion,
+            &options,
+            &jwt_options,
+        )?;
+    } else if let Some(input_directory) = cli_args.input_directory.clone() {
         trace_dbg!("Running batch conversion");
+        let output_directory = required(&cli_args.output_directory, "output-directory")?;
 
-        if !Path::new(&cli_args.output_directory.clone().unwrap()).is_dir() {
-            std::fs::create_dir_all(cli_args.output_directory.clone().unwrap().clone()).unwrap();
+        if !Path::new(output_directory).is_dir() {
+            std::fs::create_dir_all(output_directory)
+                .map_err(|error| ConversionError::Output(format!("{}: {}", output_directory, error)))?;
             trace_dbg!("Created the output directory");
         }
 
-        for entry in read_dir(cli_args.input_directory.clone().unwrap()).unwrap() {
-            let entry = entry.unwrap();
-            let path = entry.path();
-
-            if path.is_file() && path.extension().and_then(|s| s.to_str()) == Some("json") {
-                state.input_path = path.to_str().unwrap().to_string();
-                state.output_path = format!(
-                    "{}{}_{}.json",
-                    cli_args.output_directory.clone().unwrap(),
-                    path.file_name()
-                        .and_then(|s| s.to_str())
-                        .map(|s| s.strip_suffix(".json").unwrap_or(s))
-                        .unwrap(),
-                    state.mapping.output_format()
-                );
-                load_files_apply_transformations(state);
-            } else if path.is_dir() {
-                cli_args.input_directory = Some(path.to_str().unwrap().to_string());
-                let _ = run_headless(cli_args, state);
+        convert_directory(
+            &input_directory,
+            output_directory,
+            mapping_path,
+            conversion,
+            &options,
+            &jwt_options,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// The value of an argument the conversion can't run without, clap normally refuses the arguments without it.
+fn required<'a>(argument: &'a Option<String>, name: &str) -> Result<&'a str, ConversionError> {
+    argument
+        .as_deref()
+        .ok_or_else(|| ConversionError::InvalidArguments(format!("The argument --{} is missing", name)))
+}
+
+/// Convert the input files in the directory and its nested directories into the output directory.
+/// The arguments are checked once for the whole batch, nested directories without input files are skipped.
+fn convert_directory(
+    input_directory: &str,
+    output_directory: &str,
+    mapping_path: &str,
+    conversion: Mapping,
+    options: &ConversionOptions,
+    jwt_options: &JwtOptions,
+) -> Result<(), ConversionError> {
+    let mut result = Ok(());
+
+    let entries = read_dir(input_directory)
+        .map_err(|error| ConversionError::InvalidArguments(format!("{}: {}", input_directory, error)))?;
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+
+        if path.is_file() && is_input_file(&path) {
+            let input_file = path.to_string_lossy().to_string();
+            let output_file = format!(
+                "{}{}_{}.{}",
+                output_directory,
+                path.file_stem().and_then(|s| s.to_str()).unwrap_or_default(),
+                conversion.output_format(),
+                if jwt_options.wrap_output { "jwt" } else { "json" }
+            );
+            if let Err(error) = convert_file(
+                &input_file,
+                &output_file,
+                mapping_path,
+                conversion,
+                options,
+                jwt_options,
+            ) {
+                eprintln!("Failed to convert {}: {}", input_file, error);
+                result = Err(error);
+            }
+        } else if path.is_dir() {
+            if let Err(error) = convert_directory(
+                &path.to_string_lossy(),
+                output_directory,
+                mapping_path,
+                conversion,
+                options,
+                jwt_options,
+            ) {
+                result = Err(error);
             }
         }
     }
 
-    Ok(())
+    result
 }
 
-pub fn load_files_apply_transformations(state: &mut AppState) {
-    load_input_file(state, true);
-    load_mapping_file(state);
-    init_conversion(state);
-    create_output_files(state);
+/// Convert a single input file and write the converted credential to the output file.
+/// An input secured in a JWT or JWS is unwrapped first, see `read_credential`.
+pub fn convert_file(
+    input_path: &str,
+    output_path: &str,
+    mapping_path: &str,
+    conversion: Mapping,
+    options: &ConversionOptions,
+    jwt_options: &JwtOptions,
+) -> Result<ConversionOutput, ConversionError> {
+    let invalid_input = |error: ConversionError| match error {
+        ConversionError::InvalidInput(message) => ConversionError::InvalidInput(format!("{}: {}", input_path, message)),
+        error => error,
+    };
+
+    let input_text = std::fs::read_to_string(input_path)
+        .map_err(|error| ConversionError::InvalidInput(format!("{}: {}", input_path, error)))?;
+    let (input, envelope) = read_credential(&input_text).map_err(invalid_input)?;
+    if let Some(key) = &jwt_options.verify_key {
+        match &envelope {
+            Some(envelope) => envelope.verify(key).map_err(invalid_input)?,
+            None => {
+                return Err(invalid_input(ConversionError::InvalidInput(
+                    "the input isn't a JWT, so its signature can't be verified".to_string(),
+                )))
+            }
+        }
+    }
+
+    let transformations = load_mapping(mapping_path, conversion)?;
+    let output = convert_with_options(&input, conversion, &transformations, options)?;
+    for coercion in output.coercions.iter().filter(|coercion| !coercion.coerced) {
+        eprintln!(
+            "Warning: {}: the {:?} at {} can't be coerced into {:?}",
+            input_path, coercion.found, coercion.pointer, coercion.expected
+        );
+    }
+
+    trace_dbg!(output_path);
+    let serialized_output = match jwt_options.wrap_output {
+        true => wrap_credential(&output.credential, jwt_options.signing_key.as_ref())?,
+        false => serde_json::to_string_pretty(&output.credential)
+            .map_err(|error| ConversionError::Output(error.to_string()))?,
+    };
+    File::create(output_path)
+        .and_then(|mut file| file.write_all(serialized_output.as_bytes()))
+        .map_err(|error| ConversionError::Output(format!("{}: {}", output_path, error)))?;
+
+    if options.trace {
+        write_report(output_path, "trace", &output.trace)?;
+    }
+    if options.report_unconsumed {
+        write_report(output_path, "unconsumed", &output.unconsumed)?;
+    }
+
+    Ok(output)
+}
+
+/// Write a report of the conversion next to the output file, e.g. `output.trace.json` for `output.json`.
+fn write_report(output_path: &str, report: &str, value: &impl Serialize) -> Result<(), ConversionError> {
+    let report_path = format!("{}.{}.json", output_path.trim_end_matches(".json"), report);
+    let pretty_report =
+        serde_json::to_string_pretty(value).map_err(|error| ConversionError::Output(error.to_string()))?;
+    File::create(&report_path)
+        .and_then(|mut file| file.write_all(pretty_report.as_bytes()))
+        .map_err(|error| ConversionError::Output(format!("{}: {}", report_path, error)))
 }
 
-pub fn check_args(cli_args: &Args) -> Result<()> {
+/// Validate the paths passed to the headless cli before converting anything.
+pub fn check_args(cli_args: &Args) -> Result<(), ConversionError> {
+    let invalid = |message: String| Err(ConversionError::InvalidArguments(message));
+
     if let Some(input_f) = &cli_args.input_file {
         if !Path::new(&input_f).is_file() {
-            panic!("The input file path does not exist: {}", &input_f);
-        } else if !input_f.ends_with(".json") {
-            panic!("The input file is not a json file: {}", &input_f);
+            return invalid(format!("The input file path does not exist: {}", &input_f));
+        } else if !is_input_file(Path::new(input_f)) {
+            return invalid(format!("The input file is not a json or jwt file: {}", &input_f));
         }
     }
     if let Some(input_dir) = &cli_args.input_directory {
         if !Path::new(&input_dir).is_dir() {
-            panic!("The input directory path does not exist: {}", input_dir);
+            return invalid(format!("The input directory path does not exist: {}", input_dir));
         }
         let mut json_count: usize = 0;
         json_count = check_input_dir(input_dir, &mut json_count);
 
         if json_count == 0 {
-            panic!("The input directory does not contain any json files: {}", input_dir);
+            return invalid(format!(
+                "The input directory does not contain any json or jwt files: {}",
+                input_dir
+            ));
         }
     }
     if let Some(output_f) = &cli_args.output_file {
-        if !output_f.ends_with(".json") {
-            panic!("The output file path doesn't end with \".json\": {}", output_f);
+        if !output_f.ends_with(".json") && !output_f.ends_with(".jwt") {
+            return invalid(format!(
+                "The output file path doesn't end with \".json\" or \".jwt\": {}",
+                output_f
+            ));
         }
     }
     if let Some(mapping_f) = &cli_args.mapping_file {
         if mapping_f != "DESM" {
             if !Path::new(&mapping_f).is_file() {
-                panic!("The mapping file path does not exist: {}", &mapping_f);
+                return invalid(format!("The mapping file path does not exist: {}", &mapping_f));
             } else if !mapping_f.ends_with(".json") {
-                panic!("The mapping file is not a json file: {}", &mapping_f);
+                return invalid(format!("The mapping file is not a json file: {}", &mapping_f));
             }
         }
     }
@@ -97,31 +245,25 @@ pub fn check_args(cli_args: &Args) -> Result<()> {
 }
 
 pub fn check_input_dir(input_dir: &str, json_count: &mut usize) -> usize {
-    for entry in read_dir(input_dir).unwrap() {
-        let entry = entry.unwrap();
+    for entry in read_dir(input_dir).into_iter().flatten().flatten() {
         let path = entry.path();
 
-        if path.is_file() && path.extension().and_then(|s| s.to_str()) == Some("json") {
+        if path.is_file() && is_input_file(&path) {
             *json_count += 1;
         } else if path.is_dir() {
-            check_input_dir(path.to_str().unwrap(), json_count);
+            check_input_dir(&path.to_string_lossy(), json_count);
         }
     }
-    let dbg_msg = format!("The input directory contains {} json files", *json_count);
+    let dbg_msg = format!("The input directory contains {} json or jwt files", *json_count);
     trace_dbg!(dbg_msg);
     *json_count
 }
 
-pub fn init_appstate_headless(args: &Args, state: &mut AppState) {
-    state.mapping = args.conversion.unwrap();
-    state.mapping_path = args.mapping_file.clone().unwrap();
-    if let Some(input_f) = args.input_file.clone() {
-        state.input_path = input_f;
-        state.output_path = args.output_file.clone().unwrap();
-    } else if let Some(input_dir) = args.input_directory.clone() {
-        state.input_path = input_dir;
-        state.output_path = args.output_directory.clone().unwrap();
-    }
+/// Whether the file has the extension of an input file, see `INPUT_EXTENSIONS`.
+fn is_input_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|s| s.to_str())
+        .is_some_and(|extension| INPUT_EXTENSIONS.contains(&extension))
 }
 
 ///// STRUCTS /////
@@ -129,7 +271,7 @@ pub fn init_appstate_headless(args: &Args, state: &mut AppState) {
 #[derive(Parser, Debug)]
 #[command(
     version = "1.0.0",
-    about = "This is the executable for the Credential Converter built by Impierce Technologies.\nWhen running without arguments it will start the Terminal User Interface.\nHere you can add, edit, save and tweak all the conversions manually\nFor headless conversion there are 2 options:\nConvert file to file in .json format.\nBatch conversion, convert all .json files in a given directory, also nested directories.\nFiles being output to an output directory will have the original name appended with \"_<conversion_destination_format>\"\nPaths to existing output files/directories will be overwritten.\nFor DESM Mappings simply enter 'DESM' as the mappping file (-m)\nPassing incorrect arguments will return helpful error messages.\nRead more below:"
+    about = "This is the executable for the Credential Converter built by Impierce Technologies.\nWhen running without arguments it will start the Terminal User Interface.\nHere you can add, edit, save and tweak all the conversions manually\nFor headless conversion there are 2 options:\nConvert file to file in .json format, or a .jwt holding a JWT.\nBatch conversion, convert all .json and .jwt files in a given directory, also nested directories.\nFiles being output to an output directory will have the original name appended with \"_<conversion_destination_format>\"\nPaths to existing output files/directories will be overwritten.\nFor DESM Mappings simply enter 'DESM' as the mappping file (-m)\nPassing incorrect arguments will return helpful error messages.\nExit codes: 0 success, 2 invalid arguments, 3 invalid input, 4 invalid mapping, 5 failed transformation, 6 failed to write output, 7 failed to read the output schema, 8 failed to read the registered formats.\nRead more below:"
 )]
 pub struct Args {
     #[arg(short, long, requires_all = ["mapping_file", "output_file"], conflicts_with_all = ["input_directory", "output_directory"])]
@@ -144,11 +286,56 @@ pub struct Args {
     #[arg(short = 'd', long, requires_all = ["mapping_file", "input_directory"], conflicts_with_all = ["input_file", "output_file"])]
     output_directory: Option<String>,
 
-    #[arg(short, long, required_if_eq_any = [("conversion", "Some"), ("input_file", "Some"), ("input_directory", "Some"), ("output_file", "Some"), ("output_directory", "Some")], requires = "conversion")]
+    #[arg(short, long, requires_all = ["from", "to"])]
     mapping_file: Option<String>,
 
-    #[arg(short, long, value_enum, required_if_eq_any = [("mapping_file", "Some"), ("input_file", "Some"), ("input_directory", "Some"), ("output_file", "Some"), ("output_directory", "Some")])]
-    conversion: Option<Mapping>,
+    /// The format of the input credentials, one of the formats registered in `json/formats`, e.g. `OBv3`.
+    #[arg(long, requires_all = ["to", "mapping_file"])]
+    from: Option<String>,
+
+    /// The format to convert the input credentials into, one of the formats registered in `json/formats`, e.g. `ELM`.
+    #[arg(long, requires_all = ["from", "mapping_file"])]
+    to: Option<String>,
+
+    /// The languages of the credentials in order of preference, e.g. `en,nl`. The first language tags the values
+    /// written to language maps, values read from language maps are taken in the first language available.
+    #[arg(short = 'l', long, value_delimiter = ',', requires = "mapping_file")]
+    preferred_languages: Vec<String>,
+
+    /// Coerce the values of the output into the types expected by the json schema of the output format, e.g. wrap a
+    /// single value where an array is expected. Values that can't be coerced are reported as warnings.
+    #[arg(long, requires = "mapping_file")]
+    coerce: bool,
+
+    /// How the ids of the nodes generated for the output are generated: numbered per type, random UUIDs or UUIDs
+    /// derived from the id of the input credential, which are the same every time the credential is converted.
+    #[arg(long, value_enum, default_value_t = IdStrategy::Sequential, requires = "mapping_file")]
+    id_strategy: IdStrategy,
+
+    /// Write a trace of the conversion next to each output file, e.g. `output.trace.json` for `output.json`. It lists
+    /// every value written to the output with the mapping entry, source paths and source values it was made of.
+    #[arg(long, requires = "mapping_file")]
+    trace: bool,
+
+    /// Write the fields of the input that no mapping entry used next to each output file, e.g.
+    /// `output.unconsumed.json` for `output.json`. Fields none of whose nested fields were used are listed as a whole.
+    #[arg(long, requires = "mapping_file")]
+    report_unconsumed: bool,
+
+    /// Verify the signature of the JWT and JWS inputs with the public key in this JWK file, an input without a valid
+    /// signature fails as invalid input. Without it the signature of the inputs isn't verified. Keys are only read
+    /// locally, HS256, RS256, ES256 and EdDSA (Ed25519) are supported.
+    #[arg(long, requires = "mapping_file")]
+    verify_key: Option<String>,
+
+    /// Write the converted credentials as a JWT with the credential in its `vc` claim and its issuer, subject id,
+    /// dates and id in the `iss`, `sub`, `nbf`, `exp` and `jti` claims. The JWT is unsecured without a `--signing-key`.
+    #[arg(long, requires = "mapping_file")]
+    output_jwt: bool,
+
+    /// Sign the JWT written by `--output-jwt` with the private key in this JWK file.
+    #[arg(long, requires = "output_jwt")]
+    signing_key: Option<String>,
 
     #[arg(short, long)]
     web_service: Option<Option<String>>,
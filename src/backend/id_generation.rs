@@ -0,0 +1,355 @@
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+use crate::backend::jsonpointer::escape_token;
+
+/// The prefix of the ids of the nodes generated for an ELM credential.
+pub const ID_PREFIX: &str = "urn:epass:";
+/// The last part of the id of a generated node until `assign_ids` replaces it, e.g. `urn:epass:note:generated`.
+/// A label can follow it to share the id between nodes and references, e.g. `urn:epass:awardingProcess:generated:claim`.
+pub const PLACEHOLDER: &str = "generated";
+
+/// How `assign_ids` generates the ids of the generated nodes.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, ValueEnum)]
+#[serde(rename_all = "camelCase")]
+pub enum IdStrategy {
+    /// Number the nodes per type in the order of the document, e.g. `urn:epass:note:1`.
+    #[default]
+    #[clap(name = "sequential")]
+    Sequential,
+    /// A random UUID, e.g. `urn:epass:note:6f1c2d9e-...`.
+    #[clap(name = "uuidV4")]
+    UuidV4,
+    /// A UUID derived from the id of the input credential and the location of the node, so converting the same
+    /// credential again results in the same ids.
+    #[clap(name = "uuidV5")]
+    UuidV5,
+}
+
+/// The placeholder id of a generated node of `node_type`, e.g. `urn:epass:note:generated` for a `note`.
+/// Helpers and templates give the nodes they generate a placeholder id, which is replaced by `assign_ids`.
+pub fn generated_id(node_type: &str) -> String {
+    format!("{}{}:{}", ID_PREFIX, node_type, PLACEHOLDER)
+}
+
+/// Whether the id identifies a single node. An unlabelled placeholder doesn't, every generated node of a type has it
+/// until `assign_ids` gives each node an id of its own.
+pub fn identifies_node(id: &str) -> bool {
+    !matches!(parse_placeholder(id), Some((_, None)))
+}
+
+/// Replace the placeholder ids of the generated nodes in a converted credential, see `generated_id`.
+/// The ids are unique within the credential: an id that is already used, by another generated node or by a node
+/// copied from the input credential, is never generated.
+///
+/// Every node with an unlabelled placeholder gets an id of its own, the nodes with the same labelled placeholder share
+/// one. A placeholder that isn't the `id` of a node is a reference, it's replaced by the id of the first node with the
+/// placeholder, in the order of the document, so references never point at an id no node has.
+///
+/// # Arguments
+/// - `credential`: The converted credential.
+/// - `input`: The input credential, the UUIDv5 ids are derived from its `id`.
+/// - `strategy`: How the ids are generated.
+pub fn assign_ids(credential: &mut Value, input: &Value, strategy: IdStrategy) {
+    let mut generator = IdGenerator {
+        strategy,
+        namespace: input_namespace(input),
+        used: HashSet::new(),
+        counters: HashMap::new(),
+        assigned: HashMap::new(),
+    };
+    collect_ids(credential, &mut generator.used);
+
+    generator.replace_placeholders(credential, String::new());
+    generator.resolve_references(credential, String::new());
+}
+
+struct IdGenerator {
+    strategy: IdStrategy,
+    /// The namespace of the UUIDv5 ids.
+    namespace: Uuid,
+    /// The ids used in the credential, including the ones generated so far.
+    used: HashSet<String>,
+    /// The last number generated per node type.
+    counters: HashMap<String, usize>,
+    /// The id given to the first node with each placeholder, the id of the references to the placeholder.
+    assigned: HashMap<String, String>,
+}
+
+impl IdGenerator {
+    fn replace_placeholders(&mut self, value: &mut Value, pointer: String) {
+        match value {
+            Value::Object(fields) => {
+                let placeholder = fields.get("id").and_then(Value::as_str).map(str::to_string);
+                if let Some((placeholder, (node_type, label))) =
+                    placeholder.as_deref().and_then(|id| Some((id, parse_placeholder(id)?)))
+                {
+                    let id = match (label, self.assigned.get(placeholder)) {
+                        (Some(_), Some(id)) => id.clone(),
+                        _ => self.generate(node_type, &pointer),
+                    };
+                    self.assigned
+                        .entry(placeholder.to_string())
+                        .or_insert_with(|| id.clone());
+                    fields.insert("id".to_string(), Value::String(id));
+                }
+
+                for (key, field) in fields.iter_mut() {
+                    self.replace_placeholders(field, format!("{}/{}", pointer, escape_token(key)));
+                }
+            }
+            Value::Array(elements) => {
+                for (index, element) in elements.iter_mut().enumerate() {
+                    self.replace_placeholders(element, format!("{}/{}", pointer, index));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Replace the placeholders left after the nodes got their ids, which are references, by the id of the node with
+    /// the same placeholder. A reference to a placeholder no node has gets an id of its own, shared by all of them.
+    fn resolve_references(&mut self, value: &mut Value, pointer: String) {
+        match value {
+            Value::String(reference) => {
+                if let Some((node_type, _)) = parse_placeholder(reference) {
+                    let id = match self.assigned.get(reference.as_str()) {
+                        Some(id) => id.clone(),
+                        None => self.generate(node_type, &pointer),
+                    };
+                    self.assigned.insert(reference.clone(), id.clone());
+                    *reference = id;
+                }
+            }
+            Value::Object(fields) => {
+                for (key, field) in fields.iter_mut() {
+                    self.resolve_references(field, format!("{}/{}", pointer, escape_token(key)));
+                }
+            }
+            Value::Array(elements) => {
+                for (index, element) in elements.iter_mut().enumerate() {
+                    self.resolve_references(element, format!("{}/{}", pointer, index));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Generate an id for the node of `node_type` at the JsonPointer `pointer` that isn't used yet.
+    fn generate(&mut self, node_type: &str, pointer: &str) -> String {
+        let mut attempt = 0;
+        loop {
+            let suffix = match self.strategy {
+                IdStrategy::Sequential => {
+                    let counter = self.counters.entry(node_type.to_string()).or_default();
+                    *counter += 1;
+                    counter.to_string()
+                }
+                IdStrategy::UuidV4 => Uuid::new_v4().to_string(),
+                // A used id can only be generated again for the same name, so another attempt changes the name
+                IdStrategy::UuidV5 => {
+                    let name = match attempt {
+                        0 => pointer.to_string(),
+                        attempt => format!("{}#{}", pointer, attempt),
+                    };
+                    Uuid::new_v5(&self.namespace, name.as_bytes()).to_string()
+                }
+            };
+
+            let id = format!("{}{}:{}", ID_PREFIX, node_type, suffix);
+            if self.used.insert(id.clone()) {
+                return id;
+            }
+            attempt += 1;
+        }
+    }
+}
+
+/// The node type and label of a placeholder id, e.g. `note` for `urn:epass:note:generated` and `awardingProcess` and
+/// `claim` for `urn:epass:awardingProcess:generated:claim`.
+fn parse_placeholder(id: &str) -> Option<(&str, Option<&str>)> {
+    let (node_type, rest) = id.strip_prefix(ID_PREFIX)?.split_once(':')?;
+    let label = match rest.strip_prefix(PLACEHOLDER)? {
+        "" => None,
+        label => Some(label.strip_prefix(':').filter(|label| !label.is_empty())?),
+    };
+
+    (!node_type.is_empty()).then_some((node_type, label))
+}
+
+/// Collect the ids of all nodes in the credential, except the placeholders.
+fn collect_ids(value: &Value, ids: &mut HashSet<String>) {
+    match value {
+        Value::Object(fields) => {
+            if let Some(id) = fields.get("id").and_then(Value::as_str) {
+                if parse_placeholder(id).is_none() {
+                    ids.insert(id.to_string());
+                }
+            }
+            fields.values().for_each(|field| collect_ids(field, ids));
+        }
+        Value::Array(elements) => elements.iter().for_each(|element| collect_ids(element, ids)),
+        _ => {}
+    }
+}
+
+/// The namespace of the UUIDv5 ids generated for an input credential, derived from its `id`.
+/// A credential without id is identified by its content instead.
+fn input_namespace(input: &Value) -> Uuid {
+    let name = match input.get("id").and_then(Value::as_str) {
+        Some(id) => id.to_string(),
+        None => input.to_string(),
+    };
+    Uuid::new_v5(&Uuid::NAMESPACE_URL, name.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        backend::{convert::convert, transformations::Transformation},
+        state::Mapping,
+    };
+    use serde_json::json;
+
+    /// A mapping entry writing the `value` to the ELM `destination`.
+    fn stringit(value: &str, destination: &str) -> Transformation {
+        serde_json::from_value(json!({
+            "type_": "stringit",
+            "source": { "value": value },
+            "destination": { "format": "ELM", "path": destination },
+        }))
+        .unwrap()
+    }
+
+    /// Collect the ids of the nodes and the other strings that look like an id, the references.
+    fn collect_nodes_and_references(value: &Value, key: &str, nodes: &mut Vec<String>, references: &mut Vec<String>) {
+        match value {
+            Value::String(id) if key == "id" => nodes.push(id.clone()),
+            Value::String(reference) if reference.starts_with(ID_PREFIX) => references.push(reference.clone()),
+            Value::Object(fields) => fields
+                .iter()
+                .for_each(|(key, field)| collect_nodes_and_references(field, key, nodes, references)),
+            Value::Array(elements) => elements
+                .iter()
+                .for_each(|element| collect_nodes_and_references(element, key, nodes, references)),
+            _ => {}
+        }
+    }
+
+    #[test]
+    fn resolves_the_references_of_a_converted_credential() {
+        let mapping = vec![
+            stringit(
+                "urn:epass:awardingProcess:generated",
+                "$.credentialSubject.hasClaim[0].awardedBy.id",
+            ),
+            stringit("AwardingProcess", "$.credentialSubject.hasClaim[0].awardedBy.type"),
+            stringit(
+                "urn:epass:awardingProcess:generated",
+                "$.credentialSubject.hasClaim[1].awardedBy",
+            ),
+            stringit(
+                "urn:epass:note:generated:remark",
+                "$.credentialSubject.hasClaim[0].additionalNote[0].id",
+            ),
+            stringit(
+                "urn:epass:note:generated:remark",
+                "$.credentialSubject.hasClaim[1].additionalNote[0]",
+            ),
+        ];
+        let input = json!({ "id": "urn:uuid:credential", "type": ["VerifiableCredential"] });
+
+        let output = convert(&input, Mapping::find("OBv3", "ELM").unwrap(), &mapping).unwrap();
+
+        let (mut nodes, mut references) = (Vec::new(), Vec::new());
+        collect_nodes_and_references(&output.credential, "", &mut nodes, &mut references);
+        let claims = &output.credential["credentialSubject"]["hasClaim"];
+        assert_eq!(claims[1]["awardedBy"], claims[0]["awardedBy"]["id"]);
+        assert_eq!(claims[1]["additionalNote"][0], claims[0]["additionalNote"][0]["id"]);
+        for reference in &references {
+            assert!(nodes.contains(reference), "{} refers to no node", reference);
+        }
+        assert!(nodes
+            .iter()
+            .chain(&references)
+            .all(|id| parse_placeholder(id).is_none()));
+    }
+
+    #[test]
+    fn shares_the_id_of_labelled_placeholders_only() {
+        let mut credential = json!({
+            "notes": [
+                { "id": "urn:epass:note:generated" },
+                { "id": "urn:epass:note:generated" },
+                { "id": "urn:epass:note:generated:shared" },
+                { "id": "urn:epass:note:generated:shared" },
+            ],
+        });
+
+        assign_ids(&mut credential, &json!({}), IdStrategy::Sequential);
+
+        assert_eq!(
+            credential["notes"],
+            json!([
+                { "id": "urn:epass:note:1" },
+                { "id": "urn:epass:note:2" },
+                { "id": "urn:epass:note:3" },
+                { "id": "urn:epass:note:3" },
+            ])
+        );
+    }
+
+    #[test]
+    fn parses_placeholders() {
+        assert_eq!(parse_placeholder("urn:epass:note:generated"), Some(("note", None)));
+        assert_eq!(
+            parse_placeholder("urn:epass:awardingProcess:generated:claim"),
+            Some(("awardingProcess", Some("claim")))
+        );
+        assert_eq!(parse_placeholder("urn:epass:note:generated:"), None);
+        assert_eq!(parse_placeholder("urn:epass:note:1"), None);
+        assert_eq!(parse_placeholder("urn:epass::generated"), None);
+    }
+
+    #[test]
+    fn never_generates_an_id_already_used() {
+        let mut credential = json!({
+            "copied": { "id": "urn:epass:note:1" },
+            "notes": [{ "id": "urn:epass:note:generated" }, { "id": "urn:epass:note:generated" }],
+        });
+
+        assign_ids(&mut credential, &json!({}), IdStrategy::Sequential);
+
+        assert_eq!(
+            credential["notes"],
+            json!([{ "id": "urn:epass:note:2" }, { "id": "urn:epass:note:3" }])
+        );
+    }
+
+    #[test]
+    fn derives_uuid_v5_ids_from_the_input_credential() {
+        let generated =
+            json!({ "notes": [{ "id": "urn:epass:note:generated" }, { "id": "urn:epass:note:generated" }] });
+        let ids = |input: Value, strategy: IdStrategy| {
+            let mut credential = generated.clone();
+            assign_ids(&mut credential, &input, strategy);
+            [
+                credential["notes"][0]["id"].clone(),
+                credential["notes"][1]["id"].clone(),
+            ]
+        };
+        let input = json!({ "id": "urn:uuid:credential" });
+
+        let [first, second] = ids(input.clone(), IdStrategy::UuidV5);
+        assert!(first.as_str().unwrap().starts_with("urn:epass:note:"));
+        assert_ne!(first, second);
+        assert_eq!(ids(input.clone(), IdStrategy::UuidV5), [first.clone(), second]);
+        assert_ne!(ids(json!({ "id": "urn:uuid:another" }), IdStrategy::UuidV5)[0], first);
+
+        assert_ne!(ids(input.clone(), IdStrategy::UuidV4), ids(input, IdStrategy::UuidV4));
+    }
+}
@@ -3,7 +3,12 @@ use serde_json::{json, Value};
 use std::{collections::HashMap, fs::File, io::BufReader, path::Path};
 
 use crate::{
-    backend::{leaf_nodes::get_leaf_nodes, repository::Repository, transformations::Transformation},
+    backend::{
+        convert::{load_mapping, normalise_input},
+        jwt::read_credential,
+        leaf_nodes::get_leaf_nodes,
+        repository::Repository,
+    },
     state::{AppState, Mapping},
     trace_dbg,
 };
@@ -12,52 +17,41 @@ use super::{desm_mapping::apply_desm_mapping, update_display::update_display_sec
 
 pub fn init_conversion(state: &mut AppState) {
     init_schema(state);
-    load_input_file(state, false);
+    load_input_file(state);
     load_mapping_file(state);
-    enter_fixed_context_values(state);
-    enter_fixed_schema_values(state);
-    enter_credential_profile_values(state);
+    enter_fixed_values(&mut state.repository, state.mapping);
     update_display_section(state, false);
 }
 
 /// Initialize output schema
 fn init_schema(state: &mut AppState) {
-    match state.mapping {
-        Mapping::OBv3ToELM => {
-            state.target_schema = get_json("json/ebsi-elm/vcdm2.0-europass-edc-schema/schema.json")
-                .expect("error: couldn't retrieve Europass EDC ELM schema");
-        }
-        Mapping::ELMToOBv3 => {
-            state.target_schema =
-                get_json("json/obv3/obv3_schema.json").expect("error: couldn't retrieve OpenBadges version 3 schema");
-        }
-    }
+    state.target_schema = get_json(state.mapping.output_schema())
+        .unwrap_or_else(|_| panic!("error: couldn't retrieve the {} schema", state.mapping.output_format()));
 }
 
-pub fn load_input_file(state: &mut AppState, headless: bool) {
+pub fn load_input_file(state: &mut AppState) {
     let (input_format, output_format) = (state.mapping.input_format(), state.mapping.output_format());
 
-    if let Ok(rdr) = std::fs::File::open(&state.input_path) {
-        if let Ok(input_value) = serde_json::from_reader(rdr) {
-            let leaf_nodes: HashMap<String, Value> = get_leaf_nodes(input_value);
+    if let Ok(input_text) = std::fs::read_to_string(&state.input_path) {
+        // A credential secured in a JWT or JWS is shown unwrapped, its signature isn't verified
+        if let Ok((mut input_value, _)) = read_credential(&input_text) {
+            if let Err(error) = normalise_input(&mut input_value, state.mapping) {
+                trace_dbg!(&error);
+            }
+            let leaf_nodes: HashMap<String, Value> = get_leaf_nodes(input_value.clone());
             if !leaf_nodes.is_empty() {
-                if !headless {
-                    let mut input_fields = vec![(String::new(), String::new())];
-
-                    for (key, value) in leaf_nodes {
-                        input_fields.push((key, value.to_string()));
-                    }
+                let mut input_fields = vec![(String::new(), String::new())];
 
-                    input_fields.sort();
-                    state.amount_input_fields = input_fields.len() - 2;
-                    state.input_fields = input_fields;
+                for (key, value) in leaf_nodes {
+                    input_fields.push((key, value.to_string()));
                 }
 
+                input_fields.sort();
+                state.amount_input_fields = input_fields.len() - 2;
+                state.input_fields = input_fields;
+
                 state.repository = Repository::from(HashMap::from_iter(vec![
-                    (
-                        input_format.to_string(),
-                        get_json(&state.input_path).expect("No source file found"),
-                    ),
+                    (input_format.to_string(), input_value),
                     (output_format.to_string(), json!({})),
                 ]));
 
@@ -77,13 +71,14 @@ pub fn load_mapping_file(state: &mut AppState) {
     if state.mapping_path == "DESM" {
         apply_desm_mapping(state);
     } else {
-        let rdr = std::fs::File::open(&state.mapping_path).unwrap();
-        let transformations: Vec<Transformation> = serde_json::from_reader(rdr).unwrap();
-        trace_dbg!("Successfully loaded the mapping file");
-        let result = state.repository.apply_transformations(transformations, state.mapping);
+        let result = load_mapping(&state.mapping_path, state.mapping).and_then(|transformations| {
+            trace_dbg!("Successfully loaded the mapping file");
+            state.repository.apply_transformations(transformations, state.mapping)
+        });
         match result {
             Ok(_value) => {}
-            Err(_error) => {
+            Err(error) => {
+                trace_dbg!(&error);
                 state.exit_warning = true;
             }
         }
@@ -92,69 +87,21 @@ pub fn load_mapping_file(state: &mut AppState) {
     }
 }
 
-/// Enter fixed values into '@context' field, as demanded by the respective json-schema
-fn enter_fixed_context_values(state: &mut AppState) {
-    if state.mapping.output_format() == "ELM" {
-        let output_elm = state.repository.get_mut("ELM").unwrap().as_object_mut().unwrap();
-        output_elm.insert(
-            "@context".to_string(),
-            Value::Array(vec![
-                json!("https://www.w3.org/2018/credentials/v1"),
-                json!("http://data.europa.eu/snb/model/context/edc-ap"),
-            ]),
-        );
-    } else if state.mapping.output_format() == "OBv3" {
-        let output_obv3 = state.repository.get_mut("OBv3").unwrap().as_object_mut().unwrap();
-        output_obv3.insert(
-            "@context".to_string(),
-            Value::Array(vec![
-                json!("https://www.w3.org/ns/credentials/v2"),
-                json!("https://purl.imsglobal.org/spec/ob/v3p0/context-3.0.3.json"),
-            ]),
-        );
+/// Enter the fixed values of the output format, its '@context' and the fixed fields demanded by its json-schema like
+/// 'credentialSchema', as declared in the format registry, into the output credential
+pub fn enter_fixed_values(repository: &mut Repository, mapping: Mapping) {
+    let Some(output) = repository
+        .get_mut(&mapping.output_format())
+        .and_then(Value::as_object_mut)
+    else {
+        return;
+    };
+
+    if let Some(context) = &mapping.output.context {
+        output.insert("@context".to_string(), context.clone());
     }
-}
-
-/// Enter fixed values into '@context' field, as demanded by the respective json-schema
-fn enter_fixed_schema_values(state: &mut AppState) {
-    if state.mapping.output_format() == "ELM" {
-        let output_elm = state.repository.get_mut("ELM").unwrap().as_object_mut().unwrap();
-        output_elm.insert(
-            "credentialSchema".to_string(),
-            Value::Array(vec![
-                json!({"id": "http://data.europa.eu/snb/model/ap/edc-generic-full","type": "ShaclValidator2017"}), 
-                json!({"id": "https://api-pilot.ebsi.eu/trusted-schemas-registry/v3/schemas/0x7ff3bc76bd5e37b3d29721b8698646a722a24a4f4ab0a0ba63d4bbbe0ef9758d",
-      "type": "JsonSchema"})]),
-        );
-    } else if state.mapping.output_format() == "OBv3" {
-        let output_obv3 = state.repository.get_mut("OBv3").unwrap().as_object_mut().unwrap();
-        output_obv3.insert(
-            "credentialSchema".to_string(),
-            Value::Array(vec![
-                json!({"id": "https://purl.imsglobal.org/spec/ob/v3p0/schema/json/ob_v3p0_endorsementcredential_schema.json", "type": "1EdTechJsonSchemaValidator2019"}),
-                json!({"id": "https://accrediter.edu/schema/endorsementcredential.json","type": "1EdTechJsonSchemaValidator2019"})]),
-        );
-    }
-}
-
-/// Enter fixed values into 'credentialProfile' field, as demanded by the respective json-schema
-fn enter_credential_profile_values(state: &mut AppState) {
-    if state.mapping.output_format() == "ELM" {
-        let output_elm = state.repository.get_mut("ELM").unwrap().as_object_mut().unwrap();
-        output_elm.insert(
-            "credentialProfiles".to_string(),
-            Value::Array(vec![
-                json!({"id": "http://data.europa.eu/snb/credential/e34929035b","type": "Concept",
-                "inScheme": {
-                  "id": "http://data.europa.eu/snb/credential/25831c2",
-                  "type": "ConceptScheme"
-                },
-                "prefLabel": {"en": ["Generic"]}
-                }),
-            ]),
-        );
-    } else if state.mapping.output_format() == "OBv3" {
-        let _output_obv3 = state.repository.get_mut("OBv3").unwrap().as_object_mut().unwrap();
+    for (field, value) in &mapping.output.fixed_fields {
+        output.insert(field.clone(), value.clone());
     }
 }
 
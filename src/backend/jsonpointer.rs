@@ -1,9 +1,9 @@
 use std::ops::Deref;
 
-use regex::Regex;
+use serde_json::{Map, Value};
 
+/// A JsonPath (RFC 9535) addressing a location in a credential, e.g. `$.credentialSubject.hasClaim[0]['@type']`.
 #[derive(Debug)]
-// TODO: add validation
 pub struct JsonPath(pub String);
 
 impl Deref for JsonPath {
@@ -14,6 +14,31 @@ impl Deref for JsonPath {
     }
 }
 
+impl JsonPath {
+    /// The node the path of a single location leads to, with a null value at that location, e.g.
+    /// `{"hasClaim": [null, {"title": null}]}` for `$.hasClaim[1].title`. Unlike a JsonPointer, names stay object
+    /// keys also when they're digit-only or `*`.
+    pub fn leaf_node(&self) -> Result<Value, String> {
+        let mut node = Value::Null;
+
+        for segment in parse_json_path(self)?.into_iter().rev() {
+            node = match segment {
+                Segment::Name(name) => Value::Object(Map::from_iter([(name, node)])),
+                Segment::Index(index) => {
+                    let mut elements = vec![Value::Null; index];
+                    elements.push(node);
+                    Value::Array(elements)
+                }
+                Segment::Wildcard => return Err("a wildcard addresses no single location".to_string()),
+            };
+        }
+
+        Ok(node)
+    }
+}
+
+/// A JsonPointer (RFC 6901) addressing a location in a credential, e.g. `/credentialSubject/hasClaim/0/@type`.
+/// A `*` token selects every element of an array.
 #[derive(Debug)]
 pub struct JsonPointer(pub String);
 
@@ -25,27 +50,344 @@ impl Deref for JsonPointer {
     }
 }
 
+/// A single step of a path, shared by the JsonPath and the JsonPointer of a location.
+#[derive(Debug, PartialEq)]
+enum Segment {
+    Name(String),
+    Index(usize),
+    Wildcard,
+}
+
 impl From<JsonPointer> for JsonPath {
+    /// Write the pointer as a normalised JsonPath: names are written in dot notation where possible and in bracket
+    /// notation otherwise, e.g. `['@context']`, array indices and wildcards as `[0]` and `[*]`.
+    /// A JsonPointer doesn't tell a digit-only name or a `*` name from an array index or a wildcard, such tokens are
+    /// taken as the latter. A `~` that isn't part of an escape is taken as is.
     fn from(val: JsonPointer) -> JsonPath {
-        JsonPath(format!("${}", val.replace('/', ".")))
+        let segments = val
+            .split('/')
+            .skip(1)
+            .map(|token| match token {
+                "*" => Segment::Wildcard,
+                token if is_array_index(token) => Segment::Index(token.parse().unwrap()),
+                token => Segment::Name(unescape_token(token).unwrap_or_else(|_| token.to_string())),
+            })
+            .collect();
+
+        write_json_path(segments)
     }
 }
 
 impl TryFrom<JsonPath> for JsonPointer {
     type Error = String;
 
+    /// Convert a JsonPath addressing a single location, or the elements of an array with `[*]`, into a JsonPointer.
+    /// Names are escaped following RFC 6901, so `~` becomes `~0` and `/` becomes `~1`.
+    /// Paths using recursive descent, filters, slices or unions can't be converted.
     fn try_from(value: JsonPath) -> Result<Self, Self::Error> {
-        Ok(JsonPointer({
-            let mut value = value.0.trim_start_matches('$').replace('.', "/");
+        let pointer = parse_json_path(&value)?
+            .into_iter()
+            .map(|segment| match segment {
+                Segment::Name(name) => format!("/{}", escape_token(&name)),
+                Segment::Index(index) => format!("/{}", index),
+                Segment::Wildcard => "/*".to_string(),
+            })
+            .collect();
+
+        Ok(JsonPointer(pointer))
+    }
+}
+
+/// Normalise a JsonPath addressing a single location, so names like `@context` are written in bracket notation as the
+/// JsonPath implementation finding the values requires. Other paths are returned as they are.
+/// Names stay names, also when they're digit-only or `*`, unlike when converting through a JsonPointer.
+pub fn normalise_json_path(path: &str) -> String {
+    match parse_json_path(path) {
+        Ok(segments) => write_json_path(segments).0,
+        Err(_) => path.to_string(),
+    }
+}
+
+/// Write the segments as a normalised JsonPath, see `From<JsonPointer> for JsonPath`.
+fn write_json_path(segments: Vec<Segment>) -> JsonPath {
+    let mut path = "$".to_string();
+
+    for segment in segments {
+        match segment {
+            Segment::Name(name) if is_shorthand(&name) => path.push_str(&format!(".{}", name)),
+            Segment::Name(name) => path.push_str(&format!("[{}]", quote(&name))),
+            Segment::Index(index) => path.push_str(&format!("[{}]", index)),
+            Segment::Wildcard => path.push_str("[*]"),
+        }
+    }
+
+    JsonPath(path)
+}
+
+/// Escape a name for use as a JsonPointer token.
+pub fn escape_token(name: &str) -> String {
+    name.replace('~', "~0").replace('/', "~1")
+}
+
+/// The name escaped in a JsonPointer token, failing on a `~` that isn't followed by `0` or `1`.
+pub fn unescape_token(token: &str) -> Result<String, String> {
+    let mut name = String::new();
+    let mut chars = token.chars();
+
+    while let Some(char) = chars.next() {
+        match char {
+            '~' => match chars.next() {
+                Some('0') => name.push('~'),
+                Some('1') => name.push('/'),
+                _ => return Err(format!("`~` in `{}` must be followed by `0` or `1`", token)),
+            },
+            char => name.push(char),
+        }
+    }
+
+    Ok(name)
+}
+
+/// Whether a JsonPointer token is an array index: `0` or digits without a leading zero.
+pub fn is_array_index(token: &str) -> bool {
+    !token.is_empty() && token.chars().all(|c| c.is_ascii_digit()) && (token == "0" || !token.starts_with('0'))
+}
+
+/// Whether a name can be written in dot notation, by RFC 9535 and by the JsonPath implementation finding the values.
+fn is_shorthand(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|first| first.is_ascii_alphabetic() || first == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Quote a name for bracket notation. Single quotes are preferred as in RFC 9535, double quotes are used to avoid
+/// escapes when the name contains a single quote.
+fn quote(name: &str) -> String {
+    if !name.contains(['\'', '\\']) {
+        format!("'{}'", name)
+    } else if !name.contains(['"', '\\']) {
+        format!("\"{}\"", name)
+    } else {
+        format!("'{}'", name.replace('\\', "\\\\").replace('\'', "\\'"))
+    }
+}
+
+/// Parse a JsonPath into its segments. Names in dot notation can hold any character but `.` and `[`, so paths like
+/// `$.@context` are accepted as well.
+fn parse_json_path(path: &str) -> Result<Vec<Segment>, String> {
+    let path = path.trim();
+    let unsupported = |what: &str| format!("{} addresses no single location", what);
+    let unexpected = |index: usize| match path[index..].chars().next() {
+        Some(char) => format!("unexpected `{}` at position {}", char, index),
+        None => "unexpected end of the path".to_string(),
+    };
+    // The position of the rest of the path in the path, for the error messages
+    let position = |rest: &str| path.len() - rest.len();
 
-            // Check if the JsonPath contains arrays and convert them as well
-            let regx = Regex::new(r"\[(\d+)\]").unwrap();
+    let Some(mut rest) = path.strip_prefix('$') else {
+        return Err("a JsonPath starts with `$`".to_string());
+    };
+    let mut segments = Vec::new();
 
-            while regx.is_match(&value) {
-                value = regx.replace(&value, "/$1").to_string();
+    while let Some(first) = rest.chars().next() {
+        match first {
+            '.' if rest.starts_with("..") => return Err(unsupported("recursive descent")),
+            '.' if rest.starts_with(".*") => {
+                segments.push(Segment::Wildcard);
+                rest = &rest[2..];
             }
+            '.' => {
+                let end = rest[1..].find(['.', '[']).map_or(rest.len(), |end| end + 1);
+                if end == 1 {
+                    return Err(unexpected(position(rest) + 1));
+                }
+                segments.push(Segment::Name(rest[1..end].to_string()));
+                rest = &rest[end..];
+            }
+            '[' => {
+                let selector = rest[1..].trim_start();
+                let (segment, after) = match selector.chars().next() {
+                    Some('*') => (Segment::Wildcard, &selector[1..]),
+                    Some('\'' | '"') => {
+                        let (name, after) = parse_quoted(selector).ok_or_else(|| unexpected(position(selector)))?;
+                        (Segment::Name(name), after)
+                    }
+                    Some('0'..='9') => {
+                        let end = selector.find(|c: char| !c.is_ascii_digit()).unwrap_or(selector.len());
+                        if !is_array_index(&selector[..end]) {
+                            return Err(format!("array index `{}` has a leading zero", &selector[..end]));
+                        }
+                        let index = selector[..end].parse().map_err(|_| unexpected(position(selector)))?;
+                        (Segment::Index(index), &selector[end..])
+                    }
+                    Some('?') => return Err(unsupported("a filter")),
+                    Some('-') => return Err(unsupported("a negative array index")),
+                    Some(':') => return Err(unsupported("a slice")),
+                    _ => return Err(unexpected(position(selector))),
+                };
+
+                let after = after.trim_start();
+                match after.chars().next() {
+                    Some(']') => {}
+                    Some(',') => return Err(unsupported("a union")),
+                    Some(':') => return Err(unsupported("a slice")),
+                    _ => return Err(unexpected(position(after))),
+                }
+                segments.push(segment);
+                rest = &after[1..];
+            }
+            _ => return Err(unexpected(position(rest))),
+        }
+    }
+
+    Ok(segments)
+}
+
+/// Parse a quoted name of bracket notation, returning the name and the rest of the path after its closing quote.
+fn parse_quoted(input: &str) -> Option<(String, &str)> {
+    let mut chars = input.char_indices();
+    let (_, quote) = chars.next()?;
+    let mut name = String::new();
+
+    while let Some((index, char)) = chars.next() {
+        match char {
+            char if char == quote => return Some((name, &input[index + 1..])),
+            '\\' => {
+                let (_, escaped) = chars.next()?;
+                let unescaped = match escaped {
+                    'b' => '\u{8}',
+                    'f' => '\u{c}',
+                    'n' => '\n',
+                    'r' => '\r',
+                    't' => '\t',
+                    'u' => {
+                        let hex: String = (0..4).filter_map(|_| chars.next().map(|(_, c)| c)).collect();
+                        char::from_u32(u32::from_str_radix(&hex, 16).ok()?)?
+                    }
+                    '\'' | '"' | '\\' | '/' => escaped,
+                    _ => return None,
+                };
+                name.push(unescaped);
+            }
+            char => name.push(char),
+        }
+    }
+
+    None
+}
+
+/// Resolve a JsonPointer which may contain wildcard `*` segments.
+/// Without wildcards this is the same as `Value::pointer`, a wildcard collects the values of all array elements.
+pub fn resolve_pointer(value: &Value, pointer: &str) -> Option<Value> {
+    match pointer.split_once("/*") {
+        None => value.pointer(pointer).cloned(),
+        Some((array_pointer, element_pointer)) => {
+            let elements = value.pointer(array_pointer)?.as_array()?;
+
+            Some(Value::Array(
+                elements
+                    .iter()
+                    .filter_map(|element| resolve_pointer(element, element_pointer))
+                    .collect(),
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn pointer(path: &str) -> String {
+        JsonPointer::try_from(JsonPath(path.to_string())).unwrap().0
+    }
+
+    fn path(pointer: &str) -> String {
+        JsonPath::from(JsonPointer(pointer.to_string())).0
+    }
+
+    #[test]
+    fn converts_json_ld_keywords() {
+        assert_eq!(pointer("$['@context']"), "/@context");
+        assert_eq!(pointer("$.@context"), "/@context");
+        assert_eq!(pointer("$.credentialSubject['@type']"), "/credentialSubject/@type");
+        assert_eq!(path("/@context"), "$['@context']");
+        assert_eq!(path("/credentialSubject/@type"), "$.credentialSubject['@type']");
+    }
+
+    #[test]
+    fn escapes_names_following_rfc_6901() {
+        assert_eq!(pointer("$['http://schema.org/name']"), "/http:~1~1schema.org~1name");
+        assert_eq!(pointer("$['a~b']"), "/a~0b");
+        assert_eq!(pointer("$['~1']"), "/~01");
+        assert_eq!(path("/http:~1~1schema.org~1name"), "$['http://schema.org/name']");
+        assert_eq!(path("/a~0b"), "$['a~b']");
+        assert_eq!(path("/~01"), "$['~1']");
+    }
+
+    #[test]
+    fn round_trips_names_through_a_pointer() {
+        for json_path in [
+            "$['@context']",
+            "$.credentialSubject['@type']",
+            "$['http://schema.org/name']",
+            "$['a~b']",
+            "$['a/b']",
+            "$['a.b']",
+            "$['a b']",
+            "$[\"it's\"]",
+            "$.hasClaim[0].title",
+            "$.hasClaim[*].title",
+        ] {
+            assert_eq!(path(&pointer(json_path)), json_path);
+        }
+    }
+
+    #[test]
+    fn normalises_paths_keeping_the_kind_of_segments() {
+        assert_eq!(normalise_json_path("$.@context"), "$['@context']");
+        assert_eq!(normalise_json_path("$[\"@type\"]"), "$['@type']");
+        assert_eq!(normalise_json_path("$['123']"), "$['123']");
+        assert_eq!(normalise_json_path("$['*']"), "$['*']");
+        assert_eq!(normalise_json_path("$.scores[123]"), "$.scores[123]");
+        assert_eq!(normalise_json_path("$.scores.*"), "$.scores[*]");
+        assert_eq!(normalise_json_path("$['a.b']['a b']"), "$['a.b']['a b']");
+        assert_eq!(normalise_json_path("$..name"), "$..name");
+    }
+
+    #[test]
+    fn takes_digit_only_and_star_tokens_as_indices_and_wildcards() {
+        assert_eq!(pointer("$['123']"), "/123");
+        assert_eq!(pointer("$['*']"), "/*");
+        assert_eq!(path("/123"), "$[123]");
+        assert_eq!(path("/*"), "$[*]");
+        assert_eq!(path("/0123"), "$['0123']");
+    }
+
+    #[test]
+    fn builds_the_node_of_a_path_keeping_names() {
+        let leaf_node = |path: &str| JsonPath(path.to_string()).leaf_node().unwrap();
+
+        assert_eq!(
+            leaf_node("$.hasClaim[1].title"),
+            json!({ "hasClaim": [null, { "title": null }] })
+        );
+        assert_eq!(leaf_node("$['123']['*']"), json!({ "123": { "*": null } }));
+        assert_eq!(leaf_node("$['@context']"), json!({ "@context": null }));
+        assert!(JsonPath("$.hasClaim[*]".to_string()).leaf_node().is_err());
+    }
 
-            value
-        }))
+    #[test]
+    fn rejects_paths_addressing_several_locations() {
+        for json_path in ["$..name", "$.a[?(@.b)]", "$.a[0:2]", "$.a[0,1]", "$.a[-1]", "name"] {
+            assert!(
+                JsonPointer::try_from(JsonPath(json_path.to_string())).is_err(),
+                "{}",
+                json_path
+            );
+        }
     }
 }
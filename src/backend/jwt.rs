@@ -0,0 +1,619 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use ring::{
+    hmac,
+    rand::SystemRandom,
+    rsa::KeyPairComponents,
+    signature::{
+        EcdsaKeyPair, Ed25519KeyPair, RsaKeyPair, RsaPublicKeyComponents, UnparsedPublicKey, ECDSA_P256_SHA256_FIXED,
+        ECDSA_P256_SHA256_FIXED_SIGNING, ED25519, RSA_PKCS1_2048_8192_SHA256, RSA_PKCS1_SHA256,
+    },
+};
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use std::{fmt, fs::File, io::BufReader};
+
+use crate::backend::{
+    date_time::{date_time_to_timestamp, normalise_timestamp, DateTimeFormat},
+    error::ConversionError,
+};
+
+/// The context of version 1.1 of the Verifiable Credentials Data Model, whose credentials are dated with
+/// `issuanceDate` and `expirationDate` instead of `validFrom` and `validUntil`.
+const VCDM_1_CONTEXT: &str = "https://www.w3.org/2018/credentials/v1";
+
+/// How the headless cli unwraps JWT inputs and wraps its outputs.
+#[derive(Debug, Clone, Default)]
+pub struct JwtOptions {
+    /// Verify the signature of the JWT and JWS inputs with this key, without it the signature isn't verified.
+    pub verify_key: Option<Jwk>,
+    /// Write the output credential as a JWT, see `wrap_credential`.
+    pub wrap_output: bool,
+    /// Sign the output JWT with this key, without it the JWT is unsecured.
+    pub signing_key: Option<Jwk>,
+}
+
+/// The JWS a credential was secured with, as a compact JWT or JWS or in the JSON serialization of a JWS.
+#[derive(Debug, Clone)]
+pub struct Envelope {
+    /// The protected header, e.g. its `alg` and `kid`.
+    pub header: Value,
+    /// The payload, the JWT claims for a VC-JWT.
+    pub payload: Value,
+    /// The header and payload as they were signed.
+    signing_input: String,
+    signature: Vec<u8>,
+}
+
+/// A key in the JSON Web Key format, read from a local file. Only the fields of the supported key types are read.
+/// Its `Debug` output leaves out the private key material, so a signing key can't end up in the logs.
+#[derive(Clone, Deserialize)]
+pub struct Jwk {
+    /// `oct` for HS256, `RSA` for RS256, `EC` with the curve `P-256` for ES256 or `OKP` with the curve `Ed25519` for
+    /// EdDSA.
+    pub kty: String,
+    pub crv: Option<String>,
+    pub kid: Option<String>,
+    /// The symmetric key of an `oct` key.
+    k: Option<String>,
+    x: Option<String>,
+    y: Option<String>,
+    d: Option<String>,
+    n: Option<String>,
+    e: Option<String>,
+    p: Option<String>,
+    q: Option<String>,
+    dp: Option<String>,
+    dq: Option<String>,
+    qi: Option<String>,
+}
+
+impl fmt::Debug for Jwk {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let redacted = |field: &Option<String>| field.as_ref().map(|_| "<redacted>");
+
+        f.debug_struct("Jwk")
+            .field("kty", &self.kty)
+            .field("crv", &self.crv)
+            .field("kid", &self.kid)
+            .field("k", &redacted(&self.k))
+            .field("x", &self.x)
+            .field("y", &self.y)
+            .field("d", &redacted(&self.d))
+            .field("n", &self.n)
+            .field("e", &self.e)
+            .field("p", &redacted(&self.p))
+            .field("q", &redacted(&self.q))
+            .field("dp", &redacted(&self.dp))
+            .field("dq", &redacted(&self.dq))
+            .field("qi", &redacted(&self.qi))
+            .finish()
+    }
+}
+
+impl Jwk {
+    /// Read a key from a JWK json file.
+    pub fn load(path: &str) -> Result<Jwk, ConversionError> {
+        let invalid = |message: String| ConversionError::InvalidArguments(format!("{}: {}", path, message));
+
+        let file = File::open(path).map_err(|error| invalid(error.to_string()))?;
+        let key: Jwk = serde_json::from_reader(BufReader::new(file)).map_err(|error| invalid(error.to_string()))?;
+        key.algorithm().map_err(invalid)?;
+
+        Ok(key)
+    }
+
+    /// The JWS algorithm of the key, e.g. `ES256`.
+    pub fn algorithm(&self) -> Result<&'static str, String> {
+        match (self.kty.as_str(), self.crv.as_deref()) {
+            ("oct", _) => Ok("HS256"),
+            ("RSA", _) => Ok("RS256"),
+            ("EC", Some("P-256")) => Ok("ES256"),
+            ("OKP", Some("Ed25519")) => Ok("EdDSA"),
+            (kty, crv) => Err(format!(
+                "unsupported key type {}{}, the supported keys are oct, RSA, EC P-256 and OKP Ed25519",
+                kty,
+                crv.map(|crv| format!(" {}", crv)).unwrap_or_default()
+            )),
+        }
+    }
+
+    /// A base64url encoded field of the key, decoded.
+    fn field(&self, name: &str, value: &Option<String>) -> Result<Vec<u8>, String> {
+        let value = value
+            .as_deref()
+            .ok_or_else(|| format!("the {} key has no `{}`", self.kty, name))?;
+        decode_segment(value).map_err(|error| format!("the `{}` of the key is invalid: {}", name, error))
+    }
+
+    /// The uncompressed point of an `EC` key.
+    fn ec_point(&self) -> Result<Vec<u8>, String> {
+        Ok([vec![0x04], self.field("x", &self.x)?, self.field("y", &self.y)?].concat())
+    }
+
+    fn sign(&self, message: &[u8]) -> Result<Vec<u8>, String> {
+        let rejected = |error: ring::error::KeyRejected| format!("the key is rejected: {}", error);
+        let rng = SystemRandom::new();
+
+        match self.algorithm()? {
+            "HS256" => {
+                let key = hmac::Key::new(hmac::HMAC_SHA256, &self.field("k", &self.k)?);
+                Ok(hmac::sign(&key, message).as_ref().to_vec())
+            }
+            "RS256" => {
+                let key_pair = RsaKeyPair::from_components(&KeyPairComponents {
+                    public_key: RsaPublicKeyComponents {
+                        n: self.field("n", &self.n)?,
+                        e: self.field("e", &self.e)?,
+                    },
+                    d: self.field("d", &self.d)?,
+                    p: self.field("p", &self.p)?,
+                    q: self.field("q", &self.q)?,
+                    dP: self.field("dp", &self.dp)?,
+                    dQ: self.field("dq", &self.dq)?,
+                    qInv: self.field("qi", &self.qi)?,
+                })
+                .map_err(rejected)?;
+                let mut signature = vec![0; key_pair.public().modulus_len()];
+                key_pair
+                    .sign(&RSA_PKCS1_SHA256, &rng, message, &mut signature)
+                    .map_err(|_| "the message can't be signed".to_string())?;
+                Ok(signature)
+            }
+            "ES256" => {
+                let key_pair = EcdsaKeyPair::from_private_key_and_public_key(
+                    &ECDSA_P256_SHA256_FIXED_SIGNING,
+                    &self.field("d", &self.d)?,
+                    &self.ec_point()?,
+                    &rng,
+                )
+                .map_err(rejected)?;
+                let signature = key_pair
+                    .sign(&rng, message)
+                    .map_err(|_| "the message can't be signed".to_string())?;
+                Ok(signature.as_ref().to_vec())
+            }
+            _ => {
+                let key_pair =
+                    Ed25519KeyPair::from_seed_and_public_key(&self.field("d", &self.d)?, &self.field("x", &self.x)?)
+                        .map_err(rejected)?;
+                Ok(key_pair.sign(message).as_ref().to_vec())
+            }
+        }
+    }
+
+    fn verify(&self, message: &[u8], signature: &[u8]) -> Result<bool, String> {
+        Ok(match self.algorithm()? {
+            "HS256" => {
+                let key = hmac::Key::new(hmac::HMAC_SHA256, &self.field("k", &self.k)?);
+                hmac::verify(&key, message, signature).is_ok()
+            }
+            "RS256" => RsaPublicKeyComponents {
+                n: self.field("n", &self.n)?,
+                e: self.field("e", &self.e)?,
+            }
+            .verify(&RSA_PKCS1_2048_8192_SHA256, message, signature)
+            .is_ok(),
+            "ES256" => UnparsedPublicKey::new(&ECDSA_P256_SHA256_FIXED, self.ec_point()?)
+                .verify(message, signature)
+                .is_ok(),
+            _ => UnparsedPublicKey::new(&ED25519, self.field("x", &self.x)?)
+                .verify(message, signature)
+                .is_ok(),
+        })
+    }
+}
+
+impl Envelope {
+    /// Verify the signature with a local key, no key is ever fetched.
+    ///
+    /// # Returns
+    /// - `Ok(())`: If the signature was made with the key.
+    /// - `Err(ConversionError)`: If the JWS is unsecured, its `alg` isn't the algorithm of the key or the signature
+    ///   doesn't match.
+    pub fn verify(&self, key: &Jwk) -> Result<(), ConversionError> {
+        let invalid = |message: String| ConversionError::InvalidInput(message);
+
+        let algorithm = key.algorithm().map_err(ConversionError::InvalidArguments)?;
+        match self.header.get("alg").and_then(Value::as_str) {
+            Some(alg) if alg == algorithm => {}
+            Some("none") | None => return Err(invalid("the JWT is unsecured, it has no signature".to_string())),
+            Some(alg) => {
+                return Err(invalid(format!(
+                    "the JWT is signed with {}, the verification key is for {}",
+                    alg, algorithm
+                )))
+            }
+        }
+
+        match key.verify(self.signing_input.as_bytes(), &self.signature) {
+            Ok(true) => Ok(()),
+            Ok(false) => Err(invalid("the signature of the JWT doesn't match the key".to_string())),
+            Err(message) => Err(ConversionError::InvalidArguments(message)),
+        }
+    }
+
+    /// The credential secured by the JWS: the `vc` claim of a VC-JWT or else the payload itself.
+    /// Like VC-JWT allows, the `jti`, `iss`, `sub`, `nbf` and `exp` claims stand in for the `id`, issuer, subject id
+    /// and dates the credential doesn't have itself.
+    pub fn credential(&self) -> Value {
+        let mut credential = match self.payload.get("vc") {
+            Some(vc @ Value::Object(_)) => vc.clone(),
+            _ => self.payload.clone(),
+        };
+        let Some(fields) = credential.as_object_mut() else {
+            return credential;
+        };
+
+        let vcdm_1 = fields
+            .get("@context")
+            .is_some_and(|context| context == VCDM_1_CONTEXT || context.get(0).is_some_and(|c| c == VCDM_1_CONTEXT));
+        let (valid_from, valid_until) = match vcdm_1 {
+            true => ("issuanceDate", "expirationDate"),
+            false => ("validFrom", "validUntil"),
+        };
+
+        let claim = |name: &str| self.payload.get(name).filter(|value| !value.is_null());
+        let date_claim = |name: &str| {
+            claim(name)
+                .and_then(Value::as_i64)
+                .and_then(|timestamp| normalise_timestamp(timestamp, DateTimeFormat::DateTime))
+                .map(Value::String)
+        };
+        for (field, value) in [
+            ("id", claim("jti").cloned()),
+            // As a profile, the issuer is an object in most formats
+            ("issuer", claim("iss").map(|iss| json!({ "id": iss }))),
+            (valid_from, date_claim("nbf")),
+            (valid_until, date_claim("exp")),
+        ] {
+            if let Some(value) = value.filter(|_| !fields.contains_key(field)) {
+                fields.insert(field.to_string(), value);
+            }
+        }
+        if let (Some(subject), Some(sub)) = (
+            fields.get_mut("credentialSubject").and_then(Value::as_object_mut),
+            claim("sub"),
+        ) {
+            subject.entry("id").or_insert_with(|| sub.clone());
+        }
+
+        credential
+    }
+}
+
+/// Read a credential that may be secured with a JWS: a compact JWT or JWS, possibly as a json string, or the JSON
+/// serialization of a JWS. Any other json is the credential itself.
+///
+/// # Returns
+/// - `Ok((Value, Option<Envelope>))`: The credential and the JWS it was secured with, if any.
+/// - `Err(ConversionError)`: If the input is neither json nor a JWT, or the JWS can't be decoded.
+pub fn read_credential(input: &str) -> Result<(Value, Option<Envelope>), ConversionError> {
+    let input = input.trim();
+    if is_compact(input) {
+        let envelope = decode_compact(input)?;
+        return Ok((envelope.credential(), Some(envelope)));
+    }
+
+    let value: Value = serde_json::from_str(input).map_err(|error| ConversionError::InvalidInput(error.to_string()))?;
+    let envelope = match &value {
+        Value::String(token) if is_compact(token) => decode_compact(token)?,
+        Value::Object(fields) if fields.get("payload").is_some_and(Value::is_string) => {
+            decode_json_serialization(fields)?
+        }
+        _ => return Ok((value, None)),
+    };
+
+    Ok((envelope.credential(), Some(envelope)))
+}
+
+/// Secure a credential in a compact JWT with the credential as its `vc` claim, mirrored in the registered claims:
+/// `iss` is the issuer, `sub` the id of the subject, `nbf` and `exp` the dates the credential is valid from and
+/// until and `jti` its id. The JWT is signed with the key, or unsecured with the `alg` `none` without one.
+pub fn wrap_credential(credential: &Value, key: Option<&Jwk>) -> Result<String, ConversionError> {
+    let invalid_key = |message: String| ConversionError::InvalidArguments(message);
+
+    let mut header = json!({ "alg": "none", "typ": "JWT" });
+    if let Some(key) = key {
+        header["alg"] = json!(key.algorithm().map_err(invalid_key)?);
+        if let Some(kid) = &key.kid {
+            header["kid"] = json!(kid);
+        }
+    }
+
+    let field = |names: &[&str]| names.iter().find_map(|name| credential.get(name));
+    let timestamp = |names: &[&str]| field(names).and_then(Value::as_str).and_then(date_time_to_timestamp);
+    let subject = match credential.get("credentialSubject") {
+        Some(Value::Array(subjects)) => subjects.first(),
+        subject => subject,
+    };
+
+    let mut claims = Map::new();
+    for (claim, value) in [
+        (
+            "iss",
+            field(&["issuer"])
+                .and_then(|issuer| issuer.get("id").or(Some(issuer)))
+                .cloned(),
+        ),
+        ("sub", subject.and_then(|subject| subject.get("id")).cloned()),
+        ("nbf", timestamp(&["validFrom", "issuanceDate"]).map(Value::from)),
+        ("exp", timestamp(&["validUntil", "expirationDate"]).map(Value::from)),
+        ("jti", field(&["id"]).cloned()),
+    ] {
+        if let Some(value) = value.filter(|value| value.is_string() || value.is_number()) {
+            claims.insert(claim.to_string(), value);
+        }
+    }
+    claims.insert("vc".to_string(), credential.clone());
+
+    let signing_input = format!("{}.{}", encode_json(&header)?, encode_json(&Value::Object(claims))?);
+    let signature = match key {
+        Some(key) => URL_SAFE_NO_PAD.encode(key.sign(signing_input.as_bytes()).map_err(invalid_key)?),
+        None => String::new(),
+    };
+
+    Ok(format!("{}.{}", signing_input, signature))
+}
+
+/// Whether the input looks like a compact JWT or JWS: three base64url segments separated by dots, the signature of
+/// an unsecured JWT being empty.
+fn is_compact(input: &str) -> bool {
+    let segments: Vec<&str> = input.split('.').collect();
+    let is_base64url = |segment: &str| {
+        segment
+            .bytes()
+            .all(|byte| byte.is_ascii_alphanumeric() || byte == b'-' || byte == b'_')
+    };
+
+    segments.len() == 3
+        && !segments[0].is_empty()
+        && !segments[1].is_empty()
+        && segments.iter().all(|segment| is_base64url(segment))
+}
+
+fn decode_compact(token: &str) -> Result<Envelope, ConversionError> {
+    let segments: Vec<&str> = token.split('.').collect();
+    decode_envelope(segments[0], segments[1], segments[2])
+}
+
+/// Decode the flattened JSON serialization of a JWS, or the first signature of the general JSON serialization.
+fn decode_json_serialization(fields: &Map<String, Value>) -> Result<Envelope, ConversionError> {
+    let signature = match fields.get("signatures").and_then(|signatures| signatures.get(0)) {
+        Some(signature) => signature,
+        None => &Value::Object(fields.clone()),
+    };
+    let segment = |value: Option<&Value>| value.and_then(Value::as_str).unwrap_or_default().to_string();
+
+    decode_envelope(
+        &segment(signature.get("protected")),
+        &segment(fields.get("payload")),
+        &segment(signature.get("signature")),
+    )
+}
+
+fn decode_envelope(header: &str, payload: &str, signature: &str) -> Result<Envelope, ConversionError> {
+    let invalid = |part: &str, message: String| ConversionError::InvalidInput(format!("the JWT {} {}", part, message));
+    let decode_json = |part: &str, segment: &str| -> Result<Value, ConversionError> {
+        let bytes = decode_segment(segment).map_err(|error| invalid(part, format!("isn't base64url: {}", error)))?;
+        serde_json::from_slice(&bytes).map_err(|error| invalid(part, format!("isn't json: {}", error)))
+    };
+
+    Ok(Envelope {
+        header: decode_json("header", header)?,
+        payload: decode_json("payload", payload)?,
+        signing_input: format!("{}.{}", header, payload),
+        signature: decode_segment(signature)
+            .map_err(|error| invalid("signature", format!("isn't base64url: {}", error)))?,
+    })
+}
+
+/// Decode a base64url segment, tolerating the padding JWS doesn't allow but some encoders add.
+fn decode_segment(segment: &str) -> Result<Vec<u8>, base64::DecodeError> {
+    URL_SAFE_NO_PAD.decode(segment.trim_end_matches('='))
+}
+
+fn encode_json(value: &Value) -> Result<String, ConversionError> {
+    serde_json::to_vec(value)
+        .map(|bytes| URL_SAFE_NO_PAD.encode(bytes))
+        .map_err(|error| ConversionError::Output(error.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        backend::convert::{convert, load_mapping},
+        state::Mapping,
+    };
+
+    fn hs256_key(secret: &str) -> Jwk {
+        serde_json::from_value(json!({ "kty": "oct", "kid": "key-1", "k": URL_SAFE_NO_PAD.encode(secret) })).unwrap()
+    }
+
+    /// The Ed25519 key of RFC 8037, appendix A.1.
+    fn ed25519_key() -> Jwk {
+        serde_json::from_value(json!({
+            "kty": "OKP",
+            "crv": "Ed25519",
+            "d": "nWGxne_9WmC6hEr0kuwsxERJxWl7MmkZcDusAxyuf2A",
+            "x": "11qYAYKxCrfVS_7TyWQHOg7hcvPapiMlrwIaaPcHURo"
+        }))
+        .unwrap()
+    }
+
+    fn credential() -> Value {
+        json!({
+            "@context": ["https://www.w3.org/ns/credentials/v2"],
+            "id": "urn:uuid:91537dba-56cb-11ec-bf63-0242ac130002",
+            "type": ["VerifiableCredential"],
+            "issuer": { "id": "did:example:issuer" },
+            "validFrom": "2024-01-01T00:00:00Z",
+            "credentialSubject": { "id": "did:example:subject" }
+        })
+    }
+
+    #[test]
+    fn verifies_a_signed_credential() {
+        for key in [hs256_key("a secret of the issuer"), ed25519_key()] {
+            let token = wrap_credential(&credential(), Some(&key)).unwrap();
+
+            let (read, envelope) = read_credential(&token).unwrap();
+            let envelope = envelope.unwrap();
+
+            assert_eq!(read, credential());
+            assert_eq!(envelope.header["alg"], key.algorithm().unwrap());
+            assert_eq!(envelope.payload["iss"], "did:example:issuer");
+            assert_eq!(envelope.payload["sub"], "did:example:subject");
+            assert_eq!(envelope.payload["nbf"], 1704067200);
+            assert_eq!(envelope.payload["jti"], "urn:uuid:91537dba-56cb-11ec-bf63-0242ac130002");
+            envelope.verify(&key).unwrap();
+        }
+    }
+
+    #[test]
+    fn rejects_a_tampered_payload() {
+        let key = ed25519_key();
+        let token = wrap_credential(&credential(), Some(&key)).unwrap();
+        let mut tampered = credential();
+        tampered["credentialSubject"]["id"] = json!("did:example:someone-else");
+        let payload = encode_json(&json!({ "vc": tampered })).unwrap();
+        let segments: Vec<&str> = token.split('.').collect();
+
+        let (read, envelope) = read_credential(&format!("{}.{}.{}", segments[0], payload, segments[2])).unwrap();
+
+        assert_eq!(read["credentialSubject"]["id"], "did:example:someone-else");
+        assert!(matches!(
+            envelope.unwrap().verify(&key),
+            Err(ConversionError::InvalidInput(message)) if message.contains("doesn't match")
+        ));
+    }
+
+    #[test]
+    fn rejects_a_signature_of_another_key() {
+        let token = wrap_credential(&credential(), Some(&hs256_key("another secret"))).unwrap();
+
+        let (_, envelope) = read_credential(&token).unwrap();
+
+        assert!(matches!(
+            envelope.unwrap().verify(&hs256_key("a secret of the issuer")),
+            Err(ConversionError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_an_unsecured_jwt_and_another_algorithm() {
+        let token = wrap_credential(&credential(), None).unwrap();
+        assert!(token.ends_with('.'));
+
+        let (read, envelope) = read_credential(&token).unwrap();
+        let envelope = envelope.unwrap();
+
+        assert_eq!(read, credential());
+        assert_eq!(envelope.header["alg"], "none");
+        assert!(matches!(
+            envelope.verify(&ed25519_key()),
+            Err(ConversionError::InvalidInput(message)) if message.contains("unsecured")
+        ));
+
+        let token = wrap_credential(&credential(), Some(&hs256_key("a secret of the issuer"))).unwrap();
+        let (_, envelope) = read_credential(&token).unwrap();
+        assert!(matches!(
+            envelope.unwrap().verify(&ed25519_key()),
+            Err(ConversionError::InvalidInput(message)) if message.contains("signed with HS256")
+        ));
+    }
+
+    #[test]
+    fn reads_the_serializations_of_a_credential() {
+        let (read, envelope) = read_credential(&credential().to_string()).unwrap();
+        assert_eq!(read, credential());
+        assert!(envelope.is_none());
+
+        let key = ed25519_key();
+        let token = wrap_credential(&credential(), Some(&key)).unwrap();
+        let (read, _) = read_credential(&json!(token).to_string()).unwrap();
+        assert_eq!(read, credential());
+
+        let segments: Vec<&str> = token.split('.').collect();
+        let flattened = json!({ "protected": segments[0], "payload": segments[1], "signature": segments[2] });
+        let (read, envelope) = read_credential(&flattened.to_string()).unwrap();
+        assert_eq!(read, credential());
+        envelope.unwrap().verify(&key).unwrap();
+
+        let general =
+            json!({ "payload": segments[1], "signatures": [{ "protected": segments[0], "signature": segments[2] }] });
+        let (_, envelope) = read_credential(&general.to_string()).unwrap();
+        envelope.unwrap().verify(&key).unwrap();
+
+        assert!(matches!(
+            read_credential("not a credential"),
+            Err(ConversionError::InvalidInput(_))
+        ));
+        assert!(matches!(
+            read_credential("e30.bm90IGpzb24.c2ln"),
+            Err(ConversionError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn takes_the_missing_fields_from_the_claims() {
+        let payload = json!({
+            "iss": "did:example:issuer",
+            "sub": "did:example:subject",
+            "jti": "urn:uuid:1",
+            "nbf": 1704067200,
+            "exp": 1735689600,
+            "vc": {
+                "@context": ["https://www.w3.org/2018/credentials/v1"],
+                "type": ["VerifiableCredential"],
+                "credentialSubject": {}
+            }
+        });
+        let token = format!(
+            "{}.{}.",
+            encode_json(&json!({ "alg": "none" })).unwrap(),
+            encode_json(&payload).unwrap()
+        );
+
+        let (read, _) = read_credential(&token).unwrap();
+
+        assert_eq!(read["id"], "urn:uuid:1");
+        assert_eq!(read["issuer"], json!({ "id": "did:example:issuer" }));
+        assert_eq!(read["issuanceDate"], "2024-01-01T00:00:00Z");
+        assert_eq!(read["expirationDate"], "2025-01-01T00:00:00Z");
+        assert_eq!(read["credentialSubject"]["id"], "did:example:subject");
+    }
+
+    #[test]
+    fn leaves_the_private_key_out_of_the_debug_output() {
+        let debug = format!("{:?}", ed25519_key());
+        assert!(!debug.contains("nWGxne_9WmC6hEr0kuwsxERJxWl7MmkZcDusAxyuf2A"));
+        assert!(debug.contains("11qYAYKxCrfVS_7TyWQHOg7hcvPapiMlrwIaaPcHURo"));
+        assert!(debug.contains("<redacted>"));
+
+        let debug = format!("{:?}", hs256_key("a secret of the issuer"));
+        assert!(!debug.contains(&URL_SAFE_NO_PAD.encode("a secret of the issuer")));
+    }
+
+    #[test]
+    fn converts_the_credential_of_a_vc_jwt() {
+        let token = std::fs::read_to_string("test/OBv3_example.jwt").unwrap();
+        let (credential, envelope) = read_credential(&token).unwrap();
+        assert_eq!(envelope.unwrap().header["alg"], "none");
+
+        let conversion = Mapping::find("OBv3", "W3CVC").unwrap();
+        let mapping = load_mapping(conversion.default_mapping().unwrap(), conversion).unwrap();
+        let output = convert(&credential, conversion, &mapping).unwrap();
+
+        assert_eq!(output.credential["id"], "http://1edtech.edu/credentials/3732");
+        assert_eq!(output.credential["issuer"]["id"], "https://1edtech.edu/issuers/565049");
+        assert_eq!(
+            output.credential["credentialSubject"]["id"],
+            "did:example:ebfeb1f712ebc6f1c276e12ec21"
+        );
+
+        let (rewrapped, envelope) = read_credential(&wrap_credential(&output.credential, None).unwrap()).unwrap();
+        assert_eq!(rewrapped, output.credential);
+        assert_eq!(envelope.unwrap().payload["jti"], "http://1edtech.edu/credentials/3732");
+    }
+}
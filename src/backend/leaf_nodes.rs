@@ -1,24 +1,49 @@
 use serde_json::{Map, Value};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+
+use crate::backend::jsonpointer::{escape_token, is_array_index, unescape_token};
 
 pub fn extract_leaf_nodes(json_object: &Value, path: String, result: &mut HashMap<String, Value>) {
     match json_object {
         Value::Object(map) => {
             for (key, value) in map {
                 let new_path = if path.is_empty() {
-                    key.clone()
+                    escape_token(key)
                 } else {
-                    format!("{}/{}", path, key)
+                    format!("{}/{}", path, escape_token(key))
                 };
                 extract_leaf_nodes(value, new_path, result);
             }
         }
+        Value::Array(array) if array.iter().any(Value::is_object) => {
+            extract_wildcard_leaf_nodes(array, &path, result);
+            result.insert(path, json_object.clone());
+        }
         _ => {
             result.insert(path, json_object.clone());
         }
     }
 }
 
+/// Add a wildcard leaf node `<path>/*/<key>` for every key found in the objects of the array,
+/// holding the values of that key for all elements, so the elements can be mapped element-wise.
+fn extract_wildcard_leaf_nodes(array: &[Value], path: &str, result: &mut HashMap<String, Value>) {
+    let mut wildcard_leaf_nodes: BTreeMap<String, Vec<Value>> = BTreeMap::new();
+
+    for element in array.iter().filter(|element| element.is_object()) {
+        let mut element_leaf_nodes = HashMap::new();
+        extract_leaf_nodes(element, String::new(), &mut element_leaf_nodes);
+
+        for (key, value) in element_leaf_nodes {
+            wildcard_leaf_nodes.entry(key).or_default().push(value);
+        }
+    }
+
+    for (key, values) in wildcard_leaf_nodes {
+        result.insert(format!("{}/*/{}", path, key), Value::Array(values));
+    }
+}
+
 pub fn get_leaf_nodes(json_object: Value) -> HashMap<String, Value> {
     let mut result = HashMap::new();
     extract_leaf_nodes(&json_object, String::new(), &mut result);
@@ -38,7 +63,7 @@ pub fn construct_leaf_node(path: &str) -> Value {
     // Iterate through the parts in reverse order to build the nested structure
     for part in parts.into_iter().rev() {
         // Check if the part is an array index
-        if part.chars().all(|c| c.is_numeric()) {
+        if is_array_index(part) {
             if let Ok(part_array_index) = part.parse::<usize>() {
                 let mut new_array = Vec::new();
                 for i in 0..part_array_index {
@@ -50,7 +75,7 @@ pub fn construct_leaf_node(path: &str) -> Value {
             }
         } else {
             let mut new_object = Map::new();
-            new_object.insert(part.to_string(), current_value);
+            new_object.insert(unescape_token(part).unwrap_or_else(|_| part.to_string()), current_value);
             current_value = Value::Object(new_object);
         }
     }
@@ -1,15 +1,27 @@
 pub mod base64_encode;
 pub mod candidate_value;
+pub mod clr;
+pub mod coercion;
+pub mod code_list;
+pub mod convert;
+pub mod date_time;
 pub mod desm_mapping;
 pub mod elm_mapping_helper;
+pub mod error;
+pub mod formats;
 pub mod getters_resolvers;
+pub mod handlers;
 pub mod headless_cli;
+pub mod id_generation;
 pub mod init_conversion;
 pub mod jsonpointer;
+pub mod jwt;
 pub mod leaf_nodes;
 pub mod logging;
 pub mod repository;
 pub mod routes;
+pub mod template;
 pub mod transformations;
+pub mod unconsumed;
 pub mod update_display;
 pub mod web;
@@ -1,30 +1,107 @@
 use crate::{
     backend::{
-        base64_encode::{create_display_parameter, image_to_elm_media_object},
-        elm_mapping_helper::{
-            address_to_location, assessment_type_to_specifiedby_assesment, create_learning_outcome_summary, credentialpoint_values_to_object, eqf_to_specifiedby_qualification, object_to_note_literal, title_to_specifiedby, transform_learning_setting, transform_alignment_to_learning_outcomes
-        },
-        jsonpointer::{JsonPath, JsonPointer},
+        error::{ConversionError, TransformationError},
+        handlers::get_handler,
+        id_generation::{generated_id, identifies_node},
+        jsonpointer::{normalise_json_path, unescape_token, JsonPath, JsonPointer},
         leaf_nodes::construct_leaf_node,
-        transformations::{DataLocation, DataTypeLocation, StringArrayValue, StringValue, Transformation},
+        template::{fill_template, load_template},
+        transformations::{
+            Condition, DataLocation, DataTypeLocation, EntryOptions, MergeStrategy, OneToOne, StringArrayValue,
+            StringValue, Transformation,
+        },
+        unconsumed::{unconsumed_fields, UnconsumedField},
     },
-    state::{AppState, Mapping},
+    state::{AppState, Mapping, MappingOptions},
     trace_dbg,
 };
-use jsonpath_rust::JsonPathFinder;
+use jsonpath_rust::{JsonPathFinder, JsonPathInst};
+use serde::Serialize;
 use serde_json::{json, Map, Value};
 //use tracing_subscriber::fmt::format;
 use std::{
-    collections::HashMap,
+    collections::{BTreeSet, HashMap},
     ops::{Deref, DerefMut},
+    str::FromStr,
 };
 
+/// The JsonPath wildcard selecting every element of an array.
+const WILDCARD: &str = "[*]";
+
+/// The source recorded for a field completed with the `default` of its transformation.
+pub const DEFAULT_SOURCE: &str = "default";
+
+/// The result of applying the transformations of a mapping.
+#[derive(Debug, Default, Clone)]
+pub struct AppliedMapping {
+    /// The fields completed by the mapping as (destination path, source path).
+    pub completed_fields: Vec<(String, String)>,
+    /// The transformations completed with one of their fallbacks or their default, instead of their source.
+    pub fallbacks: Vec<UsedFallback>,
+    /// The values written to the output credential, only recorded when the trace is enabled, see `enable_trace`.
+    pub trace: Vec<FieldTrace>,
+}
+
+/// A transformation whose source didn't resolve, so a fallback source or the default value was used.
+#[derive(Debug, Clone, Serialize)]
+pub struct UsedFallback {
+    /// The index of the transformation in the mapping.
+    pub index: usize,
+    pub destination: String,
+    /// The source path of the transformation that didn't resolve.
+    pub source: String,
+    /// The fallback path used instead, or `default` when the default value was written.
+    pub fallback: String,
+}
+
+/// A value written to the output credential by a mapping entry, so a wrong output field can be traced back to the
+/// entry and the source values it was made of.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldTrace {
+    /// The JsonPointer of the field in the output credential.
+    pub pointer: String,
+    /// The index of the mapping entry in the mapping.
+    pub index: usize,
+    /// The type of the transformation, e.g. `copy` or `lookup`.
+    pub transformation: String,
+    /// The source paths of the transformation, `default` when the default value of the entry was written.
+    pub sources: Vec<String>,
+    /// The values found at the source paths, `null` for a path without value, or the literal source value.
+    pub source_values: Vec<Value>,
+    /// The value written to the field, before it was merged with the value already there and before the ids of the
+    /// generated nodes are assigned.
+    pub value: Value,
+}
+
+/// The state of the trace while a mapping is applied.
+#[derive(Debug, Default, Clone)]
+struct Tracer {
+    /// The index and transformation type of the mapping entry being applied.
+    entry: (usize, String),
+    /// The source paths and values of the transformation being applied.
+    sources: Vec<String>,
+    source_values: Vec<Value>,
+    traces: Vec<FieldTrace>,
+}
+
 #[derive(Debug, Default, Clone)]
-pub struct Repository(HashMap<String, Value>);
+pub struct Repository {
+    credentials: HashMap<String, Value>,
+    /// Only set when the trace is enabled.
+    tracer: Option<Tracer>,
+    /// The source paths of the transformation being applied.
+    source_paths: Vec<String>,
+    /// The source paths of the transformations that wrote a value, see `unconsumed_fields`.
+    consumed_paths: BTreeSet<String>,
+    /// The fallbacks used by the transformation being applied as (destination path, source path, fallback), one for
+    /// each element completed with a fallback when it's applied element-wise.
+    used_fallbacks: Vec<(String, String, String)>,
+}
 
 impl DerefMut for Repository {
     fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
+        &mut self.credentials
     }
 }
 
@@ -32,602 +109,309 @@ impl Deref for Repository {
     type Target = HashMap<String, Value>;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.credentials
     }
 }
 
 impl From<HashMap<String, Value>> for Repository {
     fn from(map: HashMap<String, Value>) -> Self {
-        Self(map)
+        Self {
+            credentials: map,
+            ..Default::default()
+        }
     }
 }
 
 impl Repository {
+    /// Record every value written by `apply_mapping`, returned as the `trace` of the applied mapping.
+    pub fn enable_trace(&mut self) {
+        self.tracer.get_or_insert_with(Tracer::default);
+    }
+
+    /// The fields of the credential of `input_format` that none of the transformations applied so far used to write
+    /// a value, grouped by subtree.
+    pub fn unconsumed_fields(&self, input_format: &str) -> Vec<UnconsumedField> {
+        match self.get(input_format) {
+            Some(input) => unconsumed_fields(input, &self.consumed_paths),
+            None => Vec::new(),
+        }
+    }
+
+    /// Record the source paths of a transformation that was applied without `apply_transformation`, like a value
+    /// written by the TUI, as used.
+    pub fn consume_source_paths(&mut self, source_paths: impl IntoIterator<Item = String>) {
+        self.consumed_paths.extend(source_paths);
+    }
+
     pub fn apply_transformation(
         &mut self,
         transformation: Transformation,
         mapping: Mapping,
-    ) -> Result<Option<(String, String)>, &'static str> {
-        match transformation {
-            Transformation::OneToOne {
-                type_: transformation,
-                source:
-                    DataLocation {
-                        format: source_format,
-                        path: mut source_path,
-                    },
-                destination:
-                    DataLocation {
-                        format: destination_format,
-                        path: destination_path,
-                    },
-            } => {
-                if source_format != mapping.input_format() || destination_format != mapping.output_format() {
-                    return Ok(None);
-                }
-
-                let source_credential = self.get(&source_format).unwrap();
-
-                // custom code to handle the special character '@' in the source_path
-                if source_path == "$.@context" {
-                    source_path = r#"$["@context"]"#.to_string();
-                };
-
-                let finder = JsonPathFinder::from_str(&source_credential.to_string(), &source_path).unwrap();
-
-                let source_value = match finder.find().as_array() {
-                    // todo: still need to investigate other find() return types
-                    Some(array) => array.first().unwrap().clone(),
-                    None => {
-                        return Ok(None);
-                    }
-                };
-
-                let destination_credential = self.entry(destination_format).or_insert(json!({})); // or_insert should never happen, since repository is initialized with all formats, incl empty json value when not present.
-
-                let pointer = JsonPointer::try_from(JsonPath(destination_path.clone())).unwrap();
-
-                let mut leaf_node = construct_leaf_node(&pointer);
-
-                if let Some(value) = leaf_node.pointer_mut(&pointer) {
-                    *value = transformation.apply(source_value);
-                }
-
-                merge(destination_credential, leaf_node);
-
-                trace_dbg!("Successfully completed transformation");
-                Ok(Some((destination_path, source_path)))
-            }
-            Transformation::ManyToOne {
-                type_: transformation,
-                sources,
-                destination,
-            } => {
-                if sources.iter().any(|source| source.format != mapping.input_format())
-                    || destination.format != mapping.output_format()
-                {
-                    return Ok(None);
-                }
-
-                let source_values = sources
-                    .iter()
-                    .map(|source| {
-                        let source_credential = self.get(&source.format).unwrap();
-
-                        let finder = JsonPathFinder::from_str(&source_credential.to_string(), &source.path).unwrap();
-                        finder.find().as_array().unwrap().first().unwrap().clone()
-                    })
-                    .collect::<Vec<_>>();
-
-                let destination_credential = self.entry(destination.format).or_insert(json!({}));
-                let pointer = JsonPointer::try_from(JsonPath(destination.path.clone())).unwrap();
-
-                let mut leaf_node = construct_leaf_node(&pointer);
-
-                if let Some(value) = leaf_node.pointer_mut(&pointer) {
-                    *value = transformation.apply(source_values);
-                }
-
-                merge(destination_credential, leaf_node);
-
-                trace_dbg!("Successfully completed transformation");
-                Ok(None) // Todo: this is not implemented yet, so returns None for now
+    ) -> Result<Option<(String, String)>, TransformationError> {
+        match (transformation.source_path(), transformation.destination_path()) {
+            (Some(source_path), Some(destination_path))
+                if source_path.contains(WILDCARD) && destination_path.contains(WILDCARD) =>
+            {
+                self.apply_element_wise_transformation(transformation, mapping, source_path, destination_path)
             }
+            _ => self.apply_single_transformation(transformation, mapping),
+        }
+    }
 
-            Transformation::StringToOne {
-                type_: transformation,
-                source: StringValue { value: source_value },
-                destination:
-                    DataLocation {
-                        format: destination_format,
-                        path: destination_path,
-                    },
-            } => {
-                if destination_format != mapping.output_format() {
-                    return Ok(None);
-                }
-
-                let destination_credential = self.entry(destination_format).or_insert(json!({})); // or_insert should never happen, since repository is initialized with all formats, incl empty json value when not present.
-                let pointer = JsonPointer::try_from(JsonPath(destination_path)).unwrap();
-
-                let mut leaf_node = construct_leaf_node(&pointer);
-                if let Some(value) = leaf_node.pointer_mut(&pointer) {
-                    *value = transformation.apply(source_value);
-                }
-
-                merge(destination_credential, leaf_node);
-                Ok(None)
+    /// Apply a transformation with a wildcard `[*]` in both the source and the destination path to every element
+    /// of the source array, so `$.x[*].name` -> `$.y[*].title` writes one `title` for each element of `x`.
+    /// The first wildcard of both paths is replaced by the index of each element, nested wildcards are handled recursively.
+    /// The elements are the ones of the array of the first source path with a wildcard, a transformation combining
+    /// multiple sources can combine each element with sources outside the array.
+    fn apply_element_wise_transformation(
+        &mut self,
+        mut transformation: Transformation,
+        mapping: Mapping,
+        source_path: String,
+        destination_path: String,
+    ) -> Result<Option<(String, String)>, TransformationError> {
+        let array_path = transformation
+            .source_paths_mut()
+            .into_iter()
+            .find_map(|path| path.split_once(WILDCARD).map(|(array_path, _)| array_path.to_string()));
+        let Some(array_path) = array_path else {
+            return Ok(None);
+        };
+
+        let Some(Value::Array(elements)) = self.find_source_value(&mapping.input_format(), &array_path)? else {
+            return self.apply_element_wise_fallbacks(transformation, mapping, source_path);
+        };
+
+        let mut completed_field = None;
+        for index in 0..elements.len() {
+            let element_transformation = element_transformation(&transformation, index);
+            if self.apply_transformation(element_transformation, mapping)?.is_some() {
+                completed_field = Some((destination_path.clone(), source_path.clone()));
             }
+        }
 
-            Transformation::StringArrayToOne {
-                type_: transformation,
-                source: StringArrayValue { value: source_value },
-                destination:
-                    DataLocation {
-                        format: destination_format,
-                        path: destination_path,
-                    },
-            } => {
-                if destination_format != mapping.output_format() {
-                    return Ok(None);
-                }
+        Ok(completed_field)
+    }
 
-                let destination_credential = self.entry(destination_format).or_insert(json!({})); // or_insert should never happen, since repository is initialized with all formats, incl empty json value when not present.
-                let pointer = JsonPointer::try_from(JsonPath(destination_path)).unwrap();
-                let mut leaf_node = construct_leaf_node(&pointer);
-                if let Some(value) = leaf_node.pointer_mut(&pointer) {
-                    let json_value: Value = Value::Array(
-                        source_value
-                            .into_iter()
-                            .map(Value::String) // Convert each String into serde_json::Value::String
-                            .collect(),
-                    );
-                    *value = transformation.apply(json_value);
+    /// Apply the fallbacks and default of an element-wise transformation whose source array is missing, like
+    /// `apply_fallbacks` does for a single transformation. A fallback with a wildcard is mapped element-wise in place
+    /// of the source, a fallback without one and the default fill the first element of the destination.
+    fn apply_element_wise_fallbacks(
+        &mut self,
+        transformation: Transformation,
+        mapping: Mapping,
+        source_path: String,
+    ) -> Result<Option<(String, String)>, TransformationError> {
+        let EntryOptions { fallbacks, default, .. } = transformation.options().clone();
+        let input_format = mapping.input_format();
+        let mut first_element = element_transformation(&transformation, 0);
+
+        // Fallbacks replace a single source, a transformation combining multiple sources only uses the default
+        let mut resolving_fallback = None;
+        if first_element.source_paths_mut().len() == 1 {
+            for (position, fallback) in fallbacks.iter().enumerate() {
+                if self.resolves(&input_format, fallback)? {
+                    resolving_fallback = Some(position);
+                    break;
                 }
-
-                merge(destination_credential, leaf_node);
-                Ok(None)
             }
+        }
 
-            Transformation::JsonToMarkdown {
-                type_: transformation,
-                source:
-                    DataLocation {
-                        format: source_format,
-                        path: source_path,
-                    },
-                destination:
-                    DataLocation {
-                        format: destination_format,
-                        path: destination_path,
-                    },
-            } => {
-                if source_format != mapping.input_format() || destination_format != mapping.output_format() {
-                    return Ok(None);
-                }
-
-                let source_credential = self.get(&source_format).unwrap();
-
-                let finder = JsonPathFinder::from_str(&source_credential.to_string(), &source_path).unwrap();
-
-                let source_value = match finder.find().as_array() {
-                    // todo: still need to investigate other find() return types
-                    Some(array) => array.first().unwrap().clone(),
-                    None => {
-                        return Ok(None);
-                    }
+        let completed_field = match (resolving_fallback, default) {
+            (Some(position), _) => {
+                let mut fallback_transformation = match fallbacks[position].contains(WILDCARD) {
+                    true => transformation,
+                    false => first_element,
                 };
-
-                let destination_credential = self.entry(destination_format).or_insert(json!({})); // or_insert should never happen, since repository is initialized with all formats, incl empty json value when not present.
-                let pointer = JsonPointer::try_from(JsonPath(destination_path.clone())).unwrap();
-
-                let mut leaf_node = construct_leaf_node(&pointer);
-
-                // run the source value through a markdown converter to fit the nested objects into a markdown string
-                let markdown_source_value = json!(json_to_markdown(&source_value, 0));
-
-                if let Some(value) = leaf_node.pointer_mut(&pointer) {
-                    *value = transformation.apply(markdown_source_value);
+                for path in fallback_transformation.source_paths_mut() {
+                    path.clone_from(&fallbacks[position]);
                 }
-
-                merge(destination_credential, leaf_node);
-
-                trace_dbg!("Successfully completed transformation");
-                Ok(Some((destination_path, source_path)))
+                fallback_transformation.options_mut().fallbacks = fallbacks[position + 1..].to_vec();
+                self.apply_transformation(fallback_transformation, mapping)?
             }
-
-            Transformation::MarkdownToJson {
-                type_: transformation,
-                source:
-                    DataLocation {
-                        format: source_format,
-                        path: source_path,
-                    },
-                destination:
-                    DataLocation {
-                        format: destination_format,
-                        path: destination_path,
-                    },
-            } => {
-                if source_format != mapping.input_format() || destination_format != mapping.output_format() {
-                    return Ok(None);
-                }
-
-                let source_credential = self.get(&source_format).unwrap();
-
-                let finder = JsonPathFinder::from_str(&source_credential.to_string(), &source_path).unwrap();
-
-                let source_value = match finder.find().as_array() {
-                    // todo: still need to investigate other find() return types
-                    Some(array) => array.first().unwrap().clone(),
-                    None => {
+            (None, Some(default)) => {
+                if let Some(condition) = &first_element.options().when {
+                    if !self.condition_holds(condition, &input_format)? {
                         return Ok(None);
                     }
-                };
-
-                let destination_credential = self.entry(destination_format).or_insert(json!({})); // or_insert should never happen, since repository is initialized with all formats, incl empty json value when not present.
-                let pointer = JsonPointer::try_from(JsonPath(destination_path.clone())).unwrap();
-
-                let mut leaf_node = construct_leaf_node(&pointer);
-
-                if let Some(inner_string) = &source_value.as_str() {
-                    let mut lines: Vec<&str> = inner_string.lines().collect();
-
-                    lines.insert(0, "");
-
-                    // Split the string by newlines and collect into Vec<&str>
-                    let markdown_function_result = markdown_to_json(&lines);
-
-                    if let Some(value) = leaf_node.pointer_mut(&pointer) {
-                        *value = transformation.apply(markdown_function_result);
-                    }
                 }
-
-                merge(destination_credential, leaf_node);
-
-                trace_dbg!("Successfully completed transformation");
-                Ok(Some((destination_path, source_path)))
+                self.write_default(&first_element, default, mapping)?
             }
+            (None, None) => None,
+        };
 
-            Transformation::AddIdentifier {
-                type_: transformation,
-                source:
-                    DataTypeLocation {
-                        format: source_format,
-                        datatype: source_type,
-                        path: source_path,
-                    },
-                destination:
-                    DataLocation {
-                        format: destination_format,
-                        path: destination_path,
-                    },
-            } => {
-                if source_format != mapping.input_format() || destination_format != mapping.output_format() {
-                    return Ok(None);
-                }
-
-                let source_credential = self.get(&source_format).unwrap();
-
-                let finder = JsonPathFinder::from_str(&source_credential.to_string(), &source_path).unwrap();
-
-                let source_value = match finder.find().as_array() {
-                    // todo: still need to investigate other find() return types
-                    Some(array) => array.first().unwrap().clone(),
-                    None => {
-                        return Ok(None);
-                    }
-                };
-
-                let destination_credential = self.entry(destination_format).or_insert(json!({})); // or_insert should never happen, since repository is initialized with all formats, incl empty json value when not present.
-                let pointer = JsonPointer::try_from(JsonPath(destination_path.clone())).unwrap();
-
-                let mut leaf_node = construct_leaf_node(&pointer);
-                let identifier_function_result = values_to_identity(&source_type, source_value);
-
-                if let Some(value) = leaf_node.pointer_mut(&pointer) {
-                    *value = transformation.apply(identifier_function_result);
-                }
+        if let Some(completed_field) = &completed_field {
+            self.record_fallback(Some(source_path), completed_field);
+        }
 
-                merge(destination_credential, leaf_node);
+        Ok(completed_field)
+    }
 
-                trace_dbg!("Successfully completed transformation");
-                Ok(Some((destination_path, source_path)))
+    fn apply_single_transformation(
+        &mut self,
+        mut transformation: Transformation,
+        mapping: Mapping,
+    ) -> Result<Option<(String, String)>, TransformationError> {
+        if let Some(condition) = &transformation.options().when {
+            if !self.condition_holds(condition, &mapping.input_format())? {
+                trace_dbg!("Skipped transformation, its condition doesn't hold");
+                return Ok(None);
             }
+        }
 
-            Transformation::IdentifierToObject {
-                type_: transformation,
-                source:
-                    DataTypeLocation {
-                        format: source_format,
-                        datatype: source_type,
-                        path: source_path,
-                    },
-                destination:
-                    DataLocation {
-                        format: destination_format,
-                        path: destination_path,
-                    },
-            } => {
-                if source_format != mapping.input_format() || destination_format != mapping.output_format() {
-                    return Ok(None);
-                }
-
-                let source_credential = self.get(&source_format).unwrap();
-
-                let finder = JsonPathFinder::from_str(&source_credential.to_string(), &source_path).unwrap();
-
-                let source_value = match finder.find().as_array() {
-                    // todo: still need to investigate other find() return types
-                    Some(array) => array.first().unwrap().clone(),
-                    None => {
-                        return Ok(None);
-                    }
-                };
-
-                let destination_credential = self.entry(destination_format).or_insert(json!({})); // or_insert should never happen, since repository is initialized with all formats, incl empty json value when not present.
-                let pointer = JsonPointer::try_from(JsonPath(destination_path.clone())).unwrap();
+        let source_path = transformation.source_path();
+        if let Some(completed_field) = self.apply_fallbacks(&mut transformation, mapping)? {
+            self.record_fallback(source_path, &completed_field);
+            return Ok(Some(completed_field));
+        }
 
-                let mut leaf_node = construct_leaf_node(&pointer);
-                let identifier_function_result = identity_to_object(&source_type, source_value);
+        let uses_fallback = transformation.source_path() != source_path;
+        let completed_field = self.write_transformation(transformation, mapping)?;
+        if let Some(completed_field) = completed_field.as_ref().filter(|_| uses_fallback) {
+            self.record_fallback(source_path, completed_field);
+        }
 
-                if let Some(value) = leaf_node.pointer_mut(&pointer) {
-                    *value = transformation.apply(identifier_function_result);
-                }
+        Ok(completed_field)
+    }
 
-                merge(destination_credential, leaf_node);
+    /// Record that the transformation completed the field with a fallback or its default instead of its source.
+    fn record_fallback(&mut self, source_path: Option<String>, completed_field: &(String, String)) {
+        if let Some(source_path) = source_path {
+            self.used_fallbacks
+                .push((completed_field.0.clone(), source_path, completed_field.1.clone()));
+        }
+    }
 
-                trace_dbg!("Successfully completed transformation");
-                Ok(Some((destination_path, source_path)))
-            }
+    /// Write the value of the transformation to its destination, once its condition and fallbacks are handled.
+    fn write_transformation(
+        &mut self,
+        mut transformation: Transformation,
+        mapping: Mapping,
+    ) -> Result<Option<(String, String)>, TransformationError> {
+        let merge_strategy = transformation.options().merge;
+
+        let sources: Vec<String> = transformation
+            .source_paths_mut()
+            .into_iter()
+            .map(|path| path.clone())
+            .collect();
+        self.source_paths.clone_from(&sources);
+
+        if self.tracer.is_some() {
+            let source_values = match &transformation {
+                Transformation::StringToOne { source, .. } => vec![json!(source.value)],
+                Transformation::StringArrayToOne { source, .. } => vec![json!(source.value)],
+                _ => sources
+                    .iter()
+                    .map(|path| self.find_source_value(&mapping.input_format(), path).ok().flatten())
+                    .map(Option::unwrap_or_default)
+                    .collect(),
+            };
+            self.trace_sources(sources, source_values);
+        }
 
-            Transformation::ImageToIndividualDisplay {
+        match transformation {
+            Transformation::OneToOne {
                 type_: transformation,
-                source:
-                    DataLocation {
-                        format: source_format,
-                        path: source_path,
-                    },
-                destination:
-                    DataLocation {
-                        format: destination_format,
-                        path: destination_path,
-                    },
-            } => {
-                if source_format != mapping.input_format() || destination_format != mapping.output_format() {
-                    return Ok(None);
-                }
-
-                let source_credential = self.get(&source_format).unwrap();
-
-                let finder = JsonPathFinder::from_str(&source_credential.to_string(), &source_path).unwrap();
-
-                let source_value = match finder.find().as_array() {
-                    // todo: still need to investigate other find() return types
-                    Some(array) => array.first().unwrap().clone(),
-                    None => {
-                        return Ok(None);
-                    }
-                };
-
-                let destination_credential = self.entry(destination_format).or_insert(json!({})); // or_insert should never happen, since repository is initialized with all formats, incl empty json value when not present.
-                let pointer = JsonPointer::try_from(JsonPath(destination_path.clone())).unwrap();
-
-                let mut leaf_node = construct_leaf_node(&pointer);
-
-                // run the source value through a markdown converter to fit the nested objects into a markdown string
-                //                let image_individualdisplay_source = Value::Array(vec![json!(create_display_parameter(source_value))]);
-                let result = create_display_parameter(source_value);
-                match result {
-                    Ok(image_individualdisplay_source) => {
-                        if let Some(value) = leaf_node.pointer_mut(&pointer) {
-                            *value = transformation.apply(image_individualdisplay_source);
-                        }
-                    }
-                    Err(_error) => {
-                        return Err(_error);
-                    }
-                }
-
-                merge(destination_credential, leaf_node);
-
-                trace_dbg!("Successfully completed transformation");
-                Ok(Some((destination_path, source_path)))
-            }
-
-            Transformation::ImageToMediaObject {
+                source,
+                destination,
+                ..
+            } => self.apply_steps(&[transformation], source, destination, mapping, merge_strategy),
+            Transformation::Pipeline {
+                pipeline,
+                source,
+                destination,
+                ..
+            } => self.apply_steps(&pipeline, source, destination, mapping, merge_strategy),
+            Transformation::ManyToOne {
                 type_: transformation,
-                source:
-                    DataLocation {
-                        format: source_format,
-                        path: source_path,
-                    },
-                destination:
-                    DataLocation {
-                        format: destination_format,
-                        path: destination_path,
-                    },
+                sources,
+                destination,
+                ..
             } => {
-                if source_format != mapping.input_format() || destination_format != mapping.output_format() {
+                if sources.iter().any(|source| source.format != mapping.input_format())
+                    || destination.format != mapping.output_format()
+                {
                     return Ok(None);
                 }
 
-                let source_credential = self.get(&source_format).unwrap();
-
-                let finder = JsonPathFinder::from_str(&source_credential.to_string(), &source_path).unwrap();
-
-                let source_value = match finder.find().as_array() {
-                    // todo: still need to investigate other find() return types
-                    Some(array) => array.first().unwrap().clone(),
-                    None => {
-                        return Ok(None);
-                    }
-                };
-
-                let destination_credential = self.entry(destination_format).or_insert(json!({})); // or_insert should never happen, since repository is initialized with all formats, incl empty json value when not present.
-                let pointer = JsonPointer::try_from(JsonPath(destination_path.clone())).unwrap();
-
-                let mut leaf_node = construct_leaf_node(&pointer);
-
-                // run the source value through a markdown converter to fit the nested objects into a markdown string
-                //                let image_individualdisplay_source = Value::Array(vec![json!(create_display_parameter(source_value))]);
-                let result = image_to_elm_media_object(source_value);
-                match result {
-                    Ok(image_media_object_source) => {
-                        if let Some(value) = leaf_node.pointer_mut(&pointer) {
-                            *value = transformation.apply(image_media_object_source);
-                        }
-                    }
-                    Err(_error) => {
-                        return Err(_error);
-                    }
+                let mut source_values = Vec::new();
+                for source in &sources {
+                    source_values.push(self.find_source_value(&source.format, &source.path)?);
                 }
 
-                merge(destination_credential, leaf_node);
-
-                trace_dbg!("Successfully completed transformation");
-                Ok(Some((destination_path, source_path)))
-            }
-
-            Transformation::TitleToSpecifiedByObject {
-                type_: transformation,
-                source:
-                    DataLocation {
-                        format: source_format,
-                        path: source_path,
-                    },
-                destination:
-                    DataLocation {
-                        format: destination_format,
-                        path: destination_path,
-                    },
-            } => {
-                if source_format != mapping.input_format() || destination_format != mapping.output_format() {
+                // Missing sources are handled by the transformation, only when all sources are missing there is nothing to map
+                if source_values.iter().all(Option::is_none) {
                     return Ok(None);
                 }
 
-                let source_credential = self.get(&source_format).unwrap();
+                self.write_destination_value(
+                    destination.format,
+                    &destination.path,
+                    transformation.apply(source_values),
+                    merge_strategy,
+                )?;
 
-                let finder = JsonPathFinder::from_str(&source_credential.to_string(), &source_path).unwrap();
-
-                let source_value = match finder.find().as_array() {
-                    // todo: still need to investigate other find() return types
-                    Some(array) => array.first().unwrap().clone(),
-                    None => {
-                        return Ok(None);
-                    }
-                };
-
-                let destination_credential = self.entry(destination_format).or_insert(json!({})); // or_insert should never happen, since repository is initialized with all formats, incl empty json value when not present.
-                let pointer = JsonPointer::try_from(JsonPath(destination_path.clone())).unwrap();
-
-                let mut leaf_node = construct_leaf_node(&pointer);
-                // run the source value through a speficfiedby converter to fit the nested objects into a markdown string
-                let specifiedby_source = json!(title_to_specifiedby(source_value));
-
-                if let Some(value) = leaf_node.pointer_mut(&pointer) {
-                    *value = transformation.apply(specifiedby_source);
-                }
-
-                merge(destination_credential, leaf_node);
+                let source_paths: Vec<&str> = sources.iter().map(|source| source.path.as_str()).collect();
 
                 trace_dbg!("Successfully completed transformation");
-                Ok(Some((destination_path, source_path)))
+                Ok(Some((destination.path, source_paths.join(", "))))
             }
 
-            Transformation::CreditToSpecifiedByObject {
+            Transformation::StringToOne {
                 type_: transformation,
-                source:
-                    DataLocation {
-                        format: source_format,
-                        path: source_path,
-                    },
+                source: StringValue { value: source_value },
                 destination:
                     DataLocation {
                         format: destination_format,
                         path: destination_path,
                     },
+                ..
             } => {
-                if source_format != mapping.input_format() || destination_format != mapping.output_format() {
+                if destination_format != mapping.output_format() {
                     return Ok(None);
                 }
 
-                let source_credential = self.get(&source_format).unwrap();
-
-                let finder = JsonPathFinder::from_str(&source_credential.to_string(), &source_path).unwrap();
-
-                let source_value = match finder.find().as_array() {
-                    // todo: still need to investigate other find() return types
-                    Some(array) => array.first().unwrap().clone(),
-                    None => {
-                        return Ok(None);
-                    }
-                };
-
-                let destination_credential = self.entry(destination_format).or_insert(json!({})); // or_insert should never happen, since repository is initialized with all formats, incl empty json value when not present.
-                let pointer = JsonPointer::try_from(JsonPath(destination_path.clone())).unwrap();
-
-                let mut leaf_node = construct_leaf_node(&pointer);
-                // run the source value through a speficfiedby converter to fit the nested objects into a markdown string
-                let cred_specifiedby_source = json!(credentialpoint_values_to_object(source_value));
-
-                if let Some(value) = leaf_node.pointer_mut(&pointer) {
-                    *value = transformation.apply(cred_specifiedby_source);
-                }
-
-                merge(destination_credential, leaf_node);
-
-                trace_dbg!("Successfully completed transformation");
-                Ok(Some((destination_path, source_path)))
+                self.write_destination_value(
+                    destination_format,
+                    &destination_path,
+                    transformation.apply(source_value),
+                    merge_strategy,
+                )?;
+                Ok(None)
             }
 
-            Transformation::EqfToSpecifiedByQualification {
+            Transformation::StringArrayToOne {
                 type_: transformation,
-                source:
-                    DataLocation {
-                        format: source_format,
-                        path: source_path,
-                    },
+                source: StringArrayValue { value: source_value },
                 destination:
                     DataLocation {
                         format: destination_format,
                         path: destination_path,
                     },
+                ..
             } => {
-                if source_format != mapping.input_format() || destination_format != mapping.output_format() {
+                if destination_format != mapping.output_format() {
                     return Ok(None);
                 }
 
-                let source_credential = self.get(&source_format).unwrap();
-
-                let finder = JsonPathFinder::from_str(&source_credential.to_string(), &source_path).unwrap();
-
-                let source_value = match finder.find().as_array() {
-                    // todo: still need to investigate other find() return types
-                    Some(array) => array.first().unwrap().clone(),
-                    None => {
-                        return Ok(None);
-                    }
-                };
-
-                let destination_credential = self.entry(destination_format).or_insert(json!({})); // or_insert should never happen, since repository is initialized with all formats, incl empty json value when not present.
-                let pointer = JsonPointer::try_from(JsonPath(destination_path.clone())).unwrap();
-
-                let mut leaf_node = construct_leaf_node(&pointer);
-                // run the source value through a speficfiedby converter to fit the nested objects into a markdown string
-                let cred_specifiedby_source = json!(eqf_to_specifiedby_qualification(source_value));
-
-                if let Some(value) = leaf_node.pointer_mut(&pointer) {
-                    *value = transformation.apply(cred_specifiedby_source);
-                }
-
-                merge(destination_credential, leaf_node);
-
-                trace_dbg!("Successfully completed transformation");
-                Ok(Some((destination_path, source_path)))
+                let json_value: Value = Value::Array(
+                    source_value
+                        .into_iter()
+                        .map(Value::String) // Convert each String into serde_json::Value::String
+                        .collect(),
+                );
+
+                self.write_destination_value(
+                    destination_format,
+                    &destination_path,
+                    transformation.apply(json_value),
+                    merge_strategy,
+                )?;
+                Ok(None)
             }
 
-            Transformation::LearningSettingToSpecifiedByObject {
+            Transformation::JsonToMarkdown {
                 type_: transformation,
                 source:
                     DataLocation {
@@ -639,41 +423,31 @@ impl Repository {
                         format: destination_format,
                         path: destination_path,
                     },
+                ..
             } => {
                 if source_format != mapping.input_format() || destination_format != mapping.output_format() {
                     return Ok(None);
-                }
-
-                let source_credential = self.get(&source_format).unwrap();
-
-                let finder = JsonPathFinder::from_str(&source_credential.to_string(), &source_path).unwrap();
-
-                let source_value = match finder.find().as_array() {
-                    // todo: still need to investigate other find() return types
-                    Some(array) => array.first().unwrap().clone(),
-                    None => {
-                        return Ok(None);
-                    }
-                };
-
-                let destination_credential = self.entry(destination_format).or_insert(json!({})); // or_insert should never happen, since repository is initialized with all formats, incl empty json value when not present.
-                let pointer = JsonPointer::try_from(JsonPath(destination_path.clone())).unwrap();
+                }
 
-                let mut leaf_node = construct_leaf_node(&pointer);
-                // run the source value through a speficfiedby converter to fit the nested objects into a markdown string
-                let learning_setting_specifiedby_source = json!(transform_learning_setting(source_value));
+                let Some(source_value) = self.find_source_value(&source_format, &source_path)? else {
+                    return Ok(None);
+                };
 
-                if let Some(value) = leaf_node.pointer_mut(&pointer) {
-                    *value = transformation.apply(learning_setting_specifiedby_source);
-                }
+                // run the source value through a markdown converter to fit the nested objects into a markdown string
+                let markdown_source_value = json!(json_to_markdown(&source_value, 0));
 
-                merge(destination_credential, leaf_node);
+                self.write_destination_value(
+                    destination_format,
+                    &destination_path,
+                    transformation.apply(markdown_source_value),
+                    merge_strategy,
+                )?;
 
                 trace_dbg!("Successfully completed transformation");
                 Ok(Some((destination_path, source_path)))
             }
 
-            Transformation::AssessmentToProvenBy {
+            Transformation::MarkdownToJson {
                 type_: transformation,
                 source:
                     DataLocation {
@@ -685,45 +459,42 @@ impl Repository {
                         format: destination_format,
                         path: destination_path,
                     },
+                ..
             } => {
                 if source_format != mapping.input_format() || destination_format != mapping.output_format() {
                     return Ok(None);
                 }
 
-                let source_credential = self.get(&source_format).unwrap();
-
-                let finder = JsonPathFinder::from_str(&source_credential.to_string(), &source_path).unwrap();
-
-                let source_value = match finder.find().as_array() {
-                    // todo: still need to investigate other find() return types
-                    Some(array) => array.first().unwrap().clone(),
-                    None => {
-                        return Ok(None);
-                    }
+                let Some(source_value) = self.find_source_value(&source_format, &source_path)? else {
+                    return Ok(None);
                 };
 
-                let destination_credential = self.entry(destination_format).or_insert(json!({})); // or_insert should never happen, since repository is initialized with all formats, incl empty json value when not present.
-                let pointer = JsonPointer::try_from(JsonPath(destination_path.clone())).unwrap();
+                // Only string values can contain markdown, other values leave the destination untouched.
+                let mut destination_value = Value::Null;
+                if let Some(inner_string) = &source_value.as_str() {
+                    let mut lines: Vec<&str> = inner_string.lines().collect();
+
+                    lines.insert(0, "");
 
-                let mut leaf_node = construct_leaf_node(&pointer);
-                // run the source value through a speficfiedby converter to fit the nested objects into a markdown string
-                let assess_specifiedby_source = json!(assessment_type_to_specifiedby_assesment(source_value));
+                    // Split the string by newlines and collect into Vec<&str>
+                    let markdown_function_result = markdown_to_json(&lines)
+                        .map_err(|error| TransformationError::Helper(format!("invalid markdown: {}", error)))?;
 
-                if let Some(value) = leaf_node.pointer_mut(&pointer) {
-                    *value = transformation.apply(assess_specifiedby_source);
+                    destination_value = transformation.apply(markdown_function_result);
                 }
 
-                merge(destination_credential, leaf_node);
+                self.write_destination_value(destination_format, &destination_path, destination_value, merge_strategy)?;
 
                 trace_dbg!("Successfully completed transformation");
                 Ok(Some((destination_path, source_path)))
             }
 
-            Transformation::ObjectToNoteLiteral {
+            Transformation::AddIdentifier {
                 type_: transformation,
                 source:
-                    DataLocation {
+                    DataTypeLocation {
                         format: source_format,
+                        datatype: source_type,
                         path: source_path,
                     },
                 destination:
@@ -731,43 +502,35 @@ impl Repository {
                         format: destination_format,
                         path: destination_path,
                     },
+                ..
             } => {
                 if source_format != mapping.input_format() || destination_format != mapping.output_format() {
                     return Ok(None);
                 }
 
-                let source_credential = self.get(&source_format).unwrap();
-
-                let finder = JsonPathFinder::from_str(&source_credential.to_string(), &source_path).unwrap();
-                let source_value = match finder.find().as_array() {
-                    // todo: still need to investigate other find() return types
-                    Some(array) => array.first().unwrap().clone(),
-                    None => {
-                        return Ok(None);
-                    }
+                let Some(source_value) = self.find_source_value(&source_format, &source_path)? else {
+                    return Ok(None);
                 };
 
-                let destination_credential = self.entry(destination_format).or_insert(json!({})); // or_insert should never happen, since repository is initialized with all formats, incl empty json value when not present.
-                let pointer = JsonPointer::try_from(JsonPath(destination_path.clone())).unwrap();
-                let mut leaf_node = construct_leaf_node(&pointer);
-                // run the source value through a speficfiedby converter to fit the nested objects into a markdown string
-                let object_source = json!(object_to_note_literal(source_value));
-
-                if let Some(value) = leaf_node.pointer_mut(&pointer) {
-                    *value = transformation.apply(object_source);
-                }
+                let identifier_function_result = values_to_identity(&source_type, source_value);
 
-                merge(destination_credential, leaf_node);
+                self.write_destination_value(
+                    destination_format,
+                    &destination_path,
+                    transformation.apply(identifier_function_result),
+                    merge_strategy,
+                )?;
 
                 trace_dbg!("Successfully completed transformation");
                 Ok(Some((destination_path, source_path)))
             }
 
-            Transformation::TranslateLearningOutcome {
+            Transformation::IdentifierToObject {
                 type_: transformation,
                 source:
-                    DataLocation {
+                    DataTypeLocation {
                         format: source_format,
+                        datatype: source_type,
                         path: source_path,
                     },
                 destination:
@@ -775,41 +538,68 @@ impl Repository {
                         format: destination_format,
                         path: destination_path,
                     },
+                ..
             } => {
                 if source_format != mapping.input_format() || destination_format != mapping.output_format() {
                     return Ok(None);
                 }
 
-                let source_credential = self.get(&source_format).unwrap();
+                let Some(source_value) = self.find_source_value(&source_format, &source_path)? else {
+                    return Ok(None);
+                };
 
-                let finder = JsonPathFinder::from_str(&source_credential.to_string(), &source_path).unwrap();
-                let source_value = match finder.find().as_array() {
-                    // todo: still need to investigate other find() return types
-                    Some(array) => array.first().unwrap().clone(),
-                    None => {
-                        return Ok(None);
+                let identifier_function_result = identity_to_object(&source_type, source_value);
+
+                self.write_destination_value(
+                    destination_format,
+                    &destination_path,
+                    transformation.apply(identifier_function_result),
+                    merge_strategy,
+                )?;
+
+                trace_dbg!("Successfully completed transformation");
+                Ok(Some((destination_path, source_path)))
+            }
+
+            Transformation::Template {
+                template,
+                bindings,
+                destination,
+                ..
+            } => {
+                if destination.format != mapping.output_format() {
+                    return Ok(None);
+                }
+
+                let mut values = HashMap::new();
+                for (placeholder, source_path) in &bindings {
+                    if let Some(value) = self.find_source_value(&mapping.input_format(), source_path)? {
+                        if !value.is_null() {
+                            values.insert(placeholder.clone(), value);
+                        }
                     }
-                };
+                }
 
-                let destination_credential = self.entry(destination_format).or_insert(json!({})); // or_insert should never happen, since repository is initialized with all formats, incl empty json value when not present.
-                let pointer = JsonPointer::try_from(JsonPath(destination_path.clone())).unwrap();
-                let mut leaf_node = construct_leaf_node(&pointer);
-                // run the source value through a speficfiedby converter to fit the nested objects into a markdown string
-                // let learning_outcome_source = json!(transform_learning_outcomes(source_value));
-                let learning_outcome_source = json!(transform_alignment_to_learning_outcomes(source_value));
-                
-                if let Some(value) = leaf_node.pointer_mut(&pointer) {
-                    *value = transformation.apply(learning_outcome_source);
+                // Without any value the template would only hold its fixed data
+                if values.is_empty() {
+                    return Ok(None);
                 }
 
-                merge(destination_credential, leaf_node);
+                let template_value = load_template(&template)?;
+                let Some(destination_value) = fill_template(&template_value, &template, &bindings, &values)? else {
+                    return Ok(None);
+                };
+
+                self.write_destination_value(destination.format, &destination.path, destination_value, merge_strategy)?;
+
+                let source_paths: Vec<&str> = bindings.values().map(String::as_str).collect();
 
                 trace_dbg!("Successfully completed transformation");
-                Ok(Some((destination_path, source_path)))
+                Ok(Some((destination.path, source_paths.join(", "))))
             }
 
-            Transformation::CreateLearningOutcomeSummary {
-                type_: transformation,
+            Transformation::Handler {
+                type_,
                 source:
                     DataLocation {
                         format: source_format,
@@ -820,122 +610,368 @@ impl Repository {
                         format: destination_format,
                         path: destination_path,
                     },
+                parameters,
+                ..
             } => {
                 if source_format != mapping.input_format() || destination_format != mapping.output_format() {
                     return Ok(None);
                 }
 
-                let source_credential = self.get(&source_format).unwrap();
+                let handler = get_handler(&type_).ok_or(TransformationError::UnknownHandler(type_))?;
 
-                let finder = JsonPathFinder::from_str(&source_credential.to_string(), &source_path).unwrap();
-                let source_value = match finder.find().as_array() {
-                    // todo: still need to investigate other find() return types
-                    Some(array) => array.first().unwrap().clone(),
-                    None => {
-                        return Ok(None);
-                    }
+                let Some(source_value) = self.find_source_value(&source_format, &source_path)? else {
+                    return Ok(None);
                 };
 
-                let destination_credential = self.entry(destination_format).or_insert(json!({})); // or_insert should never happen, since repository is initialized with all formats, incl empty json value when not present.
-                let pointer = JsonPointer::try_from(JsonPath(destination_path.clone())).unwrap();
-                let mut leaf_node = construct_leaf_node(&pointer);
-                // run the source value through a speficfiedby converter to fit the nested objects into a markdown string
-                let learning_outcome_sum_source = json!(create_learning_outcome_summary(source_value));
-
-                if let Some(value) = leaf_node.pointer_mut(&pointer) {
-                    *value = transformation.apply(learning_outcome_sum_source);
-                }
-
-                merge(destination_credential, leaf_node);
+                self.write_destination_value(
+                    destination_format,
+                    &destination_path,
+                    handler.apply_in_conversion(source_value, &parameters, mapping)?,
+                    merge_strategy,
+                )?;
 
                 trace_dbg!("Successfully completed transformation");
                 Ok(Some((destination_path, source_path)))
             }
 
-            Transformation::AddressToLocation {
+            Transformation::OneToMany {
                 type_: transformation,
                 source:
                     DataLocation {
                         format: source_format,
                         path: source_path,
                     },
-                destination:
-                    DataLocation {
-                        format: destination_format,
-                        path: destination_path,
-                    },
+                destinations,
+                ..
             } => {
-                if source_format != mapping.input_format() || destination_format != mapping.output_format() {
+                if source_format != mapping.input_format()
+                    || destinations
+                        .iter()
+                        .any(|destination| destination.format != mapping.output_format())
+                {
                     return Ok(None);
                 }
 
-                let source_credential = self.get(&source_format).unwrap();
+                let Some(source_value) = self.find_source_value(&source_format, &source_path)? else {
+                    return Ok(None);
+                };
 
-                let finder = JsonPathFinder::from_str(&source_credential.to_string(), &source_path).unwrap();
+                let parts = transformation.apply(source_value, destinations.len())?;
+                if parts.is_empty() {
+                    return Ok(None);
+                }
 
-                let source_value = match finder.find().as_array() {
-                    // todo: still need to investigate other find() return types
-                    Some(array) => array.first().unwrap().clone(),
-                    None => {
-                        return Ok(None);
-                    }
-                };
+                for (destination, part) in destinations.iter().zip(parts) {
+                    self.write_destination_value(destination.format.clone(), &destination.path, part, merge_strategy)?;
+                }
 
-                let destination_credential = self.entry(destination_format).or_insert(json!({})); // or_insert should never happen, since repository is initialized with all formats, incl empty json value when not present.
-                let pointer = JsonPointer::try_from(JsonPath(destination_path.clone())).unwrap();
+                let destination_paths: Vec<&str> = destinations
+                    .iter()
+                    .map(|destination| destination.path.as_str())
+                    .collect();
 
-                let mut leaf_node = construct_leaf_node(&pointer);
-                // run the source value through a markdown converter to fit the nested objects into a markdown string
-                //                let image_individualdisplay_source = Value::Array(vec![json!(create_display_parameter(source_value))]);
-                let location_source = json!(address_to_location(source_value));
-                //                let markdown_source_value = json!(image_to_individual_display(source_value));
+                trace_dbg!("Successfully completed transformation");
+                Ok(Some((destination_paths.join(", "), source_path)))
+            }
+        }
+    }
+
+    /// Apply OneToOne steps to the source value in order, writing the output of the last step to the destination.
+    fn apply_steps(
+        &mut self,
+        steps: &[OneToOne],
+        source: DataLocation,
+        destination: DataLocation,
+        mapping: Mapping,
+        merge_strategy: MergeStrategy,
+    ) -> Result<Option<(String, String)>, TransformationError> {
+        let (source_format, source_path) = (source.format, source.path);
+        let (destination_format, destination_path) = (destination.format, destination.path);
+        if source_format != mapping.input_format() || destination_format != mapping.output_format() {
+            return Ok(None);
+        }
+
+        let Some(mut destination_value) = self.find_source_value(&source_format, &source_path)? else {
+            return Ok(None);
+        };
+
+        for step in steps {
+            destination_value = step.apply(destination_value)?;
+        }
+        if destination_value.is_null() {
+            return Ok(None);
+        }
+
+        self.write_destination_value(destination_format, &destination_path, destination_value, merge_strategy)?;
+
+        trace_dbg!("Successfully completed transformation");
+        Ok(Some((destination_path, source_path)))
+    }
+
+    /// When none of the sources of a transformation resolves to a value, replace its source by the first of its
+    /// `fallbacks` that does. Without such a fallback its `default` is written to the destinations instead, in which
+    /// case the completed field is returned and the transformation itself isn't applied anymore.
+    fn apply_fallbacks(
+        &mut self,
+        transformation: &mut Transformation,
+        mapping: Mapping,
+    ) -> Result<Option<(String, String)>, TransformationError> {
+        let EntryOptions { fallbacks, default, .. } = transformation.options().clone();
+        if fallbacks.is_empty() && default.is_none() {
+            return Ok(None);
+        }
+
+        let input_format = mapping.input_format();
+        let mut source_paths = transformation.source_paths_mut();
+        // Transformations with a literal source always have a value
+        if source_paths.is_empty() {
+            return Ok(None);
+        }
+        for source_path in &source_paths {
+            if self.resolves(&input_format, source_path)? {
+                return Ok(None);
+            }
+        }
 
-                if let Some(value) = leaf_node.pointer_mut(&pointer) {
-                    *value = transformation.apply(location_source);
+        // Fallbacks replace a single source, a transformation combining multiple sources only uses the default
+        if let [source_path] = source_paths.as_mut_slice() {
+            for fallback in fallbacks {
+                if self.resolves(&input_format, &fallback)? {
+                    let message = format!("Using fallback {} for {}", fallback, source_path);
+                    trace_dbg!(message);
+                    **source_path = fallback;
+                    return Ok(None);
                 }
+            }
+        }
 
-                merge(destination_credential, leaf_node);
+        match default {
+            Some(default) => self.write_default(transformation, default, mapping),
+            None => Ok(None),
+        }
+    }
 
-                trace_dbg!("Successfully completed transformation");
-                Ok(Some((destination_path, source_path)))
+    /// Write the default of a transformation to its destinations, when neither its source nor a fallback resolves.
+    fn write_default(
+        &mut self,
+        transformation: &Transformation,
+        default: Value,
+        mapping: Mapping,
+    ) -> Result<Option<(String, String)>, TransformationError> {
+        let merge_strategy = transformation.options().merge;
+
+        self.source_paths.clear();
+        self.trace_sources(vec![DEFAULT_SOURCE.to_string()], vec![default.clone()]);
+        let mut destination_paths = Vec::new();
+        for destination in transformation.destinations() {
+            if destination.format == mapping.output_format() {
+                self.write_destination_value(
+                    destination.format.clone(),
+                    &destination.path,
+                    default.clone(),
+                    merge_strategy,
+                )?;
+                destination_paths.push(destination.path.as_str());
             }
+        }
 
-            _ => todo!(),
+        if destination_paths.is_empty() {
+            return Ok(None);
         }
+
+        trace_dbg!("Completed transformation with its default value");
+        Ok(Some((destination_paths.join(", "), DEFAULT_SOURCE.to_string())))
+    }
+
+    /// Whether the JsonPath `path` resolves to a value other than null in the credential of `format`.
+    fn resolves(&self, format: &str, path: &str) -> Result<bool, TransformationError> {
+        Ok(self
+            .find_source_value(format, path)?
+            .is_some_and(|value| !value.is_null()))
     }
 
     pub fn apply_transformations(
         &mut self,
         transformations: Vec<Transformation>,
         mapping: Mapping,
-    ) -> Result<Vec<(String, String)>, &'static str> {
-        let mut completed_fields: Vec<(String, String)> = Vec::new();
-        for transformation in transformations {
+    ) -> Result<Vec<(String, String)>, ConversionError> {
+        self.apply_mapping(transformations, mapping)
+            .map(|applied_mapping| applied_mapping.completed_fields)
+    }
+
+    /// Apply all transformations of a mapping, reporting the completed fields and the fallbacks used to complete them.
+    pub fn apply_mapping(
+        &mut self,
+        transformations: Vec<Transformation>,
+        mapping: Mapping,
+    ) -> Result<AppliedMapping, ConversionError> {
+        let mut applied_mapping = AppliedMapping::default();
+        for (index, transformation) in transformations.into_iter().enumerate() {
+            let (source_path, destination_path) = (transformation.source_path(), transformation.destination_path());
+            if let Some(tracer) = self.tracer.as_mut() {
+                tracer.entry = (index, transformation.type_name());
+            }
+
+            self.used_fallbacks.clear();
             let result = self.apply_transformation(transformation, mapping);
+            // Reported for each element of a transformation applied element-wise
+            for (destination, source, fallback) in self.used_fallbacks.drain(..) {
+                applied_mapping.fallbacks.push(UsedFallback {
+                    index,
+                    destination,
+                    source,
+                    fallback,
+                });
+            }
+
             match result {
-                Ok(Some(completed_field)) => {
-                    completed_fields.push(completed_field);
+                Ok(Some(completed_field)) => applied_mapping.completed_fields.push(completed_field),
+                // The entry had nothing to map, e.g. its source is missing or its condition doesn't hold
+                Ok(None) => {}
+                Err(cause) => {
+                    return Err(ConversionError::Transformation {
+                        index,
+                        source_path,
+                        destination_path,
+                        cause,
+                    });
+                }
+            }
+        }
+
+        if let Some(tracer) = self.tracer.as_mut() {
+            applied_mapping.trace = std::mem::take(&mut tracer.traces);
+        }
+
+        Ok(applied_mapping)
+    }
+
+    /// Set the sources of the transformation being applied, recorded with the values it writes.
+    fn trace_sources(&mut self, sources: Vec<String>, source_values: Vec<Value>) {
+        if let Some(tracer) = self.tracer.as_mut() {
+            tracer.sources = sources;
+            tracer.source_values = source_values;
+        }
+    }
+
+    /// Find the value at the JsonPath `source_path` in the credential of `source_format`.
+    /// A path that can match multiple values, e.g. `$.x[*].name`, returns all matches as an array.
+    /// Returns `Ok(None)` when the path doesn't match anything.
+    fn find_source_value(&self, source_format: &str, source_path: &str) -> Result<Option<Value>, TransformationError> {
+        let source_credential = self
+            .get(source_format)
+            .ok_or_else(|| TransformationError::MissingFormat(source_format.to_string()))?;
+
+        let normalised_path = normalise_json_path(source_path);
+
+        let json_path =
+            JsonPathInst::from_str(&normalised_path).map_err(|message| TransformationError::InvalidJsonPath {
+                path: source_path.to_string(),
+                message,
+            })?;
+        let finder = JsonPathFinder::new(Box::new(source_credential.clone()), Box::new(json_path));
+
+        // todo: still need to investigate other find() return types
+        match finder.find() {
+            Value::Array(matches) if matches.is_empty() => Ok(None),
+            Value::Array(matches) if is_multi_match(source_path) => Ok(Some(Value::Array(matches))),
+            Value::Array(matches) => Ok(matches.into_iter().next()),
+            _ => Ok(None),
+        }
+    }
+
+    /// Evaluate a `when` condition of a transformation against the credential of `format`.
+    fn condition_holds(&self, condition: &Condition, format: &str) -> Result<bool, TransformationError> {
+        match condition {
+            Condition::Not { not } => Ok(!self.condition_holds(not, format)?),
+            Condition::AllOf { all_of } => {
+                for condition in all_of {
+                    if !self.condition_holds(condition, format)? {
+                        return Ok(false);
+                    }
                 }
-                Ok(None) => {} // to do and check //println!("Ok(none)");
-                Err(_error) => {
-                    return Err(_error);
+                Ok(true)
+            }
+            Condition::AnyOf { any_of } => {
+                for condition in any_of {
+                    if self.condition_holds(condition, format)? {
+                        return Ok(true);
+                    }
                 }
+                Ok(false)
             }
+            Condition::Predicate { path, predicate } => {
+                let value = self.find_source_value(format, path)?;
+                predicate.test(value.as_ref())
+            }
+        }
+    }
+
+    /// Write `value` at the JsonPath `destination_path` into the credential of `destination_format`, merging it into
+    /// the value already at the destination following `merge_strategy`.
+    fn write_destination_value(
+        &mut self,
+        destination_format: String,
+        destination_path: &str,
+        value: Value,
+        merge_strategy: MergeStrategy,
+    ) -> Result<(), TransformationError> {
+        if destination_path.contains(WILDCARD) {
+            return Err(TransformationError::InvalidJsonPointer {
+                path: destination_path.to_string(),
+                message: "a wildcard destination needs a wildcard source to map element-wise".to_string(),
+            });
+        }
+
+        let invalid_destination = |message| TransformationError::InvalidJsonPointer {
+            path: destination_path.to_string(),
+            message,
+        };
+        let destination = JsonPath(destination_path.to_string());
+        let leaf_node = destination.leaf_node().map_err(invalid_destination)?;
+        let pointer = JsonPointer::try_from(destination).map_err(invalid_destination)?;
+
+        self.consumed_paths.extend(self.source_paths.iter().cloned());
+        if let Some(tracer) = self.tracer.as_mut() {
+            tracer.traces.push(FieldTrace {
+                pointer: pointer.0.clone(),
+                index: tracer.entry.0,
+                transformation: tracer.entry.1.clone(),
+                sources: tracer.sources.clone(),
+                source_values: tracer.source_values.clone(),
+                value: value.clone(),
+            });
+        }
+
+        let destination_credential = self.entry(destination_format).or_insert(json!({})); // or_insert should never happen, since repository is initialized with all formats, incl empty json value when not present.
+
+        // The path to the destination is created first, the strategy only decides how the value itself is merged
+        merge(destination_credential, leaf_node);
+        if let Some(destination_value) = destination_credential.pointer_mut(&pointer) {
+            merge_with_strategy(destination_value, value, merge_strategy);
         }
 
-        Ok(completed_fields)
+        Ok(())
     }
 
     pub fn clear_mapping(&mut self, mut output_pointer: String, mapping: Mapping) {
         let output_json = self.get_mut(&mapping.output_format()).unwrap();
 
         output_pointer = output_pointer.trim_start_matches("/").to_string();
-        let keys: Vec<String> = output_pointer.split('/').map(|s| s.to_string()).collect();
+        let keys: Vec<String> = output_pointer
+            .split('/')
+            .map(|s| unescape_token(s).unwrap_or_else(|_| s.to_string()))
+            .collect();
 
         remove_key_recursive(output_json, &keys);
     }
 }
 
+/// Check whether a JsonPath can select more than one value, like a wildcard, recursive descent or filter.
+fn is_multi_match(path: &str) -> bool {
+    path.contains(WILDCARD) || path.contains("..") || path.contains(".*") || path.contains("[?")
+}
+
 pub fn merge(a: &mut Value, b: Value) {
     match (a, b) {
         (a @ &mut Value::Object(_), Value::Object(b)) => {
@@ -964,20 +1000,131 @@ pub fn merge(a: &mut Value, b: Value) {
     }
 }
 
-pub fn update_repository(state: &mut AppState) {
+/// The transformation of the element at `index` of an element-wise transformation: the first wildcard of its source,
+/// destination, fallback and condition paths is replaced by the index.
+fn element_transformation(transformation: &Transformation, index: usize) -> Transformation {
+    let index = format!("[{}]", index);
+    let mut element_transformation = transformation.clone();
+    for path in element_transformation.source_paths_mut() {
+        *path = path.replacen(WILDCARD, &index, 1);
+    }
+    for path in element_transformation.destination_paths_mut() {
+        *path = path.replacen(WILDCARD, &index, 1);
+    }
+    let options = element_transformation.options_mut();
+    let condition_paths = options.when.as_mut().map(Condition::paths_mut).unwrap_or_default();
+    for path in options.fallbacks.iter_mut().chain(condition_paths) {
+        *path = path.replacen(WILDCARD, &index, 1);
+    }
+
+    element_transformation
+}
+
+/// Merge `b` into the value `a` at the destination of a transformation, following the merge strategy of its entry.
+/// A null `b` leaves `a` unchanged with every strategy.
+pub fn merge_with_strategy(a: &mut Value, b: Value, strategy: MergeStrategy) {
+    if b.is_null() {
+        return;
+    }
+
+    match strategy {
+        MergeStrategy::byIndex => merge(a, b),
+        MergeStrategy::replace => *a = b,
+        MergeStrategy::append => {
+            let mut elements = match a.take() {
+                Value::Array(elements) => elements,
+                Value::Null => Vec::new(),
+                existing => vec![existing],
+            };
+            match b {
+                Value::Array(appended) => elements.extend(appended),
+                appended => elements.push(appended),
+            }
+            *a = Value::Array(elements);
+        }
+        MergeStrategy::byId => match (a, b) {
+            (a @ &mut Value::Object(_), Value::Object(b)) => {
+                let a = a.as_object_mut().unwrap();
+                for (k, v) in b {
+                    merge_with_strategy(a.entry(k).or_insert(Value::Null), v, MergeStrategy::byId);
+                }
+            }
+            (a @ &mut Value::Array(_), b) => {
+                let a_arr = a.as_array_mut().unwrap();
+                let b_arr = match b {
+                    Value::Array(b_arr) => b_arr,
+                    b_val => vec![b_val],
+                };
+
+                // Generated nodes still have the placeholder id of their type, they're never the same node
+                for b_val in b_arr {
+                    let id = b_val
+                        .get("id")
+                        .filter(|id| !id.is_null() && id.as_str().is_none_or(identifies_node));
+                    match id.and_then(|id| a_arr.iter().position(|a_val| a_val.get("id") == Some(id))) {
+                        Some(i) => merge_with_strategy(&mut a_arr[i], b_val, MergeStrategy::byId),
+                        None => a_arr.push(b_val),
+                    }
+                }
+            }
+            (a, b) => *a = b,
+        },
+    }
+}
+
+/// Write the value of the mapping confirmed in the terminal interface to the output credential.
+///
+/// # Returns
+/// - `Err(ConversionError)`: If the transformation of the mapping fails, or its candidate value isn't json.
+pub fn update_repository(state: &mut AppState) -> Result<(), ConversionError> {
     let output_pointer = state.output_pointer.clone();
     let output_format = state.mapping.output_format();
+    let transformation = state.performed_mappings.last().cloned();
+    let failed = |cause| ConversionError::Transformation {
+        index: state.performed_mappings.len().saturating_sub(1),
+        source_path: transformation.as_ref().and_then(Transformation::source_path),
+        destination_path: transformation.as_ref().and_then(Transformation::destination_path),
+        cause,
+    };
+
+    // Wildcard pointers are filled element-wise and a split fills multiple output fields,
+    // so the mapping itself is applied instead of the candidate value
+    if output_pointer.contains("/*") || state.mapping_option == MappingOptions::OneToMany {
+        if let Some(transformation) = transformation.clone() {
+            let result = state.repository.apply_transformation(transformation, state.mapping);
+            result.map_err(failed)?;
+        }
+        return Ok(());
+    }
 
-    let source_value = state.candidate_output_value.clone();
-    let output_json = state.repository.get_mut(&output_format).unwrap();
+    if let Some(mut transformation) = transformation.clone() {
+        let source_paths: Vec<String> = transformation
+            .source_paths_mut()
+            .into_iter()
+            .map(|path| path.clone())
+            .collect();
+        state.repository.consume_source_paths(source_paths);
+    }
 
+    let source_value = state.candidate_output_value.clone();
     let mut leaf_node = construct_leaf_node(&output_pointer);
 
     if let Some(value) = leaf_node.pointer_mut(&output_pointer) {
-        *value = serde_json::from_str(&source_value).unwrap();
+        *value = serde_json::from_str(&source_value).map_err(|error| {
+            failed(TransformationError::Helper(format!(
+                "the value {} isn't json: {}",
+                source_value, error
+            )))
+        })?;
     }
 
+    let output_json = state
+        .repository
+        .get_mut(&output_format)
+        .ok_or_else(|| failed(TransformationError::MissingFormat(output_format.clone())))?;
     merge(output_json, leaf_node);
+
+    Ok(())
 }
 
 fn remove_key_recursive(current_json: &mut Value, keys: &[String]) -> bool {
@@ -1035,7 +1182,7 @@ fn identity_to_object(identity_type: &str, identity_value: Value) -> Value {
     if let Some(id_value) = identity_value.get("identityHash") {
         if identity_type.eq(&"Student ID".to_string()) {
             let mut new_object = Map::new();
-            new_object.insert("id".to_string(), Value::String("urn:epass:identifier:2".to_string()));
+            new_object.insert("id".to_string(), Value::String(generated_id("identifier")));
             new_object.insert("type".to_string(), Value::String("Identifier".to_string()));
             new_object.insert("notation".to_string(), id_value.clone());
             new_object.insert("schemeName".to_string(), Value::String(identity_type.to_string()));
@@ -1083,7 +1230,7 @@ fn json_to_markdown(json: &Value, indent_level: usize) -> String {
     markdown
 }
 
-fn markdown_to_json(lines: &[&str]) -> Value {
+fn markdown_to_json(lines: &[&str]) -> Result<Value, serde_json::Error> {
     // Recursively converts indented lines of Markdown into a JSON structure.
     // 1.	Parsing Markdown:
     // •	Headings (#): These are treated as keys in the resulting JSON object.
@@ -1113,8 +1260,7 @@ fn markdown_to_json(lines: &[&str]) -> Value {
             json_string.push_str(line);
             i += 1;
         }
-        let parsed_json: Value = serde_json::to_value(&json_string).unwrap();
-        parsed_json
+        serde_json::to_value(&json_string)
     } else {
         while i < lines.len() {
             let line = lines[i];
@@ -1273,8 +1419,7 @@ fn markdown_to_json(lines: &[&str]) -> Value {
         json_string.pop();
         json_string = json_string.trim_end_matches(',').to_string();
         json_string.push_str("\n}");
-        let parsed_json: Value = serde_json::from_str(&json_string).unwrap();
-        parsed_json
+        serde_json::from_str(&json_string)
     }
 }
 
@@ -1315,3 +1460,962 @@ fn cleanup_string(string_to_clean: &str) -> String {
     // Add quotes around the cleaned string
     format!("\"{}\"", cleaned_string)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::handlers::register_handler;
+
+    /// Apply two entries writing the OBv3 `first` and `second` into the same ELM destination with the merge strategy,
+    /// none when `merge` is `None`, and return the ELM output.
+    fn merged_output(first: Value, second: Value, destination: &str, merge: Option<&str>) -> Value {
+        let conversion = Mapping::find("OBv3", "ELM").unwrap();
+        let entry = |path: &str| {
+            let mut entry = json!({
+                "type_": "copy",
+                "source": { "format": "OBv3", "path": path },
+                "destination": { "format": "ELM", "path": destination },
+            });
+            if let Some(merge) = merge {
+                entry["merge"] = json!(merge);
+            }
+            serde_json::from_value::<Transformation>(entry).unwrap()
+        };
+
+        let mut repository = Repository::from(HashMap::from([
+            ("OBv3".to_string(), json!({ "first": first, "second": second })),
+            ("ELM".to_string(), json!({})),
+        ]));
+        repository
+            .apply_mapping(vec![entry("$.first"), entry("$.second")], conversion)
+            .unwrap();
+
+        repository.remove("ELM").unwrap()
+    }
+
+    /// Apply the mapping `entries` to the OBv3 `input` and return the ELM output.
+    fn mapped_output(input: Value, entries: Value) -> Result<Value, ConversionError> {
+        let conversion = Mapping::find("OBv3", "ELM").unwrap();
+        let transformations = serde_json::from_value::<Vec<Transformation>>(entries).unwrap();
+
+        let mut repository = Repository::from(HashMap::from([
+            ("OBv3".to_string(), input),
+            ("ELM".to_string(), json!({})),
+        ]));
+        repository.apply_mapping(transformations, conversion)?;
+
+        Ok(repository.remove("ELM").unwrap())
+    }
+
+    fn identifiers() -> (Value, Value) {
+        (
+            json!([{ "id": "urn:a", "notation": "A" }, { "id": "urn:b", "notation": "B" }]),
+            json!([{ "id": "urn:b", "schemeName": "national" }, { "id": "urn:c", "notation": "C" }]),
+        )
+    }
+
+    #[test]
+    fn merges_by_index_by_default() {
+        let (first, second) = identifiers();
+        let output = merged_output(first, second, "$.credentialSubject.identifier", None);
+
+        assert_eq!(
+            output["credentialSubject"]["identifier"],
+            json!([
+                { "id": "urn:b", "notation": "A", "schemeName": "national" },
+                { "id": "urn:c", "notation": "C" },
+            ])
+        );
+    }
+
+    #[test]
+    fn merges_by_index() {
+        let (first, second) = identifiers();
+        let default = merged_output(first.clone(), second.clone(), "$.credentialSubject.identifier", None);
+        let by_index = merged_output(first, second, "$.credentialSubject.identifier", Some("byIndex"));
+
+        assert_eq!(by_index, default);
+    }
+
+    #[test]
+    fn replaces() {
+        let (first, second) = identifiers();
+        let output = merged_output(first, second.clone(), "$.credentialSubject.identifier", Some("replace"));
+
+        assert_eq!(output["credentialSubject"]["identifier"], second);
+    }
+
+    #[test]
+    fn appends() {
+        let output = merged_output(
+            json!({ "title": "Maths" }),
+            json!([{ "title": "Physics" }, { "title": "Chemistry" }]),
+            "$.credentialSubject.hasClaim.learningOutcome",
+            Some("append"),
+        );
+
+        assert_eq!(
+            output["credentialSubject"]["hasClaim"]["learningOutcome"],
+            json!([{ "title": "Maths" }, { "title": "Physics" }, { "title": "Chemistry" }])
+        );
+    }
+
+    #[test]
+    fn merges_by_id() {
+        let (first, second) = identifiers();
+        let output = merged_output(first, second, "$.credentialSubject.identifier", Some("byId"));
+
+        assert_eq!(
+            output["credentialSubject"]["identifier"],
+            json!([
+                { "id": "urn:a", "notation": "A" },
+                { "id": "urn:b", "notation": "B", "schemeName": "national" },
+                { "id": "urn:c", "notation": "C" },
+            ])
+        );
+    }
+
+    #[test]
+    fn writes_digit_only_and_star_names_as_object_keys() {
+        let output = merged_output(json!("A"), json!("B"), "$.credentialSubject['123']['*']", None);
+
+        assert_eq!(output["credentialSubject"], json!({ "123": { "*": "B" } }));
+    }
+
+    #[test]
+    fn reports_the_fallback_of_each_element() {
+        let conversion = Mapping::find("OBv3", "ELM").unwrap();
+        let transformation = serde_json::from_value::<Transformation>(json!({
+            "type_": "copy",
+            "source": { "format": "OBv3", "path": "$.results[*].title" },
+            "destination": { "format": "ELM", "path": "$.credentialSubject.hasClaim[*].title" },
+            "fallbacks": ["$.results[*].name"],
+            "default": "Untitled",
+        }))
+        .unwrap();
+
+        let mut repository = Repository::from(HashMap::from([
+            (
+                "OBv3".to_string(),
+                json!({ "results": [{ "title": "Maths" }, { "name": "Physics" }, {}] }),
+            ),
+            ("ELM".to_string(), json!({})),
+        ]));
+        let applied_mapping = repository.apply_mapping(vec![transformation], conversion).unwrap();
+
+        let fallbacks: Vec<_> = applied_mapping
+            .fallbacks
+            .iter()
+            .map(|used| {
+                (
+                    used.index,
+                    used.destination.as_str(),
+                    used.source.as_str(),
+                    used.fallback.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            fallbacks,
+            [
+                (
+                    0,
+                    "$.credentialSubject.hasClaim[1].title",
+                    "$.results[1].title",
+                    "$.results[1].name"
+                ),
+                (
+                    0,
+                    "$.credentialSubject.hasClaim[2].title",
+                    "$.results[2].title",
+                    DEFAULT_SOURCE
+                ),
+            ]
+        );
+        assert_eq!(
+            repository["ELM"]["credentialSubject"]["hasClaim"],
+            json!([{ "title": "Maths" }, { "title": "Physics" }, { "title": "Untitled" }])
+        );
+    }
+
+    /// Apply an element-wise `copy` of `$.results[*].title` with the fallbacks and default to the OBv3 `input`, and
+    /// return the ELM claims with the fallbacks used.
+    fn element_wise_fallback_output(input: Value, fallbacks: Value, default: Value) -> (Value, Vec<(String, String)>) {
+        let conversion = Mapping::find("OBv3", "ELM").unwrap();
+        let transformation = serde_json::from_value::<Transformation>(json!({
+            "type_": "copy",
+            "source": { "format": "OBv3", "path": "$.results[*].title" },
+            "destination": { "format": "ELM", "path": "$.credentialSubject.hasClaim[*].title" },
+            "fallbacks": fallbacks,
+            "default": default,
+        }))
+        .unwrap();
+
+        let mut repository = Repository::from(HashMap::from([
+            ("OBv3".to_string(), input),
+            ("ELM".to_string(), json!({})),
+        ]));
+        let applied_mapping = repository.apply_mapping(vec![transformation], conversion).unwrap();
+        let fallbacks = applied_mapping
+            .fallbacks
+            .into_iter()
+            .map(|used| (used.source, used.fallback))
+            .collect();
+
+        (repository["ELM"]["credentialSubject"]["hasClaim"].clone(), fallbacks)
+    }
+
+    #[test]
+    fn maps_a_wildcard_fallback_element_wise_when_the_source_array_is_missing() {
+        let (claims, fallbacks) = element_wise_fallback_output(
+            json!({ "courses": [{ "name": "Maths" }, { "name": "Physics" }] }),
+            json!(["$.modules[*].name", "$.courses[*].name"]),
+            json!("Untitled"),
+        );
+
+        assert_eq!(claims, json!([{ "title": "Maths" }, { "title": "Physics" }]));
+        assert_eq!(
+            fallbacks,
+            [("$.results[*].title".to_string(), "$.courses[*].name".to_string())]
+        );
+    }
+
+    #[test]
+    fn fills_the_first_element_when_the_source_array_is_missing() {
+        let (claims, _) = element_wise_fallback_output(
+            json!({ "name": "Maths" }),
+            json!(["$.courses[*].name", "$.name"]),
+            json!("Untitled"),
+        );
+        assert_eq!(claims, json!([{ "title": "Maths" }]));
+
+        let (claims, fallbacks) =
+            element_wise_fallback_output(json!({}), json!(["$.courses[*].name"]), json!("Untitled"));
+        assert_eq!(claims, json!([{ "title": "Untitled" }]));
+        assert_eq!(
+            fallbacks,
+            [("$.results[*].title".to_string(), DEFAULT_SOURCE.to_string())]
+        );
+    }
+
+    #[test]
+    fn joins_each_element_of_a_wildcard_source_with_the_other_sources() {
+        let conversion = Mapping::find("OBv3", "ELM").unwrap();
+        let transformation = serde_json::from_value::<Transformation>(json!({
+            "type_": { "join": { "separator": ", " } },
+            "sources": [
+                { "format": "OBv3", "path": "$.issuer.name" },
+                { "format": "OBv3", "path": "$.results[*].title" },
+            ],
+            "destination": { "format": "ELM", "path": "$.credentialSubject.hasClaim[*].title" },
+        }))
+        .unwrap();
+
+        let mut repository = Repository::from(HashMap::from([
+            (
+                "OBv3".to_string(),
+                json!({ "issuer": { "name": "Uni" }, "results": [{ "title": "Maths" }, { "title": "Physics" }] }),
+            ),
+            ("ELM".to_string(), json!({})),
+        ]));
+        repository.apply_mapping(vec![transformation], conversion).unwrap();
+
+        assert_eq!(
+            repository["ELM"]["credentialSubject"]["hasClaim"],
+            json!([{ "title": "Uni, Maths" }, { "title": "Uni, Physics" }])
+        );
+    }
+
+    #[test]
+    fn appends_elements_without_id_when_merging_by_id() {
+        let output = merged_output(
+            json!([{ "id": "urn:a", "title": "Maths" }]),
+            json!([{ "title": "Physics" }, { "id": "urn:a", "title": "Mathematics" }]),
+            "$.credentialSubject.hasClaim.learningOutcome",
+            Some("byId"),
+        );
+
+        assert_eq!(
+            output["credentialSubject"]["hasClaim"]["learningOutcome"],
+            json!([{ "id": "urn:a", "title": "Mathematics" }, { "title": "Physics" }])
+        );
+    }
+
+    #[test]
+    fn appends_generated_nodes_when_merging_by_id() {
+        let output = merged_output(
+            json!([
+                { "id": "urn:epass:note:generated", "noteLiteral": { "en": ["First"] } },
+                { "id": "urn:epass:note:generated:shared", "noteLiteral": { "en": ["Shared"] } },
+            ]),
+            json!([
+                { "id": "urn:epass:note:generated", "noteLiteral": { "en": ["Second"] } },
+                { "id": "urn:epass:note:generated:shared", "subject": "Maths" },
+            ]),
+            "$.credentialSubject.additionalNote",
+            Some("byId"),
+        );
+
+        assert_eq!(
+            output["credentialSubject"]["additionalNote"],
+            json!([
+                { "id": "urn:epass:note:generated", "noteLiteral": { "en": ["First"] } },
+                { "id": "urn:epass:note:generated:shared", "noteLiteral": { "en": ["Shared"] }, "subject": "Maths" },
+                { "id": "urn:epass:note:generated", "noteLiteral": { "en": ["Second"] } },
+            ])
+        );
+    }
+
+    #[test]
+    fn splits_a_value_over_the_destinations() {
+        let split = |type_: Value, destinations: &[&str]| {
+            json!([{
+                "type_": type_,
+                "source": { "format": "OBv3", "path": "$.name" },
+                "destinations": destinations
+                    .iter()
+                    .map(|path| json!({ "format": "ELM", "path": path }))
+                    .collect::<Vec<_>>(),
+            }])
+        };
+        let input = json!({ "name": "Jane Mary Doe" });
+
+        let output = mapped_output(
+            input.clone(),
+            split(
+                json!({ "split": { "dividers": " " } }),
+                &["$.credentialSubject.givenName", "$.credentialSubject.familyName"],
+            ),
+        )
+        .unwrap();
+        // The last destination gets the remainder, so nothing is lost
+        assert_eq!(
+            output["credentialSubject"],
+            json!({ "givenName": "Jane", "familyName": "Mary Doe" })
+        );
+
+        let output = mapped_output(
+            json!({ "name": "Doe,  Jane" }),
+            split(
+                json!({ "regexSplit": { "pattern": ",\\s*" } }),
+                &["$.credentialSubject.familyName", "$.credentialSubject.givenName"],
+            ),
+        )
+        .unwrap();
+        assert_eq!(
+            output["credentialSubject"],
+            json!({ "familyName": "Doe", "givenName": "Jane" })
+        );
+
+        let output = mapped_output(
+            json!({ "name": ["Jane", "Doe", "Ignored"] }),
+            split(
+                json!({ "split": { "dividers": " " } }),
+                &["$.credentialSubject.givenName", "$.credentialSubject.familyName"],
+            ),
+        )
+        .unwrap();
+        assert_eq!(
+            output["credentialSubject"],
+            json!({ "givenName": "Jane", "familyName": "Doe" })
+        );
+
+        let error = mapped_output(
+            input,
+            split(
+                json!({ "regexSplit": { "pattern": "(" } }),
+                &["$.credentialSubject.givenName"],
+            ),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            error,
+            ConversionError::Transformation {
+                cause: TransformationError::InvalidRegex { .. },
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn replaces_and_extracts_regex_matches() {
+        let entry = |type_: Value, destination: &str| {
+            json!({
+                "type_": type_,
+                "source": { "format": "OBv3", "path": "$.description" },
+                "destination": { "format": "ELM", "path": destination },
+            })
+        };
+        let input = json!({ "description": "Awarded on 2024-08-30 for 6 ECTS" });
+
+        let output = mapped_output(
+            input.clone(),
+            json!([
+                entry(
+                    json!({ "regexReplace": { "pattern": "(\\d{4})-(\\d{2})-(\\d{2})", "replacement": "$3.$2.$1" } }),
+                    "$.credentialSubject.description",
+                ),
+                entry(
+                    json!({ "regexExtract": { "pattern": "(\\d+) ECTS", "group": 1 } }),
+                    "$.credentialSubject.credits",
+                ),
+                entry(
+                    json!({ "regexExtract": { "pattern": "(\\d+) hours", "group": 1 } }),
+                    "$.credentialSubject.volumeOfLearning",
+                ),
+            ]),
+        )
+        .unwrap();
+
+        // Without a match nothing is written
+        assert_eq!(
+            output["credentialSubject"],
+            json!({ "description": "Awarded on 30.08.2024 for 6 ECTS", "credits": "6" })
+        );
+
+        let error = mapped_output(
+            input,
+            json!([entry(
+                json!({ "regexExtract": { "pattern": "[", "group": 0 } }),
+                "$.credentialSubject.credits"
+            )]),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            error,
+            ConversionError::Transformation {
+                index: 0,
+                cause: TransformationError::InvalidRegex { .. },
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn applies_entries_only_when_their_condition_holds() {
+        let entry = |destination: &str, when: Value| {
+            json!({
+                "type_": "copy",
+                "source": { "format": "OBv3", "path": "$.name" },
+                "destination": { "format": "ELM", "path": destination },
+                "when": when,
+            })
+        };
+        let input = json!({ "name": "Maths", "achievementType": "Diploma", "credits": 6 });
+
+        let output = mapped_output(
+            input,
+            json!([
+                entry("$.equals", json!({ "path": "$.achievementType", "equals": "Diploma" })),
+                entry(
+                    "$.notEquals",
+                    json!({ "not": { "path": "$.achievementType", "equals": "Diploma" } })
+                ),
+                entry("$.missing", json!({ "path": "$.level", "exists": false })),
+                entry("$.matches", json!({ "path": "$.credits", "matches": "^\\d+$" })),
+                entry("$.typeOf", json!({ "path": "$.credits", "typeOf": "string" })),
+                entry(
+                    "$.allOf",
+                    json!({ "allOf": [
+                        { "path": "$.name", "exists": true },
+                        { "path": "$.level", "exists": true },
+                    ] }),
+                ),
+                entry(
+                    "$.anyOf",
+                    json!({ "anyOf": [
+                        { "path": "$.level", "exists": true },
+                        { "path": "$.credits", "typeOf": "number" },
+                    ] }),
+                ),
+            ]),
+        )
+        .unwrap();
+
+        assert_eq!(
+            output,
+            json!({ "equals": "Maths", "missing": "Maths", "matches": "Maths", "anyOf": "Maths" })
+        );
+    }
+
+    #[test]
+    fn tests_the_condition_of_each_element() {
+        let output = mapped_output(
+            json!({ "results": [
+                { "title": "Maths", "status": "Completed" },
+                { "title": "Physics", "status": "Enrolled" },
+            ] }),
+            json!([{
+                "type_": "copy",
+                "source": { "format": "OBv3", "path": "$.results[*].title" },
+                "destination": { "format": "ELM", "path": "$.credentialSubject.hasClaim[*].title" },
+                "when": { "path": "$.results[*].status", "equals": "Completed" },
+            }]),
+        )
+        .unwrap();
+
+        assert_eq!(output["credentialSubject"]["hasClaim"], json!([{ "title": "Maths" }]));
+    }
+
+    #[test]
+    fn applies_the_steps_of_a_pipeline_in_order() {
+        let pipeline = |pipeline: Value| {
+            json!([{
+                "pipeline": pipeline,
+                "source": { "format": "OBv3", "path": "$.results[*].code" },
+                "destination": { "format": "ELM", "path": "$.credentialSubject.hasClaim[*].notation" },
+            }])
+        };
+        let input = json!({ "results": [{ "code": "maths-101" }, { "code": "physics-202" }] });
+
+        let output = mapped_output(
+            input.clone(),
+            pipeline(json!([
+                { "regexExtract": { "pattern": "^([a-z]+)-", "group": 1 } },
+                "toUpperCase",
+                { "slice": { "index": 2 } },
+            ])),
+        )
+        .unwrap();
+        assert_eq!(
+            output["credentialSubject"]["hasClaim"],
+            json!([{ "notation": "MAT" }, { "notation": "PHY" }])
+        );
+
+        let error = mapped_output(
+            input,
+            pipeline(json!(["toUpperCase", { "regexReplace": { "pattern": "(", "replacement": "" } }])),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            error,
+            ConversionError::Transformation {
+                cause: TransformationError::InvalidRegex { .. },
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn applies_registered_handlers() {
+        register_handler("prefixForTest", |value: Value, parameters: &Value| {
+            match (value, &parameters["prefix"]) {
+                (Value::String(value), Value::String(prefix)) => Ok(Value::String(format!("{}{}", prefix, value))),
+                _ => Err(TransformationError::Helper(
+                    "the value and prefix must be strings".to_string(),
+                )),
+            }
+        });
+        // A handler can't take the name of a built-in transformation
+        register_handler("copy", |_: Value, _: &Value| Ok(json!("replaced")));
+        let entry = |type_: &str, parameters: Value| {
+            json!({
+                "type_": type_,
+                "source": { "format": "OBv3", "path": "$.name" },
+                "destination": { "format": "ELM", "path": "$.title" },
+                "parameters": parameters,
+            })
+        };
+
+        let output = mapped_output(
+            json!({ "name": "Maths" }),
+            json!([entry("prefixForTest", json!({ "prefix": "Course: " }))]),
+        )
+        .unwrap();
+        assert_eq!(output, json!({ "title": "Course: Maths" }));
+
+        let output = mapped_output(json!({ "name": "Maths" }), json!([entry("copy", Value::Null)])).unwrap();
+        assert_eq!(output, json!({ "title": "Maths" }));
+
+        let error = mapped_output(
+            json!({ "name": 1 }),
+            json!([entry("prefixForTest", json!({ "prefix": "Course: " }))]),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            error,
+            ConversionError::Transformation {
+                cause: TransformationError::Helper(_),
+                ..
+            }
+        ));
+
+        let error =
+            mapped_output(json!({ "name": "Maths" }), json!([entry("notRegistered", Value::Null)])).unwrap_err();
+        assert!(matches!(
+            error,
+            ConversionError::Transformation {
+                cause: TransformationError::UnknownHandler(name),
+                ..
+            } if name == "notRegistered"
+        ));
+    }
+
+    /// Write the json `template` to a temporary file and return its path.
+    fn template_file(name: &str, template: Value) -> String {
+        let path = std::env::temp_dir().join(format!("{}-{}.json", std::process::id(), name));
+        std::fs::write(&path, template.to_string()).unwrap();
+        path.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn fills_templates_with_the_bound_values() {
+        let template = template_file(
+            "qualification-template",
+            json!({
+                "type": "Qualification",
+                "title": { "en": ["{{title}}"] },
+                "volumeOfLearning": "{{ credits }}",
+                "description": { "en": ["Level {{level}} of {{title}}"] },
+            }),
+        );
+        let entry = json!({
+            "template": template,
+            "bindings": { "title": "$.results[*].title", "credits": "$.results[*].credits", "level": "$.level" },
+            "destination": { "format": "ELM", "path": "$.credentialSubject.hasClaim[*].specifiedBy" },
+        });
+
+        let output = mapped_output(
+            json!({ "level": 6, "results": [{ "title": "Maths", "credits": 5 }, { "title": "Physics" }] }),
+            json!([entry]),
+        )
+        .unwrap();
+
+        // The fields of missing values are left out and a value filling a whole string keeps its type
+        assert_eq!(
+            output["credentialSubject"]["hasClaim"],
+            json!([
+                { "specifiedBy": {
+                    "type": "Qualification",
+                    "title": { "en": ["Maths"] },
+                    "volumeOfLearning": 5,
+                    "description": { "en": ["Level 6 of Maths"] },
+                } },
+                { "specifiedBy": {
+                    "type": "Qualification",
+                    "title": { "en": ["Physics"] },
+                    "description": { "en": ["Level 6 of Physics"] },
+                } },
+            ])
+        );
+
+        let output = mapped_output(json!({ "name": "Maths" }), json!([entry])).unwrap();
+        assert_eq!(output, json!({}));
+
+        std::fs::remove_file(&template).unwrap();
+    }
+
+    #[test]
+    fn rejects_unbound_template_placeholders() {
+        let template = template_file(
+            "unbound-template",
+            json!({ "title": "{{title}}", "level": "{{level}}" }),
+        );
+
+        let error = mapped_output(
+            json!({ "name": "Maths" }),
+            json!([{
+                "template": template,
+                "bindings": { "title": "$.name" },
+                "destination": { "format": "ELM", "path": "$.credentialSubject.hasClaim.specifiedBy" },
+            }]),
+        )
+        .unwrap_err();
+
+        std::fs::remove_file(&template).unwrap();
+        assert!(matches!(
+            error,
+            ConversionError::Transformation {
+                cause: TransformationError::InvalidTemplate { message, .. },
+                ..
+            } if message.contains("`level`")
+        ));
+    }
+
+    #[test]
+    fn looks_values_up_in_a_code_list() {
+        let code_list = std::env::temp_dir().join(format!("{}-lookup-code-list.json", std::process::id()));
+        std::fs::write(
+            &code_list,
+            json!({ "en": "ENG", "de": "DEU", "true": "yes" }).to_string(),
+        )
+        .unwrap();
+        let entry = |path: &str, destination: &str, on_missing: Value| {
+            json!({
+                "type_": { "lookup": { "file": code_list, "onMissing": on_missing } },
+                "source": { "format": "OBv3", "path": path },
+                "destination": { "format": "ELM", "path": destination },
+            })
+        };
+        let input = json!({ "language": "de", "languages": ["en", "fr", "de"], "online": true, "unknown": "fr" });
+
+        let output = mapped_output(
+            input.clone(),
+            json!([
+                entry("$.language", "$.language", json!("drop")),
+                entry("$.languages", "$.languages", json!("drop")),
+                entry("$.online", "$.online", json!("drop")),
+                entry("$.unknown", "$.dropped", json!("drop")),
+                entry("$.unknown", "$.defaulted", json!({ "default": "MUL" })),
+            ]),
+        )
+        .unwrap();
+        assert_eq!(
+            output,
+            json!({ "language": "DEU", "languages": ["ENG", "DEU"], "online": "yes", "defaulted": "MUL" })
+        );
+
+        let error = mapped_output(input, json!([entry("$.unknown", "$.language", json!("error"))])).unwrap_err();
+        std::fs::remove_file(&code_list).unwrap();
+        assert!(matches!(
+            error,
+            ConversionError::Transformation {
+                cause: TransformationError::MissingCode { value, .. },
+                ..
+            } if value == "fr"
+        ));
+    }
+
+    #[test]
+    fn normalises_dates() {
+        let entry = |path: &str, output: &str| {
+            json!({
+                "type_": { "dateTime": { "output": output } },
+                "source": { "format": "OBv3", "path": path },
+                "destination": { "format": "ELM", "path": path },
+            })
+        };
+
+        let output = mapped_output(
+            json!({
+                "validFrom": "2024-08-30T14:00:00+02:00",
+                "validUntil": "2025-08-30",
+                "awardedDate": "2024-01-01T01:00:00+02:00",
+                "issued": 1725019200,
+                "expires": "never",
+            }),
+            json!([
+                entry("$.validFrom", "xsd:dateTime"),
+                entry("$.validUntil", "xsd:dateTime"),
+                entry("$.awardedDate", "xsd:date"),
+                entry("$.issued", "xsd:dateTime"),
+                entry("$.expires", "xsd:dateTime"),
+            ]),
+        )
+        .unwrap();
+
+        // Values that aren't recognised as a date are left unchanged
+        assert_eq!(
+            output,
+            json!({
+                "validFrom": "2024-08-30T12:00:00Z",
+                "validUntil": "2025-08-30T00:00:00Z",
+                "awardedDate": "2024-01-01",
+                "issued": "2024-08-30T12:00:00Z",
+                "expires": "never",
+            })
+        );
+    }
+
+    #[test]
+    fn converts_language_maps() {
+        let entry = |type_: Value, path: &str| {
+            json!({
+                "type_": type_,
+                "source": { "format": "OBv3", "path": path },
+                "destination": { "format": "ELM", "path": path },
+            })
+        };
+        let input = json!({
+            "name": "Mathematics",
+            "tags": ["maths", "algebra"],
+            "title": { "de": ["Mathematik"], "sv": ["Matematik"], "en": ["Mathematics"] },
+            "label": { "fr": "Mathématiques", "nl": "Wiskunde" },
+        });
+        let mapping = json!([
+            entry(json!({ "toLangMap": {} }), "$.name"),
+            entry(json!({ "toLangMap": { "lang": "de" } }), "$.tags"),
+            entry(json!({ "fromLangMap": { "preferred": ["sv"] } }), "$.title"),
+            entry(json!({ "fromLangMap": {} }), "$.label"),
+        ]);
+
+        let output = mapped_output(input.clone(), mapping.clone()).unwrap();
+        assert_eq!(
+            output,
+            json!({
+                "name": { "en": ["Mathematics"] },
+                "tags": { "de": ["maths", "algebra"] },
+                "title": "Matematik",
+                // Without a preferred language or English, the first language is taken
+                "label": "Mathématiques",
+            })
+        );
+
+        // The preferred languages of the conversion come first
+        let mut transformations = serde_json::from_value::<Vec<Transformation>>(mapping).unwrap();
+        for transformation in transformations.iter_mut() {
+            for step in transformation.steps_mut() {
+                step.prefer_languages(&["nl".to_string(), "de".to_string()]);
+            }
+        }
+        let mut repository = Repository::from(HashMap::from([
+            ("OBv3".to_string(), input),
+            ("ELM".to_string(), json!({})),
+        ]));
+        repository
+            .apply_mapping(transformations, Mapping::find("OBv3", "ELM").unwrap())
+            .unwrap();
+        assert_eq!(
+            repository["ELM"],
+            json!({
+                "name": { "nl": ["Mathematics"] },
+                "tags": { "de": ["maths", "algebra"] },
+                "title": "Mathematik",
+                "label": "Wiskunde",
+            })
+        );
+    }
+
+    #[test]
+    fn traces_every_written_value() {
+        let transformations = serde_json::from_value::<Vec<Transformation>>(json!([
+            {
+                "type_": { "join": { "separator": " " } },
+                "sources": [
+                    { "format": "OBv3", "path": "$.givenName" },
+                    { "format": "OBv3", "path": "$.additionalName" },
+                    { "format": "OBv3", "path": "$.familyName" },
+                ],
+                "destination": { "format": "ELM", "path": "$.credentialSubject.fullName" },
+            },
+            {
+                "type_": "toUpperCase",
+                "source": { "format": "OBv3", "path": "$.results[*].code" },
+                "destination": { "format": "ELM", "path": "$.credentialSubject.hasClaim[*].notation" },
+            },
+            {
+                "type_": "copy",
+                "source": { "format": "OBv3", "path": "$.level" },
+                "destination": { "format": "ELM", "path": "$.credentialSubject.level" },
+                "default": "unknown",
+            },
+        ]))
+        .unwrap();
+        let mut repository = Repository::from(HashMap::from([
+            (
+                "OBv3".to_string(),
+                json!({ "givenName": "Jane", "familyName": "Doe", "results": [{ "code": "m1" }, { "code": "p2" }] }),
+            ),
+            ("ELM".to_string(), json!({})),
+        ]));
+        repository.enable_trace();
+
+        let applied_mapping = repository
+            .apply_mapping(transformations, Mapping::find("OBv3", "ELM").unwrap())
+            .unwrap();
+
+        let trace: Vec<Value> = applied_mapping
+            .trace
+            .iter()
+            .map(|trace| serde_json::to_value(trace).unwrap())
+            .collect();
+        assert_eq!(
+            trace,
+            [
+                json!({
+                    "pointer": "/credentialSubject/fullName",
+                    "index": 0,
+                    "transformation": "join",
+                    "sources": ["$.givenName", "$.additionalName", "$.familyName"],
+                    "sourceValues": ["Jane", null, "Doe"],
+                    "value": "Jane Doe",
+                }),
+                json!({
+                    "pointer": "/credentialSubject/hasClaim/0/notation",
+                    "index": 1,
+                    "transformation": "toUpperCase",
+                    "sources": ["$.results[0].code"],
+                    "sourceValues": ["m1"],
+                    "value": "M1",
+                }),
+                json!({
+                    "pointer": "/credentialSubject/hasClaim/1/notation",
+                    "index": 1,
+                    "transformation": "toUpperCase",
+                    "sources": ["$.results[1].code"],
+                    "sourceValues": ["p2"],
+                    "value": "P2",
+                }),
+                json!({
+                    "pointer": "/credentialSubject/level",
+                    "index": 2,
+                    "transformation": "copy",
+                    "sources": [DEFAULT_SOURCE],
+                    "sourceValues": ["unknown"],
+                    "value": "unknown",
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_the_unconsumed_subtrees_of_the_input() {
+        let transformations = serde_json::from_value::<Vec<Transformation>>(json!([
+            {
+                "type_": "copy",
+                "source": { "format": "OBv3", "path": "$.credentialSubject.achievement.name" },
+                "destination": { "format": "ELM", "path": "$.credentialSubject.hasClaim.title" },
+                // Reading a field in a condition doesn't consume it
+                "when": { "path": "$.credentialSubject.achievement.achievementType", "exists": true },
+            },
+            {
+                "type_": "copy",
+                "source": { "format": "OBv3", "path": "$.issuer" },
+                "destination": { "format": "ELM", "path": "$.issuer" },
+            },
+            {
+                "type_": "copy",
+                "source": { "format": "OBv3", "path": "$.missing" },
+                "destination": { "format": "ELM", "path": "$.missing" },
+            },
+        ]))
+        .unwrap();
+        let input = json!({
+            "issuer": { "id": "did:example:issuer", "name": "Uni" },
+            "credentialSubject": {
+                "achievement": {
+                    "name": "Maths",
+                    "achievementType": "Course",
+                    "alignment": [{ "targetName": "ESCO", "targetUrl": "https://esco.ec.europa.eu" }],
+                },
+                "result": [],
+            },
+        });
+        let mut repository = Repository::from(HashMap::from([
+            ("OBv3".to_string(), input),
+            ("ELM".to_string(), json!({})),
+        ]));
+        repository
+            .apply_mapping(transformations, Mapping::find("OBv3", "ELM").unwrap())
+            .unwrap();
+
+        let unconsumed: Vec<(String, Value)> = repository
+            .unconsumed_fields("OBv3")
+            .into_iter()
+            .map(|field| (field.pointer, field.value))
+            .collect();
+        assert_eq!(
+            unconsumed,
+            [
+                (
+                    "/credentialSubject/achievement/achievementType".to_string(),
+                    json!("Course")
+                ),
+                (
+                    "/credentialSubject/achievement/alignment".to_string(),
+                    json!([{ "targetName": "ESCO", "targetUrl": "https://esco.ec.europa.eu" }])
+                ),
+            ]
+        );
+    }
+}
@@ -1,7 +1,7 @@
 use axum::{
     // routing::post,
     // Router,
-    http::{header, HeaderMap, HeaderValue, StatusCode},
+    http::StatusCode,
     response::IntoResponse,
     Json,
 };
@@ -9,59 +9,57 @@ use axum::{
 use serde_json::{json, Value};
 
 use crate::backend::base64_encode::{decode_json, encode_json_file};
-use crate::backend::headless_cli::load_files_apply_transformations;
-use crate::state::{AppState, Mapping};
-use std::{fs::File, io::Write, path::Path};
-use tokio::fs;
+use crate::backend::convert::{convert_with_options, load_mapping, ConversionOptions};
+use crate::backend::error::ConversionError;
+use crate::backend::formats::FormatRegistry;
+use crate::backend::id_generation::IdStrategy;
+use crate::backend::jwt::read_credential;
+use crate::backend::routes::conversion_error_response;
+use crate::state::Mapping;
+use crate::trace_dbg;
 
 pub async fn api(Json(input_json): Json<Value>) -> impl IntoResponse {
     //test the input types for this API
     // with JSON body: {
     //     "From": {"Name": "OB", "Version": "3.0"},
     //     "To": {"Name": "elm", "Version": "3.2"},
-    //     "Parameters": { "PreferredLanguages": ["en", "sv"]},
+    //     "Parameters": { "PreferredLanguages": ["en", "sv"], "IdStrategy": "uuidV5", "Trace": true, "ReportUnconsumed": true},
     //     "Content": "Base 64 encoded content in From format"
     // }
 
-    // Handle the file upload
-    let input_file_path;
-    let mapping_file_name;
-    let mapping_type;
-
-    // Create directories for uploads and outputs if they don't exist
-    let upload_dir = "uploads";
-    let output_dir = "outputs";
-    let file_name = "export_file";
-    let _ = fs::create_dir_all(upload_dir).await.map_err(|_| {
-        let error_json = json!({
-                "error": "Internal Server Error",
-                "message" : "Failed to create upload directory"});
-        (StatusCode::INTERNAL_SERVER_ERROR, Json(error_json))
-    });
-    let _ = fs::create_dir_all(output_dir).await.map_err(|_| {
-        let error_json = json!({
-            "error": "Internal Server Error",
-            "message" : "Failed to create output directory"});
-        (StatusCode::INTERNAL_SERVER_ERROR, Json(error_json))
-    });
-
-    match input_json
-        .get("From")
-        .and_then(|v| v.get("Name"))
-        .and_then(|v| v.as_str())
-    {
-        Some("OB") => {
-            mapping_file_name = "json/mapping/custom_mapping_OBv3_ELM_latest.json".to_string();
-            mapping_type = Mapping::OBv3ToELM;
-        }
-        Some("ELM") => {
-            mapping_file_name = "json/mapping/custom_mapping_ELM_OBv3_latest.json".to_string();
-            mapping_type = Mapping::ELMToOBv3;
+    // The formats are found by the name of their family and their version, the latest version of the family is used
+    // when no version is given. A version that isn't registered is refused, unless a format reads inputs of that
+    // version. Without a `To` the credential is converted into the default output of its format.
+    let registry = match FormatRegistry::global() {
+        Ok(registry) => registry,
+        Err(error) => {
+            trace_dbg!(&error);
+            return conversion_error_response(&error);
         }
-        Some(value) => {
+    };
+    let find_format = |side: &str| {
+        let name = input_json
+            .pointer(&format!("/{}/Name", side))
+            .and_then(|v| v.as_str())?;
+        let version = input_json
+            .pointer(&format!("/{}/Version", side))
+            .and_then(|v| v.as_str());
+        let format = match side {
+            "From" => registry.find_input_version(name, version),
+            _ => registry.find_version(name, version),
+        };
+        Some(format.map_err(|error| match error {
+            ConversionError::InvalidArguments(message) => message,
+            error => error.to_string(),
+        }))
+    };
+
+    let input = match find_format("From") {
+        Some(Ok(format)) => format,
+        Some(Err(error)) => {
             let error_json = json!({
             "error": "Bad Request",
-            "message" : format!("Invalid translation value: {}", value)});
+            "message" : format!("Invalid translation value: {}", error)});
             return (StatusCode::BAD_REQUEST, Json(error_json));
         }
         None => {
@@ -70,136 +68,145 @@ pub async fn api(Json(input_json): Json<Value>) -> impl IntoResponse {
                 "message" : "Invalid translation value: no key found"});
             return (StatusCode::BAD_REQUEST, Json(error_json));
         }
-    }
+    };
+    let output = match find_format("To") {
+        Some(Ok(format)) => Some(format),
+        Some(Err(error)) => {
+            let error_json = json!({
+            "error": "Bad Request",
+            "message" : format!("Invalid translation value: {}", error)});
+            return (StatusCode::BAD_REQUEST, Json(error_json));
+        }
+        None => input.default_output().and_then(|name| registry.get(name)),
+    };
 
-    match input_json.get("Content").and_then(|v| v.as_str()) {
-        Some(value) => {
-            input_file_path = format!("{}/{}", upload_dir, file_name);
-            let file = File::create(&input_file_path).map_err(|_| {
-                let error_json = json!({
-                        "error": "Internal Server Error",
-                        "message" : "Failed to create file"});
-                (StatusCode::INTERNAL_SERVER_ERROR, Json(error_json))
-            });
-            match decode_json(value) {
-                Ok(data) => {
-                    match file {
-                        Ok(mut file_found) => {
-                            let _ = file_found.write_all(&data);
-                        }
-                        Err(file_error) => {
-                            return file_error;
-                        } // Err(file_error) => {let error_json = json!({
-                          //     "error": "Internal Server Error",
-                          //     "message" : "Failed to write to file"});
-                          // return (StatusCode::INTERNAL_SERVER_ERROR, Json(error_json));}
-                    }
-                }
-                Err(_decode_err) => {
+    let Some((mapping_type, mapping_file_name)) = output
+        .map(|output| Mapping {
+            registry,
+            input,
+            output,
+        })
+        .and_then(|mapping| Some((mapping, mapping.default_mapping()?)))
+    else {
+        let error_json = json!({
+            "error": "Bad Request",
+            "message" : format!("No mapping from {} into the requested format", input.name)});
+        return (StatusCode::BAD_REQUEST, Json(error_json));
+    };
+
+    // Decode the content in memory, no files are written to disk
+    let input_credential: Value = match input_json.get("Content").and_then(|v| v.as_str()) {
+        Some(value) => match decode_json(value) {
+            Ok(data) => match read_credential(&String::from_utf8_lossy(&data)) {
+                Ok((input_credential, _)) => input_credential,
+                Err(_parse_err) => {
                     let error_json = json!({
+                        "error": "Bad Request",
+                        "message" : "Content is not a valid json or jwt file"});
+                    return (StatusCode::BAD_REQUEST, Json(error_json));
+                }
+            },
+            Err(_decode_err) => {
+                let error_json = json!({
                     "error": "Internal Server Error",
                     "message" : "Failed to read file data"});
-                    return (StatusCode::INTERNAL_SERVER_ERROR, Json(error_json));
-                }
+                return (StatusCode::INTERNAL_SERVER_ERROR, Json(error_json));
             }
-        }
-
+        },
         None => {
             let error_json = json!({
                     "error": "Bad Request",
                     "message" : "Invalid data value: no key found"});
             return (StatusCode::BAD_REQUEST, Json(error_json));
         }
-    }
+    };
 
-    // Define the output file path
-    let output_file_name = format!(
-        "translated_{}",
-        Path::new(&input_file_path).file_name().unwrap().to_str().unwrap()
-    );
-    let output_file_path = format!("{}/{}", output_dir, output_file_name);
+    // The preferred languages are optional, but must be a list of language codes when given
+    let preferred_languages = match input_json.pointer("/Parameters/PreferredLanguages") {
+        None | Some(Value::Null) => Vec::new(),
+        Some(value) => match serde_json::from_value::<Vec<String>>(value.clone()) {
+            Ok(preferred_languages) => preferred_languages,
+            Err(_) => {
+                let error_json = json!({
+                    "error": "Bad Request",
+                    "message" : "PreferredLanguages is not a list of language codes"});
+                return (StatusCode::BAD_REQUEST, Json(error_json));
+            }
+        },
+    };
+    let coerce = input_json
+        .pointer("/Parameters/Coerce")
+        .and_then(Value::as_bool)
+        .unwrap_or_default();
+    let id_strategy = match input_json.pointer("/Parameters/IdStrategy") {
+        None | Some(Value::Null) => IdStrategy::default(),
+        Some(value) => match serde_json::from_value::<IdStrategy>(value.clone()) {
+            Ok(id_strategy) => id_strategy,
+            Err(_) => {
+                let error_json = json!({
+                    "error": "Bad Request",
+                    "message" : "IdStrategy is not one of sequential, uuidV4 or uuidV5"});
+                return (StatusCode::BAD_REQUEST, Json(error_json));
+            }
+        },
+    };
+    let trace = input_json
+        .pointer("/Parameters/Trace")
+        .and_then(Value::as_bool)
+        .unwrap_or_default();
+    let report_unconsumed = input_json
+        .pointer("/Parameters/ReportUnconsumed")
+        .and_then(Value::as_bool)
+        .unwrap_or_default();
+    let options = ConversionOptions {
+        preferred_languages,
+        coerce,
+        id_strategy,
+        trace,
+        report_unconsumed,
+    };
 
     // start mapping based on the input form the API
-    // 1 create a state needed for the mapping tool
-    // 2 load all hte state elements needed for mapping
-
-    let mut state = AppState {
-        input_path: input_file_path,
-        output_path: output_file_path.clone(),
-        mapping_path: mapping_file_name,
-        mapping: mapping_type,
-        ..Default::default()
+    let output = match load_mapping(mapping_file_name, mapping_type)
+        .and_then(|transformations| convert_with_options(&input_credential, mapping_type, &transformations, &options))
+    {
+        Ok(output) => output,
+        Err(error) => {
+            trace_dbg!(&error);
+            return conversion_error_response(&error);
+        }
     };
-    // let mut state = AppState::default();
-    // state.input_path = _input_file_path;
-    // state.output_path = output_file_path.clone();
-    // state.mapping_path = _mapping_file_name;
-    // state.mapping = _mapping_type;
-
-    load_files_apply_transformations(&mut state);
-
-    // Return the translated file as a response
-    // 1 load file from fs into mem
-    // 2 remove file from fs
-    // 3 push mem to http output
-
-    let output_file = fs::read(&output_file_path).await.map_err(|_| {
-        let error_json = json!({
-            "error": "Internal Server Error",
-            "message" : "Failed to read output file"});
-        (StatusCode::INTERNAL_SERVER_ERROR, Json(error_json))
-    });
-    let _ = fs::remove_file(state.input_path).await.map_err(|_| {
-        let error_json = json!({
-            "error": "Internal Server Error",
-            "message" : "Failed to remove input file"});
-        (StatusCode::INTERNAL_SERVER_ERROR, Json(error_json))
-    });
-    let _ = fs::remove_file(state.output_path).await.map_err(|_| {
-        let error_json = json!({
-            "error": "Internal Server Error",
-            "message" : "Failed to remove output file"});
-        (StatusCode::INTERNAL_SERVER_ERROR, Json(error_json))
-    });
 
-    // Set the headers, including content disposition for download
-    let mut headers = HeaderMap::new();
-    // For better integration into EDCI change the output_file_name from *.json to *.jsonld
-    headers.insert(
-        header::CONTENT_TYPE,
-        HeaderValue::from_static("application/application/json"),
-    );
-
-    //println!("state_exitwarning: {:#?}", state.exit_warning);
-    match state.exit_warning {
-        true => {
+    // Return the translated credential as a base64 encoded response
+    let content = match serde_json::to_vec_pretty(&output.credential) {
+        Ok(content) => content,
+        Err(_ser_error) => {
             let error_json = json!({
                 "error": "Internal Server Error",
-                "message" : "Failed to encode the json file"});
+                "message" : "Failed to serialize the json file"});
             return (StatusCode::INTERNAL_SERVER_ERROR, Json(error_json));
         }
-        false => {}
-    }
+    };
 
-    match output_file {
-        Ok(content) => match encode_json_file(content) {
-            Ok(encoded_json) => {
-                let response_json = json!({"content": encoded_json});
-                (StatusCode::OK, Json(response_json))
+    match encode_json_file(content) {
+        Ok(encoded_json) => {
+            let mut response_json = json!({"content": encoded_json});
+            if coerce {
+                response_json["coercions"] = json!(output.coercions);
             }
-            Err(_enc_error) => {
-                let error_json = json!({
-                    "error": "Internal Server Error",
-                    "message" : "Failed to encode the json file"});
-                (StatusCode::INTERNAL_SERVER_ERROR, Json(error_json))
+            if trace {
+                response_json["trace"] = json!(output.trace);
             }
-        },
-        Err(status) => status,
+            if report_unconsumed {
+                response_json["unconsumed"] = json!(output.unconsumed);
+            }
+            (StatusCode::OK, Json(response_json))
+        }
+        Err(_enc_error) => {
+            let error_json = json!({
+                "error": "Internal Server Error",
+                "message" : "Failed to encode the json file"});
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(error_json))
+        }
     }
-    // let encoded_json = encode_json_file(output_file)?;
-
-    // // Return the file content along with the appropriate headers
-    // let response_json = json!({"content": encoded_json});
-    // (StatusCode::OK, Json(response_json))
-    // // Ok(output_file.into_response())
 }
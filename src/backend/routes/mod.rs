@@ -2,12 +2,16 @@ pub mod api;
 pub mod root;
 pub mod translate_file;
 
-use axum::{extract::DefaultBodyLimit, routing::get, routing::post, Router};
+use axum::{extract::DefaultBodyLimit, http::StatusCode, routing::get, routing::post, Json, Router};
 //use save_file::save_file;
 use api::api;
 use root::root;
 use translate_file::translate_file;
 
+use serde_json::Value;
+
+use crate::backend::error::ConversionError;
+
 pub fn create_router() -> Router {
     Router::new()
         .route(
@@ -18,3 +22,21 @@ pub fn create_router() -> Router {
         .route("/", get(root))
     // .layer(tower_http::trace::TraceLayer::new_for_http())
 }
+
+/// The http status code returned when a conversion fails.
+/// Problems with the posted credential are the client's fault, a broken mapping or output is ours.
+pub fn conversion_error_status(error: &ConversionError) -> StatusCode {
+    match error {
+        ConversionError::InvalidArguments(_) => StatusCode::BAD_REQUEST,
+        ConversionError::InvalidInput(_) | ConversionError::Transformation { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+        ConversionError::InvalidMapping(_)
+        | ConversionError::Output(_)
+        | ConversionError::InvalidSchema(_)
+        | ConversionError::InvalidFormatRegistry(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+/// The response to a failed conversion, with the json body describing the error, see `ConversionError::to_json`.
+pub fn conversion_error_response(error: &ConversionError) -> (StatusCode, Json<Value>) {
+    (conversion_error_status(error), Json(error.to_json()))
+}
@@ -4,8 +4,25 @@ use axum::{
     // Router,
 };
 
-pub async fn root() -> Html<&'static str> {
-    Html(
+use crate::state::Mapping;
+
+pub async fn root() -> Html<String> {
+    // One option for every registered conversion that has a default mapping
+    let options: String = match Mapping::all() {
+        Ok(mappings) => mappings
+            .into_iter()
+            .filter(|mapping| mapping.default_mapping().is_some())
+            .map(|mapping| {
+                format!(
+                    "\n                            <option value=\"{0}\">{0}</option>",
+                    mapping
+                )
+            })
+            .collect(),
+        Err(_) => String::new(),
+    };
+
+    Html(format!(
         r#"
         <!doctype html>
         <html>
@@ -26,9 +43,7 @@ pub async fn root() -> Html<&'static str> {
                     <label>
                         type of translation:
                             <select id="translation" name="translation">
-                            <option value="">--Please choose an option--</option>
-                            <option value="OBv3ToELM">OBv3ToELM</option>
-                            <option value="ELMToOBv3">ELMToOBv3</option>
+                            <option value="">--Please choose an option--</option>{}
                             </select>
                     </label>
                     <p>
@@ -40,5 +55,6 @@ pub async fn root() -> Html<&'static str> {
             </body>
         </html>
         "#,
-    )
+        options
+    ))
 }
@@ -5,74 +5,58 @@ use axum::{
     // Router,
     http::{header, HeaderMap, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
+    Json,
 };
+use serde_json::{json, Value};
 
-use crate::backend::headless_cli::load_files_apply_transformations;
-use crate::state::{AppState, Mapping};
-use std::{fs::File, io::Write, path::Path};
-use tokio::fs;
+use crate::backend::convert::{convert, load_mapping};
+use crate::backend::formats::FormatRegistry;
+use crate::backend::jwt::read_credential;
+use crate::backend::routes::conversion_error_response;
+use crate::state::Mapping;
+use std::path::Path;
 
-pub async fn translate_file(mut multipart: Multipart) -> Result<Response, (StatusCode, String)> {
-    // Create directories for uploads and outputs if they don't exist
-    let upload_dir = "uploads";
-    let output_dir = "outputs";
-    fs::create_dir_all(upload_dir).await.map_err(|_| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "Failed to create upload directory".to_string(),
-        )
-    })?;
-    fs::create_dir_all(output_dir).await.map_err(|_| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "Failed to create output directory".to_string(),
-        )
-    })?;
-
-    // Handle the file upload
-    let mut input_file_path = String::new();
+pub async fn translate_file(mut multipart: Multipart) -> Result<Response, (StatusCode, Json<Value>)> {
+    // Handle the file upload, the file is kept in memory and never written to disk
+    let mut input_file_name = String::new();
+    let mut input_data = Vec::new();
     let mut mapping_file_name = String::new();
-    let mut mapping_type = Mapping::default();
+    let mut mapping_type = None;
+
+    // The translations are looked up in the registered formats, which must be readable
+    FormatRegistry::global().map_err(|error| conversion_error_response(&error))?;
 
     while let Some(field) = multipart
         .next_field()
         .await
-        .map_err(|_| (StatusCode::BAD_REQUEST, "Failed to process uploaded file".to_string()))?
+        .map_err(|_| error_response(StatusCode::BAD_REQUEST, "Failed to process uploaded file"))?
     {
-        let name = field.name().unwrap().to_string();
+        let name = field.name().unwrap_or_default().to_string();
         match name.as_str() {
             "input_file" => match process_file_field(&field) {
                 Ok(file_name) => {
-                    input_file_path = format!("{}/{}", upload_dir, file_name);
-                    let mut file = File::create(&input_file_path)
-                        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Failed to create file".to_string()))?;
-                    let data = field.bytes().await.map_err(|_| {
-                        (
-                            StatusCode::INTERNAL_SERVER_ERROR,
-                            "Failed to read file data".to_string(),
-                        )
-                    })?;
-                    file.write_all(&data)
-                        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Failed to write to file".to_string()))?;
+                    input_file_name = file_name;
+                    input_data = field
+                        .bytes()
+                        .await
+                        .map_err(|_| error_response(StatusCode::INTERNAL_SERVER_ERROR, "Failed to read file data"))?
+                        .to_vec();
                 }
                 Err(e) => eprintln!("Error: {}", e),
             },
             "translation" => {
-                // Mapping::OBv3ToELM => "OBv3".to_string(),
-                // Mapping::ELMToOBv3 => "ELM".to_string(),
                 if let Ok(translation_value) = field.text().await {
-                    // Match on the value of the text field and call different functions
-                    match translation_value.as_str() {
-                        "OBv3ToELM" => {
-                            mapping_file_name = "json/mapping/custom_mapping_OBv3_ELM_latest.json".to_string();
-                            mapping_type = Mapping::OBv3ToELM;
+                    // The translation names the formats of a registered conversion with a default mapping, e.g. OBv3ToELM
+                    match Mapping::from_name(&translation_value)
+                        .map_err(|error| conversion_error_response(&error))?
+                        .and_then(|mapping| Some((mapping, mapping.default_mapping()?)))
+                    {
+                        Some((mapping, mapping_file)) => {
+                            mapping_file_name = mapping_file.to_string();
+                            mapping_type = Some(mapping);
                         }
-                        "ELMToOBv3" => {
-                            mapping_file_name = "json/mapping/custom_mapping_ELM_OBv3_latest.json".to_string();
-                            mapping_type = Mapping::ELMToOBv3;
-                        }
-                        _ => {
-                            return Err((
+                        None => {
+                            return Err(error_response(
                                 StatusCode::BAD_REQUEST,
                                 format!("Invalid translation value: {}", translation_value),
                             ))
@@ -80,74 +64,38 @@ pub async fn translate_file(mut multipart: Multipart) -> Result<Response, (Statu
                     };
                 } else {
                     // Handle the case where reading the field text fails
-                    return Err((StatusCode::BAD_REQUEST, "Failed to read translation value".to_string()));
+                    return Err(error_response(
+                        StatusCode::BAD_REQUEST,
+                        "Failed to read translation value",
+                    ));
                 }
             }
-            &_ => return Err((StatusCode::BAD_REQUEST, "Received unwanted values".to_string())),
+            &_ => return Err(error_response(StatusCode::BAD_REQUEST, "Received unwanted values")),
         }
     }
 
-    // Define the output file path
+    // Define the output file name, the output is json also when the uploaded file is a JWT
     let output_file_name = format!(
         "translated_{}",
-        Path::new(&input_file_path).file_name().unwrap().to_str().unwrap()
+        Path::new(&input_file_name)
+            .with_extension("json")
+            .file_name()
+            .and_then(|s| s.to_str())
+            .unwrap_or("credential.json")
     );
-    let output_file_path = format!("{}/{}", output_dir, output_file_name);
-
-    // start mapping based on the input form the API
-    // 1 create a state needed for the mapping tool
-    // 2 load all hte state elements needed for mapping
 
-    //let mut state = AppState::default();
-    let mut state = AppState {
-        input_path: input_file_path,
-        output_path: output_file_path.clone(),
-        mapping_path: mapping_file_name,
-        mapping: mapping_type,
-        ..Default::default()
-    };
-    // state.input_path = input_file_path;
-    // state.output_path = output_file_path.clone();
-    // state.mapping_path = mapping_file_name;
-    // state.mapping = mapping_type;
+    // start mapping based on the input form the API, a credential secured in a JWT or JWS is unwrapped first
+    let (input_credential, _) = read_credential(&String::from_utf8_lossy(&input_data))
+        .map_err(|_| error_response(StatusCode::BAD_REQUEST, "Uploaded file is not a valid json or jwt file"))?;
 
-    load_files_apply_transformations(&mut state);
+    let mapping_type = mapping_type
+        .ok_or_else(|| error_response(StatusCode::BAD_REQUEST, "Invalid translation value: no key found"))?;
+    let output = load_mapping(&mapping_file_name, mapping_type)
+        .and_then(|transformations| convert(&input_credential, mapping_type, &transformations))
+        .map_err(|error| conversion_error_response(&error))?;
 
-    // Return the translated file as a response
-    // 1 load file from fs into mem
-    // 2 remove file from fs
-    // 3 push mem to http output
-
-    let output_file = fs::read(&output_file_path).await.map_err(|_| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "Failed to read output file".to_string(),
-        )
-    })?;
-    fs::remove_file(state.input_path).await.map_err(|_| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "Failed to remove output file".to_string(),
-        )
-    })?;
-    fs::remove_file(state.output_path).await.map_err(|_| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "Failed to remove output file".to_string(),
-        )
-    })?;
-
-    //println!("state_exitwarning: {:#?}", state.exit_warning);
-    if state.exit_warning {
-        return Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "Failed to encode the json file".to_string(),
-        ));
-    }
-    // let error_json = json!({
-    //     "error": "Internal Server Error",
-    //     "message" : "Failed to encode the json file"});
-    // return (StatusCode::INTERNAL_SERVER_ERROR, Json(error_json));
+    let output_file = serde_json::to_vec_pretty(&output.credential)
+        .map_err(|_| error_response(StatusCode::INTERNAL_SERVER_ERROR, "Failed to encode the json file"))?;
 
     // Set the headers, including content disposition for download
     let mut headers = HeaderMap::new();
@@ -160,13 +108,18 @@ pub async fn translate_file(mut multipart: Multipart) -> Result<Response, (Statu
     );
     headers.insert(
         header::CONTENT_DISPOSITION,
-        HeaderValue::from_str(&format!("attachment; filename=\"{}\"", long_output_file_name)).unwrap(),
+        HeaderValue::from_str(&format!("attachment; filename=\"{}\"", long_output_file_name))
+            .map_err(|_| error_response(StatusCode::INTERNAL_SERVER_ERROR, "Failed to set the output file name"))?,
     );
 
     // Return the file content along with the appropriate headers
     Ok((headers, output_file).into_response())
+}
 
-    // Ok(output_file.into_response())
+/// The response to a failed upload, with the json body the api and failed conversions have.
+fn error_response(status: StatusCode, message: impl Into<String>) -> (StatusCode, Json<Value>) {
+    let error = status.canonical_reason().unwrap_or_default();
+    (status, Json(json!({ "error": error, "message": message.into() })))
 }
 
 fn process_file_field(field: &Field) -> Result<String, String> {
@@ -0,0 +1,170 @@
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+use serde_json::{Map, Value};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::File,
+    io::BufReader,
+    sync::{Arc, RwLock},
+    time::SystemTime,
+};
+
+use crate::backend::error::TransformationError;
+
+lazy_static! {
+    /// Placeholders in the strings of a template, e.g. `{{title}}`.
+    static ref PLACEHOLDER: Regex = Regex::new(r"\{\{\s*([A-Za-z0-9_-]+)\s*\}\}").unwrap();
+    /// The templates read so far by their path, with the time the file was modified when it was read, so a template
+    /// applied to every element of an array is read once.
+    static ref TEMPLATES: RwLock<HashMap<String, (SystemTime, Arc<Value>)>> = RwLock::new(HashMap::new());
+}
+
+/// Load a json template file, see `fill_template` for its placeholders.
+/// A template file is read again when it was modified since it was last loaded.
+pub fn load_template(template_path: &str) -> Result<Arc<Value>, TransformationError> {
+    let modified = std::fs::metadata(template_path)
+        .and_then(|metadata| metadata.modified())
+        .map_err(|error| TransformationError::InvalidTemplate {
+            path: template_path.to_string(),
+            message: error.to_string(),
+        })?;
+
+    if let Some((_, template)) = TEMPLATES
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .get(template_path)
+        .filter(|(read_at, _)| *read_at == modified)
+    {
+        return Ok(template.clone());
+    }
+
+    let template = Arc::new(read_template(template_path)?);
+    TEMPLATES
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .insert(template_path.to_string(), (modified, template.clone()));
+
+    Ok(template)
+}
+
+fn read_template(template_path: &str) -> Result<Value, TransformationError> {
+    let invalid = |message: String| TransformationError::InvalidTemplate {
+        path: template_path.to_string(),
+        message,
+    };
+
+    let file = File::open(template_path).map_err(|error| invalid(error.to_string()))?;
+    serde_json::from_reader(BufReader::new(file)).map_err(|error| invalid(error.to_string()))
+}
+
+/// Fill the placeholders of a template with the values bound to them.
+/// A string that is exactly one placeholder is replaced by the bound value as is, other strings get the bound values
+/// written into them as text, using the first value when a placeholder is bound to multiple matches.
+/// Fields and array elements with a placeholder without a value are left out, as are the objects and arrays that
+/// are left empty because of that, so no template data is written without the values it was meant to hold.
+///
+/// # Arguments
+/// - `template`: The json template, loaded by `load_template`.
+/// - `template_path`: The path of the template, used in error messages.
+/// - `bindings`: The placeholders and the JsonPaths bound to them, to find unbound placeholders.
+/// - `values`: The values found for the placeholders, placeholders without a value are missing.
+///
+/// # Returns
+/// - `Ok(Some(Value))`: The filled template.
+/// - `Ok(None)`: If nothing of the template is left after leaving out the missing values.
+/// - `Err(TransformationError)`: If the template contains a placeholder that isn't bound.
+pub fn fill_template(
+    template: &Value,
+    template_path: &str,
+    bindings: &BTreeMap<String, String>,
+    values: &HashMap<String, Value>,
+) -> Result<Option<Value>, TransformationError> {
+    match template {
+        Value::String(string) => {
+            for captures in PLACEHOLDER.captures_iter(string) {
+                if !bindings.contains_key(&captures[1]) {
+                    return Err(TransformationError::InvalidTemplate {
+                        path: template_path.to_string(),
+                        message: format!("the placeholder `{}` is not bound to a source path", &captures[1]),
+                    });
+                }
+                if !values.contains_key(&captures[1]) {
+                    return Ok(None);
+                }
+            }
+
+            match PLACEHOLDER.captures(string) {
+                Some(captures) if captures[0].len() == string.len() => Ok(values.get(&captures[1]).cloned()),
+                Some(_) => Ok(Some(Value::String(
+                    PLACEHOLDER
+                        .replace_all(string, |captures: &Captures| value_to_text(&values[&captures[1]]))
+                        .into_owned(),
+                ))),
+                None => Ok(Some(template.clone())),
+            }
+        }
+        Value::Array(elements) => {
+            let mut filled_elements = Vec::new();
+            for element in elements {
+                if let Some(filled_element) = fill_template(element, template_path, bindings, values)? {
+                    filled_elements.push(filled_element);
+                }
+            }
+
+            if filled_elements.is_empty() && !elements.is_empty() {
+                return Ok(None);
+            }
+            Ok(Some(Value::Array(filled_elements)))
+        }
+        Value::Object(fields) => {
+            let mut filled_fields = Map::new();
+            for (key, value) in fields {
+                if let Some(filled_value) = fill_template(value, template_path, bindings, values)? {
+                    filled_fields.insert(key.clone(), filled_value);
+                }
+            }
+
+            if filled_fields.is_empty() && !fields.is_empty() {
+                return Ok(None);
+            }
+            Ok(Some(Value::Object(filled_fields)))
+        }
+        _ => Ok(Some(template.clone())),
+    }
+}
+
+/// The text written into a template string for a bound value.
+fn value_to_text(value: &Value) -> String {
+    match value {
+        Value::String(string) => string.clone(),
+        Value::Array(values) => values.first().map(value_to_text).unwrap_or_default(),
+        value => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::time::Duration;
+
+    #[test]
+    fn reads_a_modified_template_again() {
+        let path = std::env::temp_dir().join(format!("template-{}.json", std::process::id()));
+        let template_path = path.to_str().unwrap();
+
+        std::fs::write(&path, r#"{"title": "{{title}}"}"#).unwrap();
+        assert_eq!(*load_template(template_path).unwrap(), json!({"title": "{{title}}"}));
+
+        std::fs::write(&path, r#"{"name": "{{title}}"}"#).unwrap();
+        let file = File::options().write(true).open(&path).unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(60)).unwrap();
+        assert_eq!(*load_template(template_path).unwrap(), json!({"name": "{{title}}"}));
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(
+            load_template(template_path),
+            Err(TransformationError::InvalidTemplate { .. })
+        ));
+    }
+}
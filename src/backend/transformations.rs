@@ -1,5 +1,19 @@
+use lazy_static::lazy_static;
+use regex::Regex;
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::backend::{
+    code_list::load_code_list,
+    date_time::{normalise_date_time, normalise_timestamp, DateTimeFormat},
+    error::TransformationError,
+};
+
+lazy_static! {
+    /// The placeholders of a `format` template, e.g. `{0}` for the first source.
+    pub static ref FORMAT_PLACEHOLDER: Regex = Regex::new(r"\{(\d+)\}").unwrap();
+}
 
 #[allow(non_camel_case_types)]
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -7,13 +21,68 @@ pub enum OneToOne {
     copy,
     toLowerCase,
     toUpperCase,
-    takeIndex { index: usize },
-    slice { index: usize },
+    takeIndex {
+        index: usize,
+    },
+    slice {
+        index: usize,
+    },
+    /// Replace every match of `pattern`, the `replacement` can refer to capture groups like `$1`.
+    regexReplace {
+        pattern: String,
+        replacement: String,
+    },
+    /// Take capture `group` of the first match of `pattern`, group 0 is the whole match.
+    regexExtract {
+        pattern: String,
+        group: usize,
+    },
+    /// Replace the value by its entry in the code list `file`, see `load_code_list`, arrays element by element.
+    lookup {
+        file: String,
+        #[serde(default, rename = "onMissing")]
+        on_missing: OnMissing,
+    },
+    /// Normalise a date or date and time to UTC in the `output` format, see `normalise_date_time`.
+    /// Numbers are read as unix timestamps, values that aren't recognised as a date are left unchanged.
+    dateTime {
+        #[serde(default)]
+        output: DateTimeFormat,
+    },
+    /// Wrap the value in a language map like `{"en": ["value"]}`, tagged with `lang`.
+    /// Without `lang` the first preferred language of the conversion is used, or `DEFAULT_LANGUAGE`.
+    toLangMap {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        lang: Option<String>,
+    },
+    /// Take the value of the first `preferred` language found in a language map, then of `DEFAULT_LANGUAGE`, or of its
+    /// first language when none of them is found. The preferred languages of the conversion are tried before `preferred`.
+    fromLangMap {
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        preferred: Vec<String>,
+    },
+}
+
+/// The language of the values tagged by `toLangMap` when neither the mapping nor the conversion has a language,
+/// and the language taken by `fromLangMap` when none of the preferred languages is found.
+pub const DEFAULT_LANGUAGE: &str = "en";
+
+/// What a lookup does with a value that isn't in its code list.
+#[allow(non_camel_case_types)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub enum OnMissing {
+    /// Leave the value out.
+    #[default]
+    drop,
+    /// Use the given value instead.
+    default(Value),
+    /// Fail the transformation.
+    error,
 }
 
 impl OneToOne {
-    pub fn apply(&self, value: Value) -> Value {
-        match self {
+    pub fn apply(&self, value: Value) -> Result<Value, TransformationError> {
+        Ok(match self {
             OneToOne::copy => value,
             OneToOne::toLowerCase => {
                 if let Value::String(s) = value {
@@ -63,38 +132,202 @@ impl OneToOne {
                     value
                 }
             }
+            OneToOne::regexReplace { pattern, replacement } => match &value {
+                Value::String(s) => Value::String(compile_regex(pattern)?.replace_all(s, replacement).into_owned()),
+                _ => value,
+            },
+            OneToOne::regexExtract { pattern, group } => match &value {
+                // Without a match there is nothing to extract, so nothing is written to the destination
+                Value::String(s) => compile_regex(pattern)?
+                    .captures(s)
+                    .and_then(|captures| captures.get(*group))
+                    .map(|capture| Value::String(capture.as_str().to_string()))
+                    .unwrap_or_default(),
+                _ => value,
+            },
+            OneToOne::lookup { file, on_missing } => {
+                let code_list = load_code_list(file)?;
+                match value {
+                    Value::Null => value,
+                    Value::Array(values) => {
+                        let mut targets = Vec::new();
+                        for value in values {
+                            let target = look_up(&code_list, file, on_missing, value)?;
+                            if !target.is_null() {
+                                targets.push(target);
+                            }
+                        }
+                        if targets.is_empty() {
+                            Value::Null
+                        } else {
+                            Value::Array(targets)
+                        }
+                    }
+                    value => look_up(&code_list, file, on_missing, value)?,
+                }
+            }
+            OneToOne::dateTime { output } => {
+                let normalised = match &value {
+                    Value::String(s) => normalise_date_time(s, *output),
+                    Value::Number(number) => number
+                        .as_i64()
+                        .and_then(|timestamp| normalise_timestamp(timestamp, *output)),
+                    _ => None,
+                };
+                normalised.map(Value::String).unwrap_or(value)
+            }
+            OneToOne::toLangMap { lang } => {
+                let lang = lang.clone().unwrap_or_else(|| DEFAULT_LANGUAGE.to_string());
+                match value {
+                    Value::String(_) => Value::Object(Map::from_iter([(lang, Value::Array(vec![value]))])),
+                    Value::Array(_) => Value::Object(Map::from_iter([(lang, value)])),
+                    _ => value,
+                }
+            }
+            OneToOne::fromLangMap { preferred } => match &value {
+                Value::Object(lang_map) => {
+                    let translation = preferred
+                        .iter()
+                        .map(String::as_str)
+                        .chain([DEFAULT_LANGUAGE])
+                        .find_map(|lang| lang_map.get(lang).filter(|translation| !translation.is_null()))
+                        .or_else(|| lang_map.values().next());
+                    match translation {
+                        Some(Value::Array(translations)) => translations.first().cloned().unwrap_or_default(),
+                        Some(translation) => translation.clone(),
+                        None => Value::Null,
+                    }
+                }
+                _ => value,
+            },
+        })
+    }
+
+    /// Apply the preferred languages of a conversion, in order of preference, to `toLangMap` and `fromLangMap`.
+    pub fn prefer_languages(&mut self, languages: &[String]) {
+        match self {
+            OneToOne::toLangMap { lang } if lang.is_none() => *lang = languages.first().cloned(),
+            OneToOne::fromLangMap { preferred } => {
+                preferred.splice(0..0, languages.iter().cloned());
+            }
+            _ => {}
         }
     }
 }
 
+/// Look a single value up in a code list, numbers and booleans are looked up as written in json.
+fn look_up(
+    code_list: &HashMap<String, Value>,
+    file: &str,
+    on_missing: &OnMissing,
+    value: Value,
+) -> Result<Value, TransformationError> {
+    let key = value_to_string(&value).unwrap_or_default();
+    match (code_list.get(&key), on_missing) {
+        (Some(target), _) => Ok(target.clone()),
+        (None, OnMissing::drop) => Ok(Value::Null),
+        (None, OnMissing::default(default)) => Ok(default.clone()),
+        (None, OnMissing::error) => Err(TransformationError::MissingCode {
+            path: file.to_string(),
+            value: key,
+        }),
+    }
+}
+
+fn compile_regex(pattern: &str) -> Result<Regex, TransformationError> {
+    Regex::new(pattern).map_err(|error| TransformationError::InvalidRegex {
+        pattern: pattern.to_string(),
+        message: error.to_string(),
+    })
+}
+
 #[allow(non_camel_case_types)]
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum OneToMany {
-    split,
+    /// Split on every character in `dividers`.
+    split { dividers: String },
+    /// Split on every match of the regular expression `pattern`.
+    regexSplit { pattern: String },
+}
+
+impl OneToMany {
+    /// Split the value into at most `amount` parts, one for each destination.
+    /// The last part holds the remainder of the string, so no data is lost when there are fewer destinations than dividers.
+    /// An array is distributed over the destinations element by element instead.
+    pub fn apply(&self, value: Value, amount: usize) -> Result<Vec<Value>, TransformationError> {
+        let string = match value {
+            Value::String(string) => string,
+            Value::Array(array) => return Ok(array.into_iter().take(amount).collect()),
+            _ => return Ok(Vec::new()),
+        };
+
+        let parts: Vec<&str> = match self {
+            OneToMany::split { dividers } => string.splitn(amount, |c| dividers.contains(c)).collect(),
+            OneToMany::regexSplit { pattern } => compile_regex(pattern)?.splitn(&string, amount).collect(),
+        };
+
+        Ok(parts
+            .into_iter()
+            .map(|part| Value::String(part.trim().to_string()))
+            .collect())
+    }
 }
 
 #[allow(non_camel_case_types)]
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum ManyToOne {
     concat,
+    /// Join the values with a separator, missing values are skipped.
+    join {
+        separator: String,
+    },
+    /// Fill the values into a template like `"{0}, {1} ({2})"`, missing values are left empty and the result is trimmed.
+    format {
+        template: String,
+    },
 }
 
 impl ManyToOne {
-    pub fn apply(&self, values: Vec<Value>) -> Value {
+    /// Combine the values of all sources into one string, `None` is a source which wasn't found.
+    pub fn apply(&self, values: Vec<Option<Value>>) -> Value {
+        let strings: Vec<Option<String>> = values
+            .iter()
+            .map(|value| value.as_ref().and_then(value_to_string))
+            .collect();
+
         match self {
-            ManyToOne::concat => {
-                let mut s = String::new();
-                for value in values {
-                    if let Value::String(string) = value {
-                        s.push_str(&string);
-                    }
-                }
-                Value::String(s)
+            ManyToOne::concat => Value::String(strings.into_iter().flatten().collect()),
+            ManyToOne::join { separator } => Value::String(
+                strings
+                    .into_iter()
+                    .flatten()
+                    .filter(|string| !string.is_empty())
+                    .collect::<Vec<_>>()
+                    .join(separator),
+            ),
+            ManyToOne::format { template } => {
+                let formatted = FORMAT_PLACEHOLDER.replace_all(template, |captures: &regex::Captures| {
+                    captures[1]
+                        .parse::<usize>()
+                        .ok()
+                        .and_then(|index| strings.get(index).cloned().flatten())
+                        .unwrap_or_default()
+                });
+                Value::String(formatted.trim().to_string())
             }
         }
     }
 }
 
+/// Stringify a value to be combined with others, numbers and booleans are written as in json, null is left out.
+fn value_to_string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(string) => Some(string.clone()),
+        Value::Null => None,
+        value => Some(value.to_string()),
+    }
+}
+
 #[allow(non_camel_case_types)]
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum StringToOne {
@@ -179,160 +412,6 @@ impl IdentifierToObject {
     }
 }
 
-#[allow(non_camel_case_types)]
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub enum ImageToIndividualDisplay {
-    imageToIndividualDisplay,
-}
-
-impl ImageToIndividualDisplay {
-    pub fn apply(&self, value: Value) -> Value {
-        match self {
-            ImageToIndividualDisplay::imageToIndividualDisplay => value,
-        }
-    }
-}
-
-#[allow(non_camel_case_types)]
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub enum AssessmentToProvenBy {
-    assessmentToProvenBy,
-}
-
-impl AssessmentToProvenBy {
-    pub fn apply(&self, value: Value) -> Value {
-        match self {
-            AssessmentToProvenBy::assessmentToProvenBy => value,
-        }
-    }
-}
-
-#[allow(non_camel_case_types)]
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub enum ObjectToNoteLiteral {
-    objectToNoteLiteral,
-}
-
-impl ObjectToNoteLiteral {
-    pub fn apply(&self, value: Value) -> Value {
-        match self {
-            ObjectToNoteLiteral::objectToNoteLiteral => value,
-        }
-    }
-}
-
-#[allow(non_camel_case_types)]
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub enum TranslateLearningOutcome {
-    translateLearningOutcome,
-}
-
-impl TranslateLearningOutcome {
-    pub fn apply(&self, value: Value) -> Value {
-        match self {
-            TranslateLearningOutcome::translateLearningOutcome => value,
-        }
-    }
-}
-
-#[allow(non_camel_case_types)]
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub enum CreateLearningOutcomeSummary {
-    createLearningOutcomeSummary,
-}
-
-impl CreateLearningOutcomeSummary {
-    pub fn apply(&self, value: Value) -> Value {
-        match self {
-            CreateLearningOutcomeSummary::createLearningOutcomeSummary => value,
-        }
-    }
-}
-
-#[allow(non_camel_case_types)]
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub enum CreditToSpecifiedByObject {
-    creditToSpecifiedByObject,
-}
-
-impl CreditToSpecifiedByObject {
-    pub fn apply(&self, value: Value) -> Value {
-        match self {
-            CreditToSpecifiedByObject::creditToSpecifiedByObject => value,
-        }
-    }
-}
-
-#[allow(non_camel_case_types)]
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub enum EqfToSpecifiedByQualification {
-    eqfToSpecifiedByQualification,
-}
-
-impl EqfToSpecifiedByQualification {
-    pub fn apply(&self, value: Value) -> Value {
-        match self {
-            EqfToSpecifiedByQualification::eqfToSpecifiedByQualification => value,
-        }
-    }
-}
-
-#[allow(non_camel_case_types)]
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub enum LearningSettingToSpecifiedByObject {
-    learningSettingToSpecifiedByObject,
-}
-
-impl LearningSettingToSpecifiedByObject {
-    pub fn apply(&self, value: Value) -> Value {
-        match self {
-            LearningSettingToSpecifiedByObject::learningSettingToSpecifiedByObject => value,
-        }
-    }
-}
-
-#[allow(non_camel_case_types)]
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub enum TitleToSpecifiedByObject {
-    titleToSpecifiedByObject,
-}
-
-impl TitleToSpecifiedByObject {
-    pub fn apply(&self, value: Value) -> Value {
-        match self {
-            TitleToSpecifiedByObject::titleToSpecifiedByObject => value,
-        }
-    }
-}
-
-#[allow(non_camel_case_types)]
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub enum ImageToMediaObject {
-    imageToMediaObject,
-}
-
-impl ImageToMediaObject {
-    pub fn apply(&self, value: Value) -> Value {
-        match self {
-            ImageToMediaObject::imageToMediaObject => value,
-        }
-    }
-}
-
-#[allow(non_camel_case_types)]
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub enum AddressToLocation {
-    addressToLocation,
-}
-
-impl AddressToLocation {
-    pub fn apply(&self, value: Value) -> Value {
-        match self {
-            AddressToLocation::addressToLocation => value,
-        }
-    }
-}
-
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(untagged)]
 pub enum Transformation {
@@ -340,102 +419,272 @@ pub enum Transformation {
         type_: OneToOne,
         source: DataLocation,
         destination: DataLocation,
+        #[serde(flatten)]
+        options: EntryOptions,
+    },
+    /// Apply the OneToOne steps in order, each step transforms the output of the previous one.
+    Pipeline {
+        pipeline: Vec<OneToOne>,
+        source: DataLocation,
+        destination: DataLocation,
+        #[serde(flatten)]
+        options: EntryOptions,
     },
     StringToOne {
         type_: StringToOne,
         source: StringValue,
         destination: DataLocation,
+        #[serde(flatten)]
+        options: EntryOptions,
     },
     StringArrayToOne {
         type_: StringArrayToOne,
         source: StringArrayValue,
         destination: DataLocation,
+        #[serde(flatten)]
+        options: EntryOptions,
     },
     MarkdownToJson {
         type_: MarkdownToJson,
         source: DataLocation,
         destination: DataLocation,
+        #[serde(flatten)]
+        options: EntryOptions,
     },
     JsonToMarkdown {
         type_: JsonToMarkdown,
         source: DataLocation,
         destination: DataLocation,
+        #[serde(flatten)]
+        options: EntryOptions,
     },
     AddIdentifier {
         type_: AddIdentifier,
         source: DataTypeLocation,
         destination: DataLocation,
+        #[serde(flatten)]
+        options: EntryOptions,
     },
     IdentifierToObject {
         type_: IdentifierToObject,
         source: DataTypeLocation,
         destination: DataLocation,
-    },
-    ImageToIndividualDisplay {
-        type_: ImageToIndividualDisplay,
-        source: DataLocation,
-        destination: DataLocation,
-    },
-    ImageToMediaObject {
-        type_: ImageToMediaObject,
-        source: DataLocation,
-        destination: DataLocation,
-    },
-    TitleToSpecifiedByObject {
-        type_: TitleToSpecifiedByObject,
-        source: DataLocation,
-        destination: DataLocation,
-    },
-    EqfToSpecifiedByQualification {
-        type_: EqfToSpecifiedByQualification,
-        source: DataLocation,
-        destination: DataLocation,
-    },
-    LearningSettingToSpecifiedByObject {
-        type_: LearningSettingToSpecifiedByObject,
-        source: DataLocation,
-        destination: DataLocation,
-    },
-    CreditToSpecifiedByObject {
-        type_: CreditToSpecifiedByObject,
-        source: DataLocation,
-        destination: DataLocation,
-    },
-    AssessmentToProvenBy {
-        type_: AssessmentToProvenBy,
-        source: DataLocation,
-        destination: DataLocation,
-    },
-    ObjectToNoteLiteral {
-        type_: ObjectToNoteLiteral,
-        source: DataLocation,
-        destination: DataLocation,
-    },
-    TranslateLearningOutcome {
-        type_: TranslateLearningOutcome,
-        source: DataLocation,
-        destination: DataLocation,
-    },
-    CreateLearningOutcomeSummary {
-        type_: CreateLearningOutcomeSummary,
-        source: DataLocation,
-        destination: DataLocation,
-    },
-    AddressToLocation {
-        type_: AddressToLocation,
-        source: DataLocation,
-        destination: DataLocation,
+        #[serde(flatten)]
+        options: EntryOptions,
     },
     OneToMany {
         type_: OneToMany,
         source: DataLocation,
         destinations: Vec<DataLocation>,
+        #[serde(flatten)]
+        options: EntryOptions,
     },
     ManyToOne {
         type_: ManyToOne,
         sources: Vec<DataLocation>,
         destination: DataLocation,
+        #[serde(flatten)]
+        options: EntryOptions,
+    },
+    /// Fill the placeholders of the json template file `template` with the values found at the source paths bound to
+    /// them in `bindings`, see `backend::template`.
+    Template {
+        template: String,
+        bindings: BTreeMap<String, String>,
+        destination: DataLocation,
+        #[serde(flatten)]
+        options: EntryOptions,
     },
+    /// Apply the `TransformationHandler` registered under the name `type_`, see `backend::handlers`.
+    /// This variant comes last, so the names of the transformations above can't be taken by a handler.
+    Handler {
+        type_: String,
+        source: DataLocation,
+        destination: DataLocation,
+        /// Passed to the handler as is, for handlers that can be configured per mapping entry.
+        #[serde(default, skip_serializing_if = "Value::is_null")]
+        parameters: Value,
+        #[serde(flatten)]
+        options: EntryOptions,
+    },
+}
+
+impl Transformation {
+    /// The source path(s) of the transformation, `None` for transformations with a literal source value.
+    pub fn source_path(&self) -> Option<String> {
+        match self {
+            Transformation::OneToOne { source, .. }
+            | Transformation::Pipeline { source, .. }
+            | Transformation::Handler { source, .. }
+            | Transformation::MarkdownToJson { source, .. }
+            | Transformation::JsonToMarkdown { source, .. }
+            | Transformation::OneToMany { source, .. } => Some(source.path.clone()),
+            Transformation::AddIdentifier { source, .. } | Transformation::IdentifierToObject { source, .. } => {
+                Some(source.path.clone())
+            }
+            Transformation::ManyToOne { sources, .. } => Some(
+                sources
+                    .iter()
+                    .map(|source| source.path.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            ),
+            Transformation::Template { bindings, .. } => {
+                Some(bindings.values().map(String::as_str).collect::<Vec<_>>().join(", "))
+            }
+            Transformation::StringToOne { .. } | Transformation::StringArrayToOne { .. } => None,
+        }
+    }
+
+    /// Mutable access to the source path(s) of the transformation.
+    pub fn source_paths_mut(&mut self) -> Vec<&mut String> {
+        match self {
+            Transformation::OneToOne { source, .. }
+            | Transformation::Pipeline { source, .. }
+            | Transformation::Handler { source, .. }
+            | Transformation::MarkdownToJson { source, .. }
+            | Transformation::JsonToMarkdown { source, .. }
+            | Transformation::OneToMany { source, .. } => vec![&mut source.path],
+            Transformation::AddIdentifier { source, .. } | Transformation::IdentifierToObject { source, .. } => {
+                vec![&mut source.path]
+            }
+            Transformation::ManyToOne { sources, .. } => sources.iter_mut().map(|source| &mut source.path).collect(),
+            Transformation::Template { bindings, .. } => bindings.values_mut().collect(),
+            Transformation::StringToOne { .. } | Transformation::StringArrayToOne { .. } => Vec::new(),
+        }
+    }
+
+    /// The destination path(s) of the transformation.
+    pub fn destination_path(&self) -> Option<String> {
+        match self {
+            Transformation::OneToMany { destinations, .. } => Some(
+                destinations
+                    .iter()
+                    .map(|destination| destination.path.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            ),
+            Transformation::OneToOne { destination, .. }
+            | Transformation::Pipeline { destination, .. }
+            | Transformation::Handler { destination, .. }
+            | Transformation::Template { destination, .. }
+            | Transformation::StringToOne { destination, .. }
+            | Transformation::StringArrayToOne { destination, .. }
+            | Transformation::MarkdownToJson { destination, .. }
+            | Transformation::JsonToMarkdown { destination, .. }
+            | Transformation::AddIdentifier { destination, .. }
+            | Transformation::IdentifierToObject { destination, .. }
+            | Transformation::ManyToOne { destination, .. } => Some(destination.path.clone()),
+        }
+    }
+
+    /// Mutable access to the destination path(s) of the transformation.
+    pub fn destination_paths_mut(&mut self) -> Vec<&mut String> {
+        match self {
+            Transformation::OneToMany { destinations, .. } => destinations
+                .iter_mut()
+                .map(|destination| &mut destination.path)
+                .collect(),
+            Transformation::OneToOne { destination, .. }
+            | Transformation::Pipeline { destination, .. }
+            | Transformation::Handler { destination, .. }
+            | Transformation::Template { destination, .. }
+            | Transformation::StringToOne { destination, .. }
+            | Transformation::StringArrayToOne { destination, .. }
+            | Transformation::MarkdownToJson { destination, .. }
+            | Transformation::JsonToMarkdown { destination, .. }
+            | Transformation::AddIdentifier { destination, .. }
+            | Transformation::IdentifierToObject { destination, .. }
+            | Transformation::ManyToOne { destination, .. } => vec![&mut destination.path],
+        }
+    }
+
+    /// The destination(s) of the transformation.
+    pub fn destinations(&self) -> Vec<&DataLocation> {
+        match self {
+            Transformation::OneToMany { destinations, .. } => destinations.iter().collect(),
+            Transformation::OneToOne { destination, .. }
+            | Transformation::Pipeline { destination, .. }
+            | Transformation::Handler { destination, .. }
+            | Transformation::Template { destination, .. }
+            | Transformation::StringToOne { destination, .. }
+            | Transformation::StringArrayToOne { destination, .. }
+            | Transformation::MarkdownToJson { destination, .. }
+            | Transformation::JsonToMarkdown { destination, .. }
+            | Transformation::AddIdentifier { destination, .. }
+            | Transformation::IdentifierToObject { destination, .. }
+            | Transformation::ManyToOne { destination, .. } => vec![destination],
+        }
+    }
+    /// The options of the mapping entry, like its `when` condition and fallbacks.
+    pub fn options(&self) -> &EntryOptions {
+        match self {
+            Transformation::OneToOne { options, .. }
+            | Transformation::Pipeline { options, .. }
+            | Transformation::Handler { options, .. }
+            | Transformation::Template { options, .. }
+            | Transformation::StringToOne { options, .. }
+            | Transformation::StringArrayToOne { options, .. }
+            | Transformation::MarkdownToJson { options, .. }
+            | Transformation::JsonToMarkdown { options, .. }
+            | Transformation::AddIdentifier { options, .. }
+            | Transformation::IdentifierToObject { options, .. }
+            | Transformation::OneToMany { options, .. }
+            | Transformation::ManyToOne { options, .. } => options,
+        }
+    }
+
+    /// Mutable access to the options of the mapping entry.
+    pub fn options_mut(&mut self) -> &mut EntryOptions {
+        match self {
+            Transformation::OneToOne { options, .. }
+            | Transformation::Pipeline { options, .. }
+            | Transformation::Handler { options, .. }
+            | Transformation::Template { options, .. }
+            | Transformation::StringToOne { options, .. }
+            | Transformation::StringArrayToOne { options, .. }
+            | Transformation::MarkdownToJson { options, .. }
+            | Transformation::JsonToMarkdown { options, .. }
+            | Transformation::AddIdentifier { options, .. }
+            | Transformation::IdentifierToObject { options, .. }
+            | Transformation::OneToMany { options, .. }
+            | Transformation::ManyToOne { options, .. } => options,
+        }
+    }
+
+    /// The OneToOne steps of the transformation, empty for the transformations of other types.
+    pub fn steps_mut(&mut self) -> &mut [OneToOne] {
+        match self {
+            Transformation::OneToOne { type_, .. } => std::slice::from_mut(type_),
+            Transformation::Pipeline { pipeline, .. } => pipeline,
+            _ => &mut [],
+        }
+    }
+
+    /// The name of the transformation as written in the `type_` of the mapping entry, e.g. `copy` or `lookup`.
+    pub fn type_name(&self) -> String {
+        let type_ = match self {
+            Transformation::OneToOne { type_, .. } => serde_json::to_value(type_),
+            Transformation::StringToOne { type_, .. } => serde_json::to_value(type_),
+            Transformation::StringArrayToOne { type_, .. } => serde_json::to_value(type_),
+            Transformation::MarkdownToJson { type_, .. } => serde_json::to_value(type_),
+            Transformation::JsonToMarkdown { type_, .. } => serde_json::to_value(type_),
+            Transformation::AddIdentifier { type_, .. } => serde_json::to_value(type_),
+            Transformation::IdentifierToObject { type_, .. } => serde_json::to_value(type_),
+            Transformation::OneToMany { type_, .. } => serde_json::to_value(type_),
+            Transformation::ManyToOne { type_, .. } => serde_json::to_value(type_),
+            Transformation::Pipeline { .. } => return "pipeline".to_string(),
+            Transformation::Template { .. } => return "template".to_string(),
+            Transformation::Handler { type_, .. } => return type_.clone(),
+        };
+
+        // Transformations with options are written as an object with the name as its only key
+        match type_ {
+            Ok(Value::String(name)) => name,
+            Ok(Value::Object(fields)) => fields.keys().next().cloned().unwrap_or_default(),
+            _ => String::new(),
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -460,3 +709,134 @@ pub struct StringValue {
 pub struct StringArrayValue {
     pub value: Vec<String>,
 }
+
+/// Options shared by all transformations of a mapping file, next to their `type_`, `source` and `destination`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct EntryOptions {
+    /// The transformation is only applied when the condition holds for the input credential.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub when: Option<Condition>,
+    /// Source paths tried in order when the source path doesn't resolve to a value.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fallbacks: Vec<String>,
+    /// Written to the destination as is when neither the source nor a fallback resolves to a value.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<Value>,
+    /// How the value is merged into the value already at the destination, e.g. written there by an earlier entry.
+    #[serde(default, skip_serializing_if = "MergeStrategy::is_default")]
+    pub merge: MergeStrategy,
+}
+
+/// How a transformation merges its value into the value already at its destination.
+/// Objects are merged field by field with every strategy but `replace`, the strategies differ in how they merge arrays.
+#[allow(non_camel_case_types)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+pub enum MergeStrategy {
+    /// Merge the elements at the same index, elements beyond the end of the existing array are appended.
+    #[default]
+    byIndex,
+    /// Replace the value at the destination.
+    replace,
+    /// Append the value, or the elements of an array value, to the array at the destination.
+    append,
+    /// Merge the elements with the same `id`, elements without `id` or with a new one are appended. Generated nodes
+    /// with the placeholder id of their type, e.g. `urn:epass:note:generated`, are appended as well.
+    byId,
+}
+
+impl MergeStrategy {
+    fn is_default(&self) -> bool {
+        *self == MergeStrategy::default()
+    }
+}
+
+/// A condition on the source credential, a transformation with a `when` condition is only applied when it holds.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum Condition {
+    /// Holds when the nested condition doesn't hold.
+    Not { not: Box<Condition> },
+    /// Holds when all nested conditions hold.
+    AllOf {
+        #[serde(rename = "allOf")]
+        all_of: Vec<Condition>,
+    },
+    /// Holds when at least one of the nested conditions holds.
+    AnyOf {
+        #[serde(rename = "anyOf")]
+        any_of: Vec<Condition>,
+    },
+    /// Holds when the value found at the JsonPath `path` satisfies the predicate.
+    Predicate {
+        path: String,
+        #[serde(flatten)]
+        predicate: Predicate,
+    },
+}
+
+impl Condition {
+    /// Mutable access to the JsonPaths of the condition, including those of nested conditions.
+    pub fn paths_mut(&mut self) -> Vec<&mut String> {
+        match self {
+            Condition::Not { not } => not.paths_mut(),
+            Condition::AllOf { all_of: conditions } | Condition::AnyOf { any_of: conditions } => {
+                conditions.iter_mut().flat_map(Condition::paths_mut).collect()
+            }
+            Condition::Predicate { path, .. } => vec![path],
+        }
+    }
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum Predicate {
+    /// `true` when the path has a value other than null, `false` when it hasn't.
+    exists(bool),
+    /// The value is equal to the given json value.
+    equals(Value),
+    /// The value is a string, number or boolean matching the regular expression.
+    matches(String),
+    /// The value is of the given json type.
+    typeOf(JsonType),
+}
+
+impl Predicate {
+    /// Test the value found for a condition, `None` when the path didn't match anything.
+    pub fn test(&self, value: Option<&Value>) -> Result<bool, TransformationError> {
+        Ok(match self {
+            Predicate::exists(expected) => value.is_some_and(|value| !value.is_null()) == *expected,
+            Predicate::equals(expected) => value == Some(expected),
+            Predicate::matches(pattern) => {
+                let regex = compile_regex(pattern)?;
+                value
+                    .and_then(value_to_string)
+                    .is_some_and(|string| regex.is_match(&string))
+            }
+            Predicate::typeOf(expected) => value.is_some_and(|value| JsonType::of(value) == *expected),
+        })
+    }
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum JsonType {
+    null,
+    boolean,
+    number,
+    string,
+    array,
+    object,
+}
+
+impl JsonType {
+    pub fn of(value: &Value) -> Self {
+        match value {
+            Value::Null => JsonType::null,
+            Value::Bool(_) => JsonType::boolean,
+            Value::Number(_) => JsonType::number,
+            Value::String(_) => JsonType::string,
+            Value::Array(_) => JsonType::array,
+            Value::Object(_) => JsonType::object,
+        }
+    }
+}
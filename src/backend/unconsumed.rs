@@ -0,0 +1,103 @@
+use jsonpath_rust::{path::config::JsonPathConfig, JsonPathInst, JsonPtr};
+use serde::Serialize;
+use serde_json::Value;
+use std::{collections::HashSet, str::FromStr};
+
+use crate::backend::jsonpointer::{escape_token, normalise_json_path};
+
+/// A part of the input credential that no mapping entry used, so its information isn't in the converted credential.
+#[derive(Debug, Clone, Serialize)]
+pub struct UnconsumedField {
+    /// The JsonPointer of the field in the input credential.
+    pub pointer: String,
+    /// The value of the field, including all nested fields when none of them was used.
+    pub value: Value,
+}
+
+/// The fields of `input` not read through any of the JsonPaths `consumed_paths`.
+/// Reading a field consumes everything nested in it. The unconsumed leaf nodes are grouped by subtree: a field none of
+/// whose nested fields was consumed is reported once with its whole value, instead of once for every leaf node.
+/// Empty objects and arrays hold no information and are only reported as part of such a subtree.
+pub fn unconsumed_fields<'a>(
+    input: &Value,
+    consumed_paths: impl IntoIterator<Item = &'a String>,
+) -> Vec<UnconsumedField> {
+    // The consumed values are identified by their address, so the JsonPaths don't need to be converted to pointers
+    let mut consumed = HashSet::new();
+    for path in consumed_paths {
+        let Ok(json_path) = JsonPathInst::from_str(&normalise_json_path(path)) else {
+            continue;
+        };
+        for found in json_path.find_slice(input, JsonPathConfig::default()) {
+            if let JsonPtr::Slice(value) = found {
+                consumed.insert(value as *const Value);
+            }
+        }
+    }
+
+    // The root itself is never reported as a whole, only the fields it consists of
+    let mut fields = Vec::new();
+    for (pointer, child) in children(input, "") {
+        fields.extend(collect_unconsumed(child, pointer, &consumed).1);
+    }
+    fields
+}
+
+/// Whether any part of `value` is consumed, with the unconsumed fields of `value`.
+fn collect_unconsumed(
+    value: &Value,
+    pointer: String,
+    consumed: &HashSet<*const Value>,
+) -> (bool, Vec<UnconsumedField>) {
+    if consumed.contains(&(value as *const Value)) {
+        return (true, Vec::new());
+    }
+
+    let children = children(value, &pointer);
+    if children.is_empty() {
+        let fields = match value {
+            Value::Object(_) | Value::Array(_) => Vec::new(),
+            value => vec![UnconsumedField {
+                pointer,
+                value: value.clone(),
+            }],
+        };
+        return (false, fields);
+    }
+
+    let mut used = false;
+    let mut fields = Vec::new();
+    for (child_pointer, child) in children {
+        let (child_used, child_fields) = collect_unconsumed(child, child_pointer, consumed);
+        used |= child_used;
+        fields.extend(child_fields);
+    }
+
+    if used {
+        (true, fields)
+    } else {
+        (
+            false,
+            vec![UnconsumedField {
+                pointer,
+                value: value.clone(),
+            }],
+        )
+    }
+}
+
+/// The fields of an object or the elements of an array, with their JsonPointers.
+fn children<'a>(value: &'a Value, pointer: &str) -> Vec<(String, &'a Value)> {
+    match value {
+        Value::Object(fields) => fields
+            .iter()
+            .map(|(key, field)| (format!("{}/{}", pointer, escape_token(key)), field))
+            .collect(),
+        Value::Array(elements) => elements
+            .iter()
+            .enumerate()
+            .map(|(index, element)| (format!("{}/{}", pointer, index), element))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
@@ -8,6 +8,7 @@ use crate::{
 use super::{
     candidate_value::set_output_pointer,
     getters_resolvers::{get_optional_fields, get_required_fields, resolve_logic_construct, resolve_ref},
+    jsonpointer::resolve_pointer,
 };
 
 #[allow(clippy::collapsible_else_if)]
@@ -122,13 +123,11 @@ pub fn update_display_section(state: &mut AppState, preload_p3: bool) {
         if tmp_map.is_empty() {
             // this should actually also check that type != object, if it is an object then it might be the case where an object (key) is required but all fields within the object are optional
             tmp_map.clone_from(subset.get_mut(key).unwrap().as_object().unwrap()); // todo remove unwrap
-            if let Some(value) = state
-                .repository
-                .get(&state.mapping.output_format())
-                .unwrap()
-                .pointer(&state.output_pointer)
-            {
-                tmp_map.insert("Your input >>".to_string(), value.clone());
+            if let Some(value) = resolve_pointer(
+                state.repository.get(&state.mapping.output_format()).unwrap(),
+                &state.output_pointer,
+            ) {
+                tmp_map.insert("Your input >>".to_string(), value);
             } else {
                 tmp_map.insert("Your input >>".to_string(), Value::Null);
             }
@@ -160,13 +159,11 @@ pub fn update_display_section(state: &mut AppState, preload_p3: bool) {
         if tmp_map.is_empty() {
             // this should actually also check that type != object, if it is an object then it might be the edge case where an object (key) is required but all fields within the object are optional
             tmp_map.clone_from(subset.get_mut(key).unwrap().as_object().unwrap()); // todo remove unwrap
-            if let Some(value) = state
-                .repository
-                .get(&state.mapping.output_format())
-                .unwrap()
-                .pointer(&state.output_pointer)
-            {
-                tmp_map.insert("Your input >>".to_string(), value.clone());
+            if let Some(value) = resolve_pointer(
+                state.repository.get(&state.mapping.output_format()).unwrap(),
+                &state.output_pointer,
+            ) {
+                tmp_map.insert("Your input >>".to_string(), value);
             } else {
                 tmp_map.insert("Your input >>".to_string(), Value::Null);
             }
@@ -9,8 +9,9 @@ use crate::backend::init_conversion::get_json;
 use crate::backend::update_display::{update_display_section, update_pointer};
 use crate::{
     backend::{
-        candidate_value::set_candidate_output_value,
-        jsonpointer::{JsonPath, JsonPointer},
+        candidate_value::{parse_regex_input, set_candidate_output_value},
+        id_generation::{assign_ids, IdStrategy},
+        jsonpointer::{resolve_pointer, JsonPath, JsonPointer},
         repository::update_repository,
         transformations::Transformation,
     },
@@ -45,8 +46,17 @@ pub fn handle_backspace(state: &mut AppState) {
             state.selected_transformation -= 1;
         }
     }
-    // Delete a character from the dividers
-    else if state.p2_p3_tabs == P2P3Tabs::MappingOptions && state.mapping_option == MappingOptions::OneToMany {
+    // Delete a character from the regex
+    else if state.p2_p3_tabs == P2P3Tabs::MappingOptions
+        && state.mapping_option == MappingOptions::Transformations
+        && state.transformations == Transformations::Regex
+    {
+        state.regex.pop();
+    }
+    // Delete a character from the dividers, or the separator of ManyToOne
+    else if state.p2_p3_tabs == P2P3Tabs::MappingOptions
+        && (state.mapping_option == MappingOptions::OneToMany || state.mapping_option == MappingOptions::ManyToOne)
+    {
         state.dividers.pop();
     }
 }
@@ -197,7 +207,17 @@ pub fn handle_char(state: &mut AppState, char: char) {
         && char.is_ascii_digit()
     {
         state.transformation_index = Some(char as usize - 48);
-    } else if state.popup_mapping_p2_p3 && state.mapping_option == MappingOptions::OneToMany {
+    } else if state.p2_p3_tabs == P2P3Tabs::MappingOptions
+        && state.mapping_option == MappingOptions::Transformations
+        && !state.select_mapping_option
+        && !state.selected_transformations_tab
+        && state.transformations == Transformations::Regex
+    {
+        state.regex.push(char);
+    } else if (state.mapping_option == MappingOptions::OneToMany || state.mapping_option == MappingOptions::ManyToOne)
+        && !state.select_mapping_option
+        && (state.popup_mapping_p2_p3 || state.p2_p3_tabs == P2P3Tabs::MappingOptions)
+    {
         state.dividers.push(char);
     }
 }
@@ -245,6 +265,10 @@ pub fn handle_enter(state: &mut AppState) -> bool {
                             state.selected_transformations.push(state.transformations);
                             state.transformations = Transformations::LowerCase;
                         }
+                    } else if state.transformations == Transformations::Regex {
+                        if parse_regex_input(&state.regex).is_some() {
+                            state.selected_transformations.push(state.transformations);
+                        }
                     } else {
                         state.selected_transformations.push(state.transformations);
                     }
@@ -271,8 +295,26 @@ pub fn handle_enter(state: &mut AppState) -> bool {
                 }
             }
             _ => {
+                // Select or deselect an output field as destination of the split
+                if !state.popup_mapping_p2_p3
+                    && state.p2_p3_tabs == P2P3Tabs::OutputFields
+                    && state.mapping_option == MappingOptions::OneToMany
+                    && !state.select_mapping_option
+                    && state.output_display_subset[1].0 == *"Your input >>"
+                    && state.selected_output_field == 1
+                {
+                    toggle_split_destination(state);
+                }
+                // Select or deselect an input field to be joined
+                else if !state.popup_mapping_p2_p3
+                    && state.p2_p3_tabs == P2P3Tabs::InputFields
+                    && state.mapping_option == MappingOptions::ManyToOne
+                    && !state.select_mapping_option
+                {
+                    toggle_join_source(state);
+                }
                 // Complete a mapping from the view popup
-                if state.popup_mapping_p2_p3 {
+                else if state.popup_mapping_p2_p3 {
                     // "Your input >>" needs to be selected to perform the mapping
                     if state.output_display_subset[1].0 == *"Your input >>" && state.selected_output_field == 1 {
                         confirm_mapping(state);
@@ -422,7 +464,15 @@ pub fn next_page(state: &mut AppState) {
 /// It is recommended not to alter the custom mapping files manually, since this will likely result in an error.
 pub fn create_output_files(state: &mut AppState) {
     let output_format = state.mapping.output_format();
+    state.unconsumed_fields = state.repository.unconsumed_fields(&state.mapping.input_format());
+    state.selected_unconsumed_field = 0;
+    let input_value = state
+        .repository
+        .get(&state.mapping.input_format())
+        .cloned()
+        .unwrap_or_default();
     let json_value = state.repository.get_mut(&output_format).unwrap();
+    assign_ids(json_value, &input_value, IdStrategy::default());
 
     // Create Output File
     trace_dbg!(&state.output_path);
@@ -453,6 +503,10 @@ pub fn create_output_files(state: &mut AppState) {
 
 pub fn clear_mapping_options(state: &mut AppState) {
     state.select_mapping_option = true;
+    state.dividers.clear();
+    state.split_destinations.clear();
+    state.join_sources.clear();
+    state.regex.clear();
     state.selected_transformation = 0;
     state.selected_transformations.clear();
     state.selected_transformations_tab = false;
@@ -527,7 +581,9 @@ pub fn clear_button(state: &mut AppState) {
 
 pub fn confirm_mapping(state: &mut AppState) {
     set_candidate_output_value(state, true);
-    update_repository(state);
+    if let Err(error) = update_repository(state) {
+        trace_dbg!(&error);
+    }
     update_resolved_subset(state);
     save_completed_fields(state);
     move_active_fields(state);
@@ -541,7 +597,9 @@ pub fn confirm_mapping(state: &mut AppState) {
 ///// HELPERS /////
 
 fn update_resolved_subset(state: &mut AppState) {
-    if state.page == Pages::RequiredDataP2 {
+    if state.mapping_option == MappingOptions::OneToMany {
+        update_split_resolved_subsets(state);
+    } else if state.page == Pages::RequiredDataP2 {
         let output_map = state.resolved_subsets.get_mut(&state.required_field_pointer).unwrap();
         *output_map.get_mut("Your input >>").unwrap() = Value::from(state.candidate_output_value.clone());
     } else if state.page == Pages::OptionalDataP3 {
@@ -550,7 +608,88 @@ fn update_resolved_subset(state: &mut AppState) {
     }
 }
 
+/// Show the split values in the "Your input >>" field of every destination that has already been opened.
+fn update_split_resolved_subsets(state: &mut AppState) {
+    let output_format = state.mapping.output_format();
+
+    for (output_field_pointer, output_pointer) in &state.split_destinations {
+        let value = resolve_pointer(state.repository.get(&output_format).unwrap(), output_pointer).unwrap_or_default();
+
+        if let Some(input_value) = state
+            .resolved_subsets
+            .get_mut(output_field_pointer)
+            .and_then(|output_map| output_map.get_mut("Your input >>"))
+        {
+            *input_value = value;
+        }
+    }
+}
+
+fn toggle_split_destination(state: &mut AppState) {
+    let mut output_field_pointer = state.required_field_pointer.clone();
+    if state.page == Pages::OptionalDataP3 {
+        output_field_pointer = state.optional_field_pointer.clone();
+    }
+
+    if let Some(index) = state
+        .split_destinations
+        .iter()
+        .position(|(_, output_pointer)| output_pointer == &state.output_pointer)
+    {
+        state.split_destinations.remove(index);
+    } else {
+        state
+            .split_destinations
+            .push((output_field_pointer, state.output_pointer.clone()));
+    }
+}
+
+fn toggle_join_source(state: &mut AppState) {
+    let input_field_pointer = state.input_fields[state.selected_input_field].0.clone();
+
+    if let Some(index) = state
+        .join_sources
+        .iter()
+        .position(|input_pointer| input_pointer == &input_field_pointer)
+    {
+        state.join_sources.remove(index);
+    } else {
+        state.join_sources.push(input_field_pointer);
+    }
+}
+
 fn save_completed_fields(state: &mut AppState) {
+    if state.mapping_option == MappingOptions::OneToMany {
+        let input_field_pointer = state.input_fields[state.selected_input_field].0.clone();
+        for (output_field_pointer, _) in &state.split_destinations {
+            state
+                .completed_fields
+                .retain(|(first, _)| first != output_field_pointer);
+            state
+                .completed_fields
+                .push((output_field_pointer.clone(), input_field_pointer.clone()));
+        }
+        trace_dbg!(&state.completed_fields);
+        return;
+    }
+    if state.mapping_option == MappingOptions::ManyToOne {
+        let mut output_field_pointer = state.required_field_pointer.clone();
+        if state.page == Pages::OptionalDataP3 {
+            output_field_pointer = state.optional_field_pointer.clone();
+        }
+
+        state
+            .completed_fields
+            .retain(|(first, _)| first != &output_field_pointer);
+        for input_field_pointer in &state.join_sources {
+            state
+                .completed_fields
+                .push((output_field_pointer.clone(), input_field_pointer.clone()));
+        }
+        trace_dbg!(&state.completed_fields);
+        return;
+    }
+
     let mut output_field_pointer = &state.required_field_pointer;
     if state.page == Pages::OptionalDataP3 {
         output_field_pointer = &state.optional_field_pointer;
@@ -599,9 +738,11 @@ fn move_active_fields(state: &mut AppState) {
 fn clear_performed_transformation(state: &mut AppState) {
     let output_path: JsonPath = JsonPointer(state.output_pointer.clone()).into();
 
-    state.performed_mappings.retain(|transformation| match transformation {
-        Transformation::OneToOne { destination, .. } => destination.path != output_path.to_string(),
-        // other Transformations are not implemented yet
-        _ => false,
+    // A split writing to the field is removed as a whole, with its other destinations
+    state.performed_mappings.retain(|transformation| {
+        transformation
+            .destinations()
+            .iter()
+            .all(|destination| destination.path != output_path.to_string())
     });
 }
@@ -5,18 +5,38 @@ use super::is_mouse_over_area;
 
 pub fn p4_handler(event: Event, state: &mut AppState) -> Result<bool, std::io::Error> {
     if let event::Event::Key(key) = event {
-        if key.kind == KeyEventKind::Press && key.code == Esc {
-            return Ok(true);
+        if key.kind == KeyEventKind::Press {
+            match key.code {
+                Esc => return Ok(true),
+                Up => scroll_unconsumed_fields_up(state),
+                Down => scroll_unconsumed_fields_down(state),
+                _ => {}
+            }
         }
     }
     if let event::Event::Mouse(mouse_event) = event {
-        if let event::MouseEventKind::Up(_) = mouse_event.kind {
-            if is_mouse_over_area(state.complete_button, mouse_event.column, mouse_event.row) {
-                return Ok(true);
-            } else if is_mouse_over_area(state.prev_page_button, mouse_event.column, mouse_event.row) {
-                state.page.prev();
+        match mouse_event.kind {
+            event::MouseEventKind::ScrollUp => scroll_unconsumed_fields_up(state),
+            event::MouseEventKind::ScrollDown => scroll_unconsumed_fields_down(state),
+            event::MouseEventKind::Up(_) => {
+                if is_mouse_over_area(state.complete_button, mouse_event.column, mouse_event.row) {
+                    return Ok(true);
+                } else if is_mouse_over_area(state.prev_page_button, mouse_event.column, mouse_event.row) {
+                    state.page.prev();
+                }
             }
+            _ => {}
         }
     }
     Ok(false)
 }
+
+fn scroll_unconsumed_fields_up(state: &mut AppState) {
+    state.selected_unconsumed_field = state.selected_unconsumed_field.saturating_sub(1);
+}
+
+fn scroll_unconsumed_fields_down(state: &mut AppState) {
+    if state.selected_unconsumed_field + 1 < state.unconsumed_fields.len() {
+        state.selected_unconsumed_field += 1;
+    }
+}
@@ -0,0 +1,9 @@
+pub mod backend;
+pub mod events;
+pub mod render;
+pub mod state;
+
+// Load I18n macro, for allow you use `t!` macro in anywhere.
+#[macro_use]
+extern crate rust_i18n;
+i18n!("src/locales", fallback = "en");
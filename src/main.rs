@@ -1,14 +1,12 @@
-mod backend;
-mod events;
-mod render;
-mod state;
-
-use backend::headless_cli::run_headless;
-use backend::headless_cli::Args;
-use backend::logging::initialize_logging;
-use events::*;
-use render::*;
-use state::AppState;
+use credential_converter::backend::formats::FormatRegistry;
+use credential_converter::backend::headless_cli::run_headless;
+use credential_converter::backend::headless_cli::Args;
+use credential_converter::backend::logging::initialize_logging;
+use credential_converter::backend::web::api_service;
+use credential_converter::events::events_handler;
+use credential_converter::render::render_page;
+use credential_converter::state::AppState;
+use credential_converter::trace_dbg;
 
 use clap::Parser;
 use crossterm::event::DisableMouseCapture;
@@ -19,41 +17,47 @@ use ratatui::prelude::{CrosstermBackend, Terminal};
 use std::io::{stdout, Result};
 use std::net::SocketAddr;
 
-// Load I18n macro, for allow you use `t!` macro in anywhere.
-#[macro_use]
-extern crate rust_i18n;
-i18n!("src/locales", fallback = "en");
-
 fn main() -> Result<()> {
     initialize_logging().expect("Unexpected error while initializing logging");
     trace_dbg!("Starting the application");
 
-    let mut state = AppState::default();
-
     // if arguments are passed we are working headless
     // two options:
     // 1. headless CLI (with arguments -o -i -m)
     // 2. headless webservice (with argument -w)
     if std::env::args().len() > 1 {
         trace_dbg!("Arguments detected, running headless conversion");
-        let mut cli_args = Args::parse();
+        let cli_args = Args::parse();
         println!("Arguments detected, running headless conversion");
         let args: Vec<String> = std::env::args().collect();
         if args.contains(&"-w".to_string()) {
             println!("Let's run the webservice!!!");
             if args[args.len() - 1].parse::<SocketAddr>().is_ok() {
                 println!("Let's use {}", args[2].clone());
-                crate::backend::web::api_service(Some(args[args.len() - 1].clone()));
+                api_service(Some(args[args.len() - 1].clone()));
             } else {
                 println!("The webservice runs default 127.0.0.1:3000");
-                crate::backend::web::api_service(None);
+                api_service(None);
             }
         } else {
-            run_headless(&mut cli_args, &mut state)?;
+            if let Err(error) = run_headless(&cli_args) {
+                trace_dbg!(&error);
+                eprintln!("Conversion failed: {}", error);
+                std::process::exit(error.exit_code());
+            }
         }
     } else {
         trace_dbg!("No arguments detected, starting the TUI");
 
+        // The terminal interface lists the conversions between the registered formats, it can't run without them
+        if let Err(error) = FormatRegistry::global() {
+            trace_dbg!(&error);
+            eprintln!("Couldn't start: {}", error);
+            std::process::exit(error.exit_code());
+        }
+
+        let mut state = AppState::default();
+
         // Default example values, remove if no longer needed
         state.input_path =
             "json/ebsi-elm/vcdm2.0-europass-edc-schema/examples/Bengales_highSchoolDiploma.json".to_string();
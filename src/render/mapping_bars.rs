@@ -1,4 +1,7 @@
-use crate::state::{translate, AppState, MappingOptions, P2P3Tabs, Transformations};
+use crate::{
+    backend::candidate_value::parse_regex_input,
+    state::{translate, AppState, MappingOptions, P2P3Tabs, Transformations},
+};
 use ratatui::{
     buffer::Buffer,
     layout::{Constraint, Rect},
@@ -52,7 +55,8 @@ pub fn render_transformations_bar(area: Rect, buf: &mut Buffer, state: &mut AppS
         translate("uppercase").to_string(),
         translate("takeindex").to_string(),
         translate("slice").to_string(),
-        "Regex".to_string(),
+        translate("regex").to_string(),
+        translate("datetime").to_string(),
     ];
     let clear_len = format!(" {} ", translate("clear")).chars().count() as u16;
     let view_len = format!(" {} ", translate("view")).chars().count() as u16;
@@ -100,6 +104,16 @@ pub fn render_transformations_bar(area: Rect, buf: &mut Buffer, state: &mut AppS
                 .style(Style::new().fg(Color::Red).bg(Color::DarkGray))
                 .render(selected, buf);
         }
+    } else if state.transformations == Transformations::Regex && !state.selected_transformations_tab {
+        let txt = format!(" {} {}", translate("enter_regex"), state.regex);
+        let color = if parse_regex_input(&state.regex).is_some() {
+            Color::Green
+        } else {
+            Color::Red
+        };
+        Paragraph::new(txt)
+            .style(Style::new().fg(color).bg(Color::DarkGray))
+            .render(selected, buf);
     } else {
         let selected_transformations: Vec<String> = state
             .selected_transformations
@@ -145,7 +159,7 @@ pub fn render_onetomany_bar(area: Rect, buf: &mut Buffer, state: &mut AppState)
         .render(txt_area, buf);
 
     // Display italic instructions to be overwritten by user input for dividers.
-    if state.dividers.is_empty() {
+    if state.dividers.is_empty() && state.split_destinations.is_empty() {
         let txt = format!("{} ", translate("select_output"));
         Paragraph::new(txt)
             .style(
@@ -156,7 +170,13 @@ pub fn render_onetomany_bar(area: Rect, buf: &mut Buffer, state: &mut AppState)
             )
             .render(dividers, buf);
     } else {
-        Paragraph::new(" ".to_owned() + state.dividers.as_str())
+        // Show the selected output fields behind the dividers, in the order they will be filled
+        let destinations: Vec<&str> = state
+            .split_destinations
+            .iter()
+            .map(|(_, output_pointer)| output_pointer.as_str())
+            .collect();
+        Paragraph::new(format!(" {}  >>  {}", state.dividers, destinations.join(", ")))
             .style(Style::default().fg(Color::Black).bg(Color::Gray))
             .render(dividers, buf);
     }
@@ -170,8 +190,9 @@ pub fn render_manytoone_bar(area: Rect, buf: &mut Buffer, state: &mut AppState)
     let clear_len = format!(" {} ", translate("clear")).chars().count() as u16;
     let view_len = format!(" {} ", translate("view")).chars().count() as u16;
 
-    let [txt_area, clear, view] = Layout::horizontal(vec![
-        Constraint::Min(txt.len() as u16 + 2),
+    let [txt_area, separator, clear, view] = Layout::horizontal(vec![
+        Constraint::Min(txt.chars().count() as u16),
+        Constraint::Percentage(100),
         Constraint::Length(clear_len),
         Constraint::Length(view_len),
     ])
@@ -186,6 +207,11 @@ pub fn render_manytoone_bar(area: Rect, buf: &mut Buffer, state: &mut AppState)
         )
         .render(txt_area, buf);
 
+    // Show the separator or template followed by the selected input fields, in the order they will be joined
+    Paragraph::new(format!(" {}  <<  {}", state.dividers, state.join_sources.join(", ")))
+        .style(Style::default().fg(Color::Black).bg(Color::Gray))
+        .render(separator, buf);
+
     render_mapping_bar_buttons(clear, view, state, buf);
 }
 
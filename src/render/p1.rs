@@ -1,6 +1,6 @@
 use crate::{
-    popups::{render_popup_exit_warning, render_popup_overwrite_warning},
-    state::{translate, AppState, P1Prompts},
+    render::popups::{render_popup_exit_warning, render_popup_overwrite_warning},
+    state::{translate, AppState, Mapping, P1Prompts},
 };
 use ratatui::{
     buffer::Buffer,
@@ -134,7 +134,27 @@ pub fn render_description_input_p1(area: Rect, buf: &mut Buffer, state: &mut App
         horizontal: 0,
     });
 
-    let tabs = vec![" OBv3 -> ELM ", " ELM -> OBv3 "];
+    // One tab for every registered conversion, scrolled to the selected one when they don't all fit
+    let mappings = Mapping::all().unwrap_or_default();
+    let selected = mappings
+        .iter()
+        .position(|mapping| *mapping == state.mapping)
+        .unwrap_or_default();
+    let mut tabs: Vec<String> = mappings
+        .iter()
+        .map(|mapping| format!(" {} -> {} ", mapping.input.name, mapping.output.name))
+        .collect();
+    let max_width = mapping_prompt_inner.width.saturating_sub(2) as usize;
+    let mut first = 0;
+    while first < selected && tabs[first..=selected].concat().len() > max_width {
+        first += 1;
+    }
+    let mut last = selected + 1;
+    while last < tabs.len() && tabs[first..=last].concat().len() <= max_width {
+        last += 1;
+    }
+    tabs = tabs[first..last].to_vec();
+
     let [_left, tabs_center, _right] = Layout::horizontal(vec![
         Constraint::Min(1),
         Constraint::Max(tabs.concat().len() as u16 + 2),
@@ -145,7 +165,7 @@ pub fn render_description_input_p1(area: Rect, buf: &mut Buffer, state: &mut App
     Tabs::new(tabs)
         .style(Style::default().fg(Color::White))
         .highlight_style(Color::Yellow)
-        .select(state.mapping as usize)
+        .select(selected - first)
         .divider("")
         .render(tabs_center, buf);
 
@@ -1,7 +1,7 @@
 use crate::{
-    backend::getters_resolvers::value_to_str,
-    mapping_bars::{render_manytoone_bar, render_mapping_bar},
-    popups::{render_popup_exit_warning, render_popup_mapping, render_popup_uncompleted_warning_p2},
+    backend::{getters_resolvers::value_to_str, jsonpointer::resolve_pointer},
+    render::mapping_bars::render_mapping_bar,
+    render::popups::{render_popup_exit_warning, render_popup_mapping, render_popup_uncompleted_warning_p2},
     state::{translate, AppState, MappingOptions, P2P3Tabs},
 };
 
@@ -82,6 +82,10 @@ pub fn render_required_data_p2(area: Rect, buf: &mut Buffer, state: &mut AppStat
                 {
                     row = row.style(Style::default().fg(Color::Green));
                 }
+                // Input fields selected to be joined by a ManyToOne mapping
+                if state.join_sources.contains(key) {
+                    row = row.style(Style::default().fg(Color::Cyan));
+                }
                 row
             })
             .collect();
@@ -127,10 +131,8 @@ pub fn render_required_data_p2(area: Rect, buf: &mut Buffer, state: &mut AppStat
             .iter()
             .map(|(key, value)| {
                 let mut row = Row::new(vec![key.deref(), value.deref()]);
-                if output_repository
-                    .pointer(&(state.output_pointer.clone() + "/" + key))
-                    .is_some()
-                    || (key == "Your input >>" && output_repository.pointer(&state.output_pointer).is_some())
+                if resolve_pointer(output_repository, &(state.output_pointer.clone() + "/" + key)).is_some()
+                    || (key == "Your input >>" && resolve_pointer(output_repository, &state.output_pointer).is_some())
                 // todo: validfrom can't be seen as green since 'up' scrolls the list up immediately.
                 {
                     row = row.style(Style::default().fg(Color::Green));
@@ -166,7 +168,7 @@ pub fn render_required_data_p2(area: Rect, buf: &mut Buffer, state: &mut AppStat
                     MappingOptions::Transformations => render_popup_mapping(area, buf, state),
                     MappingOptions::OneToMany => render_popup_mapping(area, buf, state), // todo
                     MappingOptions::StringToOne => render_popup_mapping(area, buf, state), // todo
-                    MappingOptions::ManyToOne => render_manytoone_bar(area, buf, state), // todo
+                    MappingOptions::ManyToOne => render_popup_mapping(area, buf, state), // todo
                     MappingOptions::DirectCopy => {}                                     // DirectCopy
                 }
             }
@@ -1,11 +1,25 @@
 use ratatui::{buffer::Buffer, layout::Rect, prelude::*, widgets::*};
 
-use crate::state::{translate, AppState};
+use crate::{
+    backend::getters_resolvers::value_to_str,
+    state::{translate, AppState},
+};
 
 pub fn render_end_p4(area: Rect, buf: &mut Buffer, state: &mut AppState) {
     Block::new().style(Style::default().fg(Color::White)).render(area, buf);
 
-    let vertical_margin = if area.height >= 3 { (area.height - 3) / 2 } else { 0 };
+    // Layout
+    let [completed, unconsumed] =
+        Layout::vertical(vec![Constraint::Percentage(40), Constraint::Min(0)]).areas(area.inner(&Margin {
+            vertical: 1,
+            horizontal: 4,
+        }));
+
+    let vertical_margin = if completed.height >= 3 {
+        (completed.height - 3) / 2
+    } else {
+        0
+    };
 
     let txt = format!(
         "{}: {}\n{}: {}",
@@ -20,10 +34,41 @@ pub fn render_end_p4(area: Rect, buf: &mut Buffer, state: &mut AppState) {
         .alignment(Alignment::Center)
         .wrap(Wrap { trim: true })
         .render(
-            area.inner(&Margin {
+            completed.inner(&Margin {
                 vertical: vertical_margin,
-                horizontal: 4,
+                horizontal: 0,
             }),
             buf,
         );
+
+    // Input fields that weren't used by any mapping, so their information is missing from the output
+    let block = Block::new()
+        .title(format!("  {}  ", translate("unconsumed_fields")))
+        .title_alignment(Alignment::Center)
+        .borders(Borders::ALL);
+
+    if state.unconsumed_fields.is_empty() {
+        Paragraph::new(translate("no_unconsumed_fields"))
+            .centered()
+            .block(block)
+            .render(unconsumed, buf);
+        return;
+    }
+
+    let mut table_state = TableState::default().with_selected(Some(state.selected_unconsumed_field));
+    let rows: Vec<Row> = state
+        .unconsumed_fields
+        .iter()
+        .map(|field| Row::new(vec![field.pointer.clone(), value_to_str(&field.value)]))
+        .collect();
+
+    StatefulWidget::render(
+        Table::new(rows, [Constraint::Percentage(50), Constraint::Percentage(50)])
+            .block(block)
+            .header(Row::new([translate("field"), translate("value")]).style(Style::new()))
+            .highlight_style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
+        unconsumed,
+        buf,
+        &mut table_state,
+    );
 }
@@ -1,10 +1,15 @@
-use clap::ValueEnum;
 use ratatui::layout::Rect;
 use serde_json::Value;
-use std::{borrow::Cow, collections::HashMap};
+use std::{borrow::Cow, collections::HashMap, fmt};
 use strum::{AsRefStr, Display, FromRepr};
 
-use crate::backend::{repository::Repository, transformations::Transformation};
+use crate::backend::{
+    error::ConversionError,
+    formats::{Format, FormatRegistry},
+    repository::Repository,
+    transformations::Transformation,
+    unconsumed::UnconsumedField,
+};
 
 //////////      STRUCTS & ENUMS     //////////
 
@@ -45,6 +50,9 @@ pub struct AppState {
     // Transformation data, this can't be added to the Transformations enum since this will break it's loopability in the UI.
     pub dividers: String,
     pub transformation_index: Option<usize>,
+    pub regex: String, // either a pattern to extract or 'pattern => replacement'
+    pub split_destinations: Vec<(String, String)>, // (output_field_path, output_pointer) selected for OneToMany
+    pub join_sources: Vec<String>, // input field pointers selected for ManyToOne
 
     // Fields extracted from the input json file.
     pub input_fields: Vec<(String, String)>,
@@ -71,6 +79,10 @@ pub struct AppState {
     pub repository: Repository,
     pub performed_mappings: Vec<Transformation>,
 
+    // Input fields not used by any mapping, shown on P4
+    pub unconsumed_fields: Vec<UnconsumedField>,
+    pub selected_unconsumed_field: usize,
+
     // Total Area
     pub area: Rect,
 
@@ -150,28 +162,115 @@ pub enum P1Prompts {
     CustomMapping,
 }
 
-#[derive(Clone, Copy, FromRepr, Debug, Default, PartialEq, ValueEnum, Display)]
-pub enum Mapping {
-    #[clap(name = "OBv3toELM")]
-    OBv3ToELM = 0,
-    #[default]
-    #[clap(name = "ELMtoOBv3")]
-    ELMToOBv3,
+/// The direction of a conversion, between two of the formats registered in the `FormatRegistry`.
+#[derive(Clone, Copy)]
+pub struct Mapping {
+    /// The registry of the formats, which also holds the formats of the credentials embedded in a credential.
+    pub registry: &'static FormatRegistry,
+    pub input: &'static Format,
+    pub output: &'static Format,
 }
 
 impl Mapping {
+    /// The conversion between the formats with the given names in the global registry, see `FormatRegistry::mapping`
+    /// for the formats of another registry.
+    ///
+    /// # Returns
+    /// - `Ok(Mapping)`: The conversion from the `from` format into the `to` format.
+    /// - `Err(ConversionError)`: If a format isn't registered, the formats are the same or the registry can't be read.
+    pub fn find(from: &str, to: &str) -> Result<Mapping, ConversionError> {
+        FormatRegistry::global()?.mapping(from, to)
+    }
+
+    /// The conversion named like `OBv3ToELM`, ignoring case.
+    pub fn from_name(name: &str) -> Result<Option<Mapping>, ConversionError> {
+        Ok(Mapping::all()?
+            .into_iter()
+            .find(|mapping| mapping.to_string().eq_ignore_ascii_case(name)))
+    }
+
+    /// The conversions between every two formats of the global registry.
+    pub fn all() -> Result<Vec<Mapping>, ConversionError> {
+        Ok(FormatRegistry::global()?.mappings())
+    }
+
     pub fn input_format(&self) -> String {
-        match self {
-            Mapping::OBv3ToELM => "OBv3".to_string(),
-            Mapping::ELMToOBv3 => "ELM".to_string(),
-        }
+        self.input.name.clone()
     }
 
     pub fn output_format(&self) -> String {
-        match self {
-            Mapping::OBv3ToELM => "ELM".to_string(),
-            Mapping::ELMToOBv3 => "OBv3".to_string(),
+        self.output.name.clone()
+    }
+
+    /// The path of the json schema of the output format.
+    pub fn output_schema(&self) -> &'static str {
+        &self.output.schema
+    }
+
+    /// The format whose json schema the input is coerced into before it's mapped, see `normalise_input`.
+    /// Only formats allowing multiple shapes for the same field declare it, e.g. Open Badges 2.0 with a single
+    /// `evidence` or an array of them.
+    pub fn normalised_input(&self) -> Option<&'static Format> {
+        self.input.normalise_input.then_some(self.input)
+    }
+
+    /// The path of the default mapping file of the conversion, if the input format has one into the output format.
+    pub fn default_mapping(&self) -> Option<&'static str> {
+        self.input.mappings.get(&self.output.name).map(String::as_str)
+    }
+
+    /// Select the next conversion in the terminal interface, which only starts once the formats are loaded.
+    pub fn next(&mut self) {
+        let all = Mapping::all().unwrap_or_default();
+        if all.is_empty() {
+            return;
+        }
+        let index = all.iter().position(|mapping| mapping == self).unwrap_or_default();
+        *self = all[(index + 1) % all.len()];
+    }
+
+    pub fn prev(&mut self) {
+        let all = Mapping::all().unwrap_or_default();
+        if all.is_empty() {
+            return;
         }
+        let index = all.iter().position(|mapping| mapping == self).unwrap_or_default();
+        *self = all[(index + all.len() - 1) % all.len()];
+    }
+}
+
+/// The conversion of the first format declaring a default output into it, e.g. `ELMToOBv3`, or else the first
+/// conversion that has a default mapping. Only used by the terminal interface, which only starts once the formats
+/// are loaded.
+impl Default for Mapping {
+    fn default() -> Self {
+        let all = Mapping::all().unwrap_or_default();
+
+        all.iter()
+            .find(|mapping| mapping.input.default_output.as_ref() == Some(&mapping.output.name))
+            .or_else(|| all.iter().find(|mapping| mapping.default_mapping().is_some()))
+            .or(all.first())
+            .copied()
+            .expect("error: the format registry needs at least two formats")
+    }
+}
+
+/// Conversions are the same when they're between the same formats.
+impl PartialEq for Mapping {
+    fn eq(&self, other: &Self) -> bool {
+        self.input == other.input && self.output == other.output
+    }
+}
+
+impl fmt::Display for Mapping {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}To{}", self.input.name, self.output.name)
+    }
+}
+
+impl fmt::Debug for Mapping {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Mapping({})", self)
     }
 }
 
@@ -193,6 +292,7 @@ pub enum Transformations {
     TakeIndex, // should be included in Slice
     Slice,     // Slice should accept indexing singular points and ranges in the future, e.g. (9) or (1..9)
     Regex,
+    DateTime,
     StringToOne,
 }
 
@@ -263,9 +363,8 @@ macro_rules! next_prev {
 // }
 
 next_prev!(Languages, Languages::EN, Languages::SV);
-next_prev!(Mapping, Mapping::OBv3ToELM, Mapping::ELMToOBv3);
 next_prev!(Pages, Pages::InputPromptsP1, Pages::EndP4);
 next_prev!(P1Prompts, P1Prompts::Language, P1Prompts::CustomMapping);
-next_prev!(Transformations, Transformations::LowerCase, Transformations::Regex);
+next_prev!(Transformations, Transformations::LowerCase, Transformations::DateTime);
 next_prev!(MappingOptions, MappingOptions::DirectCopy, MappingOptions::ManyToOne);
 next_prev!(P2P3Tabs, P2P3Tabs::InputFields, P2P3Tabs::View);